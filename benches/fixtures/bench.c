@@ -0,0 +1,79 @@
+/* Benchmark fixture: gcc -O1 -g -no-pie -o bench bench.c && obj2yaml bench > bench.obj.yaml */
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#define ARITHMETIC(n)                                                          \
+    int arithmetic_##n(int a, int b) {                                         \
+        int r = a * (n + 1) + b;                                               \
+        for (int i = 0; i < b; i++)                                            \
+            r ^= (r << 3) + i * n;                                             \
+        return r;                                                              \
+    }
+
+#define DISPATCH(n)                                                            \
+    int dispatch_##n(int op, int x) {                                          \
+        switch (op) {                                                          \
+        case 0: return x + n;                                                  \
+        case 1: return x - n;                                                  \
+        case 2: return x * n;                                                  \
+        case 3: return x / (n + 1);                                            \
+        case 4: return x % (n + 1);                                            \
+        case 5: return x << (n % 7);                                           \
+        case 6: return x >> (n % 7);                                           \
+        case 7: return ~x;                                                     \
+        default: return 0;                                                     \
+        }                                                                      \
+    }
+
+ARITHMETIC(0) ARITHMETIC(1) ARITHMETIC(2) ARITHMETIC(3) ARITHMETIC(4)
+ARITHMETIC(5) ARITHMETIC(6) ARITHMETIC(7) ARITHMETIC(8) ARITHMETIC(9)
+ARITHMETIC(10) ARITHMETIC(11) ARITHMETIC(12) ARITHMETIC(13) ARITHMETIC(14)
+ARITHMETIC(15) ARITHMETIC(16) ARITHMETIC(17) ARITHMETIC(18) ARITHMETIC(19)
+
+DISPATCH(0) DISPATCH(1) DISPATCH(2) DISPATCH(3) DISPATCH(4)
+DISPATCH(5) DISPATCH(6) DISPATCH(7) DISPATCH(8) DISPATCH(9)
+DISPATCH(10) DISPATCH(11) DISPATCH(12) DISPATCH(13) DISPATCH(14)
+DISPATCH(15) DISPATCH(16) DISPATCH(17) DISPATCH(18) DISPATCH(19)
+
+struct node {
+    struct node *next;
+    char name[16];
+    int value;
+};
+
+struct node *push(struct node *head, const char *name, int value) {
+    struct node *node = malloc(sizeof(*node));
+    strncpy(node->name, name, sizeof(node->name) - 1);
+    node->name[sizeof(node->name) - 1] = 0;
+    node->value = value;
+    node->next = head;
+    return node;
+}
+
+int sum(const struct node *head) {
+    int total = 0;
+    for (; head; head = head->next)
+        total += head->value;
+    return total;
+}
+
+void release(struct node *head) {
+    while (head) {
+        struct node *next = head->next;
+        free(head);
+        head = next;
+    }
+}
+
+int main(int argc, char **argv) {
+    struct node *list = NULL;
+    int op = argc > 1 ? atoi(argv[1]) : 0;
+
+    list = push(list, "arithmetic", arithmetic_0(argc, op) + arithmetic_19(op, argc));
+    list = push(list, "dispatch", dispatch_0(op, argc) + dispatch_19(op, argc));
+
+    printf("%d\n", sum(list));
+    release(list);
+    return 0;
+}
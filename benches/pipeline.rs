@@ -0,0 +1,66 @@
+//! Baselines for the YAML parser, the disassembly and the complete ELF pipeline.
+//!
+//! The fixture (`benches/fixtures/bench`) is built from `bench.c`, see the comment at its top.
+
+use std::env;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use binary2groundtruth::b2g;
+use binary2groundtruth::config;
+use binary2groundtruth::disassembler;
+use binary2groundtruth::elf;
+use binary2groundtruth::groundtruth;
+use binary2groundtruth::parser;
+
+const BINARY: &[u8] = include_bytes!("fixtures/bench");
+const DUMP: &str = include_str!("fixtures/bench.obj.yaml");
+
+fn parse(c: &mut Criterion) {
+    c.bench_function("parse elf yaml", |b| {
+        b.iter(|| parser::yaml::elf::load_elf_from_str(DUMP).unwrap())
+    });
+}
+
+fn disassemble(c: &mut Criterion) {
+    let text = elf::parse_sections_from_buffer(BINARY)
+        .unwrap()
+        .into_iter()
+        .find(|s| s.name == ".text")
+        .unwrap();
+    let start = text.raw_data_offset as usize;
+    let buffer = BINARY[start..start + text.raw_data_size as usize].to_vec();
+    let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+
+    c.bench_function("disassemble text section", |b| {
+        b.iter(|| {
+            disassembler::disassemble(
+                buffer.clone(),
+                &architecture,
+                disassembler::DISASSEMBLER::CAPSTONE,
+            )
+            .unwrap()
+        })
+    });
+}
+
+fn pipeline(c: &mut Criterion) {
+    // Keep the outputs of the pipeline out of the working directory
+    let file_name = env::temp_dir()
+        .join("binary2groundtruth-bench")
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    c.bench_function("elf pipeline", |b| {
+        b.iter(|| {
+            let mut elf =
+                b2g::elf::ELF::from_buffer(DUMP, BINARY, config::Config::default()).unwrap();
+            elf.file_name = file_name.clone();
+            elf.process();
+        })
+    });
+}
+
+criterion_group!(benches, parse, disassemble, pipeline);
+criterion_main!(benches);
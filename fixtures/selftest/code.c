@@ -0,0 +1,11 @@
+/* Self-test fixture: gcc -O1 -g0 -no-pie -o code code.c && objcopy --rename-section .text=.code code && obj2yaml code > code.obj.yaml */
+int square(int x) { return x * x; }
+
+int sum(int n) {
+    int s = 0;
+    for (int i = 0; i < n; i++)
+        s += square(i);
+    return s;
+}
+
+int main(int argc, char **argv) { return sum(argc); }
@@ -0,0 +1,12 @@
+/* Self-test fixture: gcc -O1 -g0 -no-pie -o data data.c && obj2yaml data > data.obj.yaml */
+int counter = 5;
+char greeting[] = "hello groundtruth";
+long limits[2] = {-1, 1};
+
+int bump(int x) {
+    counter += x;
+    limits[x & 1] = counter;
+    return greeting[counter & 15];
+}
+
+int main(int argc, char **argv) { return bump(argc); }
@@ -0,0 +1,8 @@
+/* Self-test fixture: gcc -O1 -g -no-pie -o elf elf.c && obj2yaml elf > elf.obj.yaml */
+static int table[4] = {3, 7, 9, 11};
+
+int lookup(int x) { return x >= 0 && x < 4 ? table[x] : 0; }
+
+int add(int a, int b) { return a + b; }
+
+int main(int argc, char **argv) { return add(argc, lookup(argc)); }
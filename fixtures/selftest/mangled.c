@@ -0,0 +1,19 @@
+/* Self-test fixture: gcc -O1 -g0 -no-pie -o mangled mangled.c && obj2yaml mangled > mangled.obj.yaml */
+int itanium(int x) __asm__("_ZN3foo3barEi");
+int itanium(int x) { return x + 1; }
+
+int rust_legacy(int x) __asm__("_ZN4core3fmt5write17h0123456789abcdefE");
+int rust_legacy(int x) { return x * 3; }
+
+int rust_v0(int x) __asm__("_RNvCs15kBYyAo9fc_7mycrate7example");
+int rust_v0(int x) { return x - 2; }
+
+int msvc_method(int x) __asm__("\"?bar@Foo@@QEAAHH@Z\"");
+int msvc_method(int x) { return x ^ 5; }
+
+int msvc_constructor(int x) __asm__("\"??0Foo@@QEAA@XZ\"");
+int msvc_constructor(int x) { return x | 8; }
+
+int main(int argc, char **argv) {
+    return itanium(argc) + rust_legacy(argc) + rust_v0(argc) + msvc_method(argc) + msvc_constructor(argc);
+}
@@ -0,0 +1,29 @@
+# Self-test fixture (MIPS32 big-endian): llvm-mc -triple=mips-linux-gnu -mcpu=mips32r2 -filetype=obj -o mips.o mips.s,
+# the .text content of mips.o (jal relocated to 0x400000) is linked by yaml2obj mips.elf.yaml -o mips
+# && obj2yaml mips > mips.obj.yaml
+        .set noreorder
+        .text
+        .globl square
+square:
+        mul $v0, $a0, $a0
+        jr $ra
+        nop
+        .globl sum
+sum:
+        addiu $sp, $sp, -24
+        sw $ra, 20($sp)
+        sw $s0, 16($sp)
+        move $s0, $a0
+        jal square
+        nop
+        addu $v0, $v0, $s0
+        lw $s0, 16($sp)
+        lw $ra, 20($sp)
+        beq $v0, $zero, 1f
+        nop
+        addiu $v0, $v0, 1
+1:
+        jr $ra
+        addiu $sp, $sp, 24
+        nop
+        nop
@@ -0,0 +1,11 @@
+/* Self-test fixture: gcc -O1 -g0 -no-pie -o names names.c && obj2yaml names > names.obj.yaml */
+int greet(int x) __asm__("Gr\xc3\xbc\xc3\x9f""e_\xce\xbb");
+int greet(int x) { return x + 1; }
+
+int number(int x) __asm__("\"1234\"");
+int number(int x) { return x * 3; }
+
+int raw(int x) __asm__("\"raw \xff\xfe\"");
+int raw(int x) { return x - 2; }
+
+int main(int argc, char **argv) { return greet(argc) + number(argc) + raw(argc); }
@@ -0,0 +1,21 @@
+/* Self-test fixture: gcc -O1 -g -no-pie -o noreturn noreturn.c && obj2yaml noreturn > noreturn.obj.yaml */
+#include <stdlib.h>
+
+__attribute__((noreturn)) void breakpoint(void) {
+    __asm__ volatile("int3");
+    __builtin_unreachable();
+}
+
+__attribute__((noreturn)) void fail(int code) { exit(code + 1); }
+
+int check(int x) {
+    if (x > 8)
+        breakpoint();
+    return x * 2;
+}
+
+int main(int argc, char **argv) {
+    if (argc > 4)
+        fail(argc);
+    return check(argc);
+}
@@ -0,0 +1,36 @@
+# Self-test fixture (Thumb-2, little-endian): llvm-mc -triple=thumbv7-linux-gnueabi -filetype=obj -o thumb.o thumb.s,
+# the .text content of thumb.o (bl resolved through the local label) is linked at 0x10000 by
+# yaml2obj thumb.obj.yaml -o thumb. Both functions end with a literal pool loaded by ldr rN, [pc, #imm].
+        .syntax unified
+        .thumb
+        .text
+        .globl answer
+        .type answer, %function
+        .thumb_func
+answer:
+.Lanswer:
+        ldr r0, 1f
+        ldr r1, 2f
+        adds r0, r0, r1
+        bx lr
+        .p2align 2
+1:
+        .word 0x12345678
+2:
+        .word 0x00c0ffee
+        .size answer, .-answer
+        .globl twice
+        .type twice, %function
+        .thumb_func
+twice:
+        push {r4, lr}
+        mov r4, r0
+        bl .Lanswer
+        adds r0, r0, r4
+        ldr r1, 3f
+        adds r0, r0, r1
+        pop {r4, pc}
+        .p2align 2
+3:
+        .word 0xdeadbeef
+        .size twice, .-twice
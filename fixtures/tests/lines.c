@@ -0,0 +1,6 @@
+/* Unit test fixture (partial line information), only this unit carries DWARF:
+ * gcc -O1 -g -c lines.c && gcc -O1 -c nolines.c
+ * && gcc -nostdlib -static -Wl,--build-id=none -e with_lines -o lines lines.o nolines.o */
+int without_lines(int x);
+
+int with_lines(int x) { return without_lines(x) * 3 + 1; }
@@ -0,0 +1,2 @@
+/* Unit test fixture, compiled without -g (see lines.c). */
+int without_lines(int x) { return x ^ 0x5a; }
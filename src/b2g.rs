@@ -1,258 +1,4000 @@
+/// Pipeline stages shared by the PE and ELF pipelines. Function offsets are expected to be relative
+/// to the start of the (trimmed) byte vector.
+pub mod common {
+    use lazy_static::lazy_static;
+    use regex::{Captures, Regex};
+    use std::collections::HashMap;
+    use std::mem;
+    use std::path::Path;
+    use std::process;
+    use std::time::{Duration, Instant};
+    use tracing::{debug, error, info, instrument, warn};
+
+    use crate::config;
+    use crate::demangle;
+    use crate::disassembler;
+    use crate::dumper;
+    use crate::groundtruth;
+    use crate::holes;
+    use crate::parser;
+
+    /// Minimum fraction of decodable bytes for a gap to be classified as code.
+    const GAP_CODE_THRESHOLD: f64 = 0.95;
+
+    /// Amount of bytes the declared and disassembled size of a function may differ.
+    const SIZE_DISCREPANCY_THRESHOLD: u64 = 0x10;
+
+    /// Instructions which (almost) never occur in compiler generated user mode code. Decoding code
+    /// of the wrong architecture produces many of them (e.g. REX prefixes become `inc`/`dec` on
+    /// x86).
+    const ANOMALOUS_MNEMONICS: [&str; 21] = [
+        "aaa", "aad", "aam", "aas", "arpl", "bound", "cli", "daa", "das", "in", "insb", "insd",
+        "into", "lds", "les", "out", "outsb", "outsd", "retf", "sti", "salc",
+    ];
+
+    /// Longest possible x86/x64 instruction in bytes.
+    const MAX_INSTRUCTION_LENGTH: usize = 15;
+
+    /// Longest period of a repeated fill pattern in bytes.
+    const MAX_FILL_PERIOD: usize = 8;
+
+    /// Rough factor between the size of a YAML dump and its parsed document tree.
+    const YAML_MEMORY_FACTOR: u64 = 10;
+
+    /// Alignment of recovered function starts which do not start a hole.
+    const FUNCTION_ALIGNMENT: u64 = 16;
+
+    /// Replaces addresses in normalized operands.
+    const ADDRESS_PLACEHOLDER: &str = "0xADDR";
+
+    /// Smallest operand value which is normalized as an (absolute) address.
+    const MIN_ABSOLUTE_ADDRESS: u64 = 0x10000;
+
+    /// Minimum number of entries with a target within the function for in-line data to be
+    /// considered a jump table.
+    const MIN_JUMP_TABLE_ENTRIES: u64 = 2;
+
+    /// Name of the in-line data of a literal pool, after the ARM mapping symbol of data.
+    const LITERAL_POOL: &str = "$d";
+
+    /// Lists the code sections: every section named `.text` or `.text*` (e.g. `.text$mn`,
+    /// `.text.hot`) which is not excluded, in the order of the section table. Sections without raw
+    /// data (virtual-only sections, e.g. of packed binaries) are skipped since there are no bytes
+    /// to map. Without such a section the largest executable section is listed (e.g. a renamed code
+    /// section).
+    pub fn code_sections(
+        sections: &[groundtruth::Section],
+        exclude: &[String],
+    ) -> Vec<groundtruth::Section> {
+        let (excluded, included): (Vec<&groundtruth::Section>, Vec<&groundtruth::Section>) =
+            sections
+                .iter()
+                .filter(|s| s.name.starts_with(".text"))
+                .partition(|s| exclude.contains(&s.name));
+
+        let (included, empty): (Vec<&groundtruth::Section>, Vec<&groundtruth::Section>) =
+            included.into_iter().partition(|s| s.raw_data_size > 0);
+
+        for section in &empty {
+            warn!(
+                section = %section.name,
+                "Code section has no raw data (virtual size only), skipping it."
+            );
+        }
+
+        info!(
+            included = ?included.iter().map(|s| &s.name).collect::<Vec<&String>>(),
+            excluded = ?excluded.iter().map(|s| &s.name).collect::<Vec<&String>>(),
+            "Code sections selected."
+        );
+
+        // Guard: Fall back to the executable sections if no section is named like code
+        if included.is_empty() {
+            let fallback = sections
+                .iter()
+                .filter(|s| s.flags.contains(&groundtruth::FLAG::EXECUTABLE))
+                .filter(|s| s.raw_data_size > 0 && !exclude.contains(&s.name))
+                .max_by_key(|s| s.raw_data_size);
+
+            if let Some(section) = fallback {
+                warn!(
+                    section = %section.name,
+                    "No .text section found, falling back to the largest executable section."
+                );
+            }
+
+            return fallback.into_iter().cloned().collect();
+        }
+
+        included.into_iter().cloned().collect()
+    }
+
+    /// Selects the code section to process. Only a single section is processed: the first of
+    /// [`code_sections`], the others are reported.
+    pub fn select_code_section(
+        sections: &[groundtruth::Section],
+        exclude: &[String],
+    ) -> Option<groundtruth::Section> {
+        let sections = code_sections(sections, exclude);
+
+        if sections.len() > 1 {
+            warn!(
+                section = %sections[0].name,
+                skipped = ?sections[1..].iter().map(|s| &s.name).collect::<Vec<&String>>(),
+                "Multiple code sections found, only the first one is processed."
+            );
+        }
+
+        sections.into_iter().next()
+    }
+
+    /// Name of the outputs: the file name of the binary without directories and extension. Names
+    /// which are not valid UTF-8 keep their bytes where possible, invalid sequences are replaced.
+    pub fn file_stem(path: &Path) -> String {
+        path.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "binary".to_string())
+    }
+
+    /// Loads the function list given with `--functions-from`. Function offsets are addresses.
+    pub fn load_function_list(config: &config::Config) -> Vec<groundtruth::Function> {
+        let path = match &config.functions_from {
+            Some(path) => path,
+            None => return Vec::new(),
+        };
+
+        let functions = match config.functions_format {
+            config::FUNCTIONS_FORMAT::GHIDRA => parser::ghidra::load_functions(path),
+            config::FUNCTIONS_FORMAT::IDA => parser::ida::load_functions(path),
+        };
+
+        match functions {
+            Ok(functions) => {
+                info!("Loaded {} functions from {}.", functions.len(), path);
+                functions
+            }
+            Err(e) => {
+                error!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    /// Keeps only the first `limit` functions (ordered by offset) if a limit is given.
+    pub fn limit_functions(functions: &mut Vec<groundtruth::Function>, limit: Option<usize>) {
+        let limit = match limit {
+            Some(limit) => limit,
+            None => return,
+        };
+
+        functions.sort_by_key(|f| f.offset);
+
+        if functions.len() > limit {
+            warn!(
+                "Only processing {} of {} functions, the dump will be partial.",
+                limit,
+                functions.len()
+            );
+            functions.truncate(limit);
+        }
+    }
+
+    /// Checks that the symbol `[offset, offset + size)` lies within the bounds of its section
+    /// (start and exclusive end, `None` if the symbol refers to no section). Records a violation
+    /// otherwise (see `--validate-offsets`).
+    pub fn fits_section(
+        kind: &str,
+        name: &str,
+        offset: u64,
+        size: u64,
+        bounds: Option<(u64, u64)>,
+        violations: &mut Vec<groundtruth::Diagnostic>,
+    ) -> bool {
+        let reason = match bounds {
+            None => "lies in no section".to_string(),
+            Some((start, end)) if offset < start || offset >= end => {
+                format!("starts outside of its section (0x{:x}-0x{:x})", start, end)
+            }
+            Some((start, end)) if offset + size > end => format!(
+                "ends 0x{:x} bytes behind its section (0x{:x}-0x{:x})",
+                offset + size - end,
+                start,
+                end
+            ),
+            Some(_) => return true,
+        };
+
+        violations.push(groundtruth::Diagnostic::warning(
+            groundtruth::WARNING::SYMBOL_OUT_OF_BOUNDS,
+            format!(
+                "{} {} at 0x{:x} (size 0x{:x}) {}.",
+                kind, name, offset, size, reason
+            ),
+        ));
+        false
+    }
+
+    /// Reports the raw data cut off from sections overlapping an earlier section (see
+    /// [`groundtruth::resolve_section_overlaps`]).
+    pub fn report_section_overlaps(
+        sections: &[groundtruth::Section],
+        warnings: &mut groundtruth::Warnings,
+    ) {
+        for section in sections {
+            for overlap in &section.overlaps {
+                let message = format!(
+                    "Raw data of section {} overlaps section {} at 0x{:x}-0x{:x}, the bytes \
+                     belong to {}.",
+                    section.name, overlap.section, overlap.start, overlap.end, overlap.section
+                );
+
+                warn!("{}", message);
+                warnings.report(groundtruth::Diagnostic::warning(
+                    groundtruth::WARNING::SECTION_OVERLAP,
+                    message,
+                ));
+            }
+        }
+    }
+
+    /// Reports the symbols which do not fit their sections all at once. They have already been
+    /// dropped, with `strict` processing is aborted instead.
+    pub fn report_offset_violations(
+        violations: Vec<groundtruth::Diagnostic>,
+        strict: bool,
+        warnings: &mut groundtruth::Warnings,
+    ) {
+        // Guard: Every symbol fits
+        if violations.is_empty() {
+            info!("All symbols fit their sections.");
+            return;
+        }
+
+        for violation in &violations {
+            warn!("{}", violation.message);
+        }
+
+        if strict {
+            error!(
+                "{} symbols do not fit their sections, aborting (--strict).",
+                violations.len()
+            );
+            process::exit(1);
+        }
+
+        warn!(
+            "Dropped {} symbols which do not fit their sections.",
+            violations.len()
+        );
+
+        for violation in violations {
+            warnings.report(violation);
+        }
+    }
+
+    /// Adds the demangled name to every function (see `--demangle`).
+    pub fn demangle_functions(functions: &mut [groundtruth::Function]) {
+        for function in functions {
+            function.demangled_name = Some(demangle::demangle(&function.name));
+        }
+    }
+
+    /// Keeps only the function with the given name. Exits with the closest matching names if it
+    /// does not exist.
+    pub fn select_function(functions: &mut Vec<groundtruth::Function>, name: &str) {
+        if functions.iter().any(|f| f.name == name) {
+            functions.retain(|f| f.name == name);
+            return;
+        }
+
+        let mut candidates: Vec<(usize, &String)> = functions
+            .iter()
+            .map(|f| (edit_distance(&f.name, name), &f.name))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        let suggestions: Vec<&String> = candidates.iter().take(3).map(|(_, n)| *n).collect();
+
+        error!(
+            suggestions = ?suggestions,
+            "Function {} not found.",
+            name
+        );
+        process::exit(1);
+    }
+
+    /// Levenshtein distance between two names.
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut row: Vec<usize> = (0..=b.len()).collect();
+
+        for (i, ca) in a.chars().enumerate() {
+            let mut previous = row[0];
+            row[0] = i + 1;
+
+            for (j, cb) in b.iter().enumerate() {
+                let substitution = previous + if ca == *cb { 0 } else { 1 };
+                previous = row[j + 1];
+                row[j + 1] = substitution.min(row[j] + 1).min(previous + 1);
+            }
+        }
+
+        row[b.len()]
+    }
+
+    /// Drops all bytes outside of the (single) selected function and makes its offsets relative
+    /// to its first byte. Returns the address of the function.
+    pub fn restrict_to_function(
+        bytes: &mut Vec<groundtruth::Byte>,
+        functions: &mut [groundtruth::Function],
+    ) -> u64 {
+        let base = bytes.first().map_or(0, |b| b.offset);
+
+        let function = match functions.first_mut() {
+            Some(function) => function,
+            None => return base,
+        };
+
+        let start = base + function.offset;
+        let end = start + function.size;
+
+        bytes.retain(|b| b.offset >= start && b.offset < end);
+
+        for data in &mut function.data {
+            data.offset -= function.offset;
+        }
+
+        for label in &mut function.labels {
+            label.offset -= function.offset;
+        }
+
+        function.offset = 0;
+
+        start
+    }
+
+    #[instrument(skip_all)]
+    pub fn trim_byte_vector(bytes: &mut Vec<groundtruth::Byte>, start: u64, end: u64) {
+        // Cut current start to new start and new end to current end
+        bytes.drain(..start as usize);
+        bytes.drain((end - start) as usize..);
+    }
+
+    #[instrument(skip_all)]
+    pub fn rebase_byte_vector(bytes: &mut [groundtruth::Byte], base: u64) {
+        // Reset offsets
+        for (offset, byte) in bytes.iter_mut().enumerate() {
+            byte.offset = offset as u64 + base;
+        }
+    }
+
+    #[instrument(skip_all)]
+    pub fn set_byte_flags(
+        bytes: &mut [groundtruth::Byte],
+        functions: &[groundtruth::Function],
+        explain: bool,
+    ) {
+        for function in functions {
+            // Set data flags
+            // Attention: we have to use the child data of a function and not from the normal
+            // data collection because ONLY the child data has a up-to-date size value.
+            for data in &function.data {
+                for i in 0..data.size {
+                    let byte = &mut bytes[(data.offset + i) as usize];
+                    byte.set_flags(vec![groundtruth::FLAG::DATA]);
+
+                    if explain {
+                        byte.explain(format!(
+                            "DATA from in-line data {} of function {}",
+                            data.name, function.name
+                        ));
+                    }
+                }
+            }
+
+            // Set data and code flags
+            for i in 0..function.size {
+                // Guard: Check if function size is greater than section size.
+                if (function.offset + i) as usize >= bytes.len() {
+                    warn!(
+                        function = %function.name,
+                        "Function (allegedly) ends outside of the text section."
+                    );
+                    break;
+                }
+
+                // Guard: Check if byte is already data (because there is data within the function)
+                if bytes[(function.offset + i) as usize].is_data() {
+                    continue;
+                }
+
+                let byte = &mut bytes[(function.offset + i) as usize];
+                byte.set_flags(vec![groundtruth::FLAG::CODE]);
+
+                if explain {
+                    byte.explain(format!("CODE from function {}", function.name));
+                }
+            }
+        }
+    }
+
+    /// Estimates the memory needed for the parsed dumps and the byte vector of the binary and
+    /// fails if it exceeds `--max-memory`. This is a soft limit: the estimate is checked once,
+    /// before the byte vector is built, and later allocations (e.g. instructions) are not
+    /// accounted for.
+    pub fn check_memory_limit(
+        max_memory: Option<u64>,
+        dump_yamls: &[String],
+        binary: &[u8],
+    ) -> Result<(), &'static str> {
+        let limit = match max_memory {
+            Some(limit) => limit * 1024 * 1024,
+            None => return Ok(()),
+        };
+
+        let dumps: u64 = dump_yamls.iter().map(|d| d.len() as u64).sum();
+        let bytes = binary.len() as u64 * mem::size_of::<groundtruth::Byte>() as u64;
+        let estimate = dumps * (1 + YAML_MEMORY_FACTOR) + binary.len() as u64 + bytes;
+
+        debug!(
+            "Estimated memory usage: {} MB (limit: {} MB).",
+            estimate / (1024 * 1024),
+            limit / (1024 * 1024)
+        );
+
+        if estimate > limit {
+            error!(
+                "Estimated memory usage of {} MB exceeds the limit of {} MB.",
+                estimate / (1024 * 1024),
+                limit / (1024 * 1024)
+            );
+            return Err("Input is too large for --max-memory!");
+        }
+
+        Ok(())
+    }
+
+    /// Removes functions without a single code byte (e.g. a jump table which was tagged as a
+    /// function and is completely covered by its in-line data). Their bytes keep the data flags.
+    #[instrument(skip_all)]
+    pub fn drop_data_only_functions(
+        bytes: &[groundtruth::Byte],
+        functions: &mut Vec<groundtruth::Function>,
+        warnings: &mut groundtruth::Warnings,
+    ) {
+        let base = bytes.first().map_or(0, |b| b.offset);
+        let function_count = functions.len();
+
+        functions.retain(|function| {
+            // Guard: Functions outside of the section are reported by the disassembly
+            if (function.offset + function.size) as usize > bytes.len() {
+                return true;
+            }
+
+            let has_code = (function.offset..function.offset + function.size)
+                .any(|offset| bytes.get(offset as usize).is_some_and(|b| b.is_code()));
+
+            if !has_code {
+                warn!(function = %function.name, "Function consists of data only, reclassified as data.");
+                warnings.report(
+                    groundtruth::Diagnostic::warning(
+                        groundtruth::WARNING::DATA_ONLY_FUNCTION,
+                        "Function consists of data only, reclassified as data.".to_string(),
+                    )
+                    .in_function(&function.name)
+                    .at(
+                        base + function.offset,
+                        base + function.offset + function.size.max(1) - 1,
+                    ),
+                );
+            }
+
+            has_code
+        });
+
+        info!(
+            "Reclassified {} data-only functions as data.",
+            function_count - functions.len()
+        );
+    }
+
+    #[instrument(skip_all)]
+    pub fn disassemble(
+        bytes: &mut [groundtruth::Byte],
+        functions: &[groundtruth::Function],
+        architecture: &groundtruth::ArchInfo,
+        anomaly_threshold: f64,
+        function_timeout: Option<Duration>,
+        disassembler: disassembler::DISASSEMBLER,
+        warnings: &mut groundtruth::Warnings,
+    ) -> Vec<groundtruth::Instruction> {
+        let mut all_instructions = Vec::new();
+        let mut architecture_checked = false;
+        let base = bytes.first().map_or(0, |b| b.offset);
+
+        for function in functions {
+            // Thumb functions of ARM binaries are decoded as Thumb
+            let architecture = &architecture.of_function(function);
+
+            // Guard: Function has to be located completely within the section
+            if (function.offset + function.size) as usize > bytes.len() {
+                warn!(
+                    function = %function.name,
+                    "Function (allegedly) ends outside of the text section."
+                );
+                warnings.report(
+                    groundtruth::Diagnostic::warning(
+                        groundtruth::WARNING::FUNCTION_OUT_OF_BOUNDS,
+                        "Function (allegedly) ends outside of the text section.".to_string(),
+                    )
+                    .in_function(&function.name)
+                    .at(
+                        base + function.offset,
+                        base + function.offset + function.size.max(1) - 1,
+                    ),
+                );
+                continue;
+            }
+
+            let mut function_buffer = Vec::new();
+
+            for offset in 0..function.size {
+                // Guard: Byte already flagged as data
+                if bytes[(function.offset + offset) as usize].is_data() {
+                    continue;
+                }
+
+                // Set specific flags
+                bytes[(function.offset + offset) as usize].set_flags(vec![
+                    groundtruth::FLAG::CODE,
+                    groundtruth::FLAG::READABLE,
+                    groundtruth::FLAG::EXECUTABLE,
+                ]);
+
+                // Add byte to function buffer
+                function_buffer.push(bytes[(function.offset + offset) as usize].value);
+            }
+
+            // Set function start and end, only code bytes may mark a function boundary
+            let first = &mut bytes[function.offset as usize];
+            if first.is_code() {
+                first.set_flags(vec![groundtruth::FLAG::FUNCTION_START]);
+            } else {
+                warn!(function = %function.name, "Function does not start with code.");
+                warnings.report(
+                    groundtruth::Diagnostic::warning(
+                        groundtruth::WARNING::FUNCTION_WITHOUT_CODE,
+                        "Function does not start with code.".to_string(),
+                    )
+                    .in_function(&function.name)
+                    .at(first.offset, first.offset),
+                );
+            }
+
+            let last = &mut bytes[(function.offset + function.size - 1) as usize];
+            if last.is_code() {
+                last.set_flags(vec![groundtruth::FLAG::FUNCTION_END]);
+            }
+
+            let buffer_length = function_buffer.len() as u64;
+            let head: Vec<u8> = function_buffer.iter().take(8).copied().collect();
+
+            // Since we (may have) cut our function buffer in the middle our instruction offset will become "wrong"
+            // the moment we come to the first instruction after the "hole" we created by erasing some bytes in the middle
+            // since they were data bytes. Therefore we need to account for the additional offset created by the size of the
+            // removed bytes.
+            // TODO: Handle multiple holes in the middle.
+            let section_offset = |offset: u64| {
+                let mut additional_offset = 0;
+
+                for data in &function.data {
+                    // Check current instruction has a offset which would in theory place in the inline data hole
+                    if (offset + function.offset + additional_offset) >= data.offset {
+                        additional_offset += data.size;
+                    }
+                }
+
+                additional_offset + function.offset + offset
+            };
+
+            // Disassemble function bytes
+            let mut instructions = match disassembler::disassemble_until(
+                function_buffer,
+                architecture,
+                disassembler,
+                function_timeout.map(|timeout| Instant::now() + timeout),
+            ) {
+                Ok(instructions) => instructions,
+                Err(e) if e == disassembler::TIMED_OUT => {
+                    warn!(
+                        function = %function.name,
+                        size = function.size,
+                        "Disassembly timed out, function skipped."
+                    );
+                    warnings.report(
+                        groundtruth::Diagnostic::warning(
+                            groundtruth::WARNING::FUNCTION_TIMEOUT,
+                            "Disassembly timed out, function skipped.".to_string(),
+                        )
+                        .in_function(&function.name)
+                        .at(
+                            base + function.offset,
+                            base + function.offset + function.size - 1,
+                        ),
+                    );
+                    continue;
+                }
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            // A declared size which is slightly too short cuts off the last instruction, decode it
+            // again including the bytes behind the function
+            let decoded_length: u64 = instructions.iter().map(|i| i.length).sum();
+            if decoded_length < buffer_length {
+                let start = section_offset(decoded_length);
+
+                if let Some(instruction) = decode_straddling_instruction(
+                    bytes,
+                    start,
+                    function.offset + function.size,
+                    architecture,
+                    disassembler,
+                ) {
+                    let message = format!(
+                        "Instruction at offset 0x{:x} continues 0x{:x} bytes behind the function ({} {}).",
+                        start,
+                        start + instruction.length - (function.offset + function.size),
+                        instruction.mnemonic,
+                        instruction.operand
+                    );
+                    warn!(function = %function.name, "{}", message);
+                    warnings.report(
+                        groundtruth::Diagnostic::warning(
+                            groundtruth::WARNING::STRADDLING_INSTRUCTION,
+                            message,
+                        )
+                        .in_function(&function.name)
+                        .at(base + start, base + start + instruction.length - 1),
+                    );
+
+                    instructions.push(groundtruth::Instruction {
+                        offset: decoded_length,
+                        ..instruction
+                    });
+                }
+            }
+
+            // Guard: Not even the first instruction could be decoded, the bytes stay code without
+            // any instruction
+            if instructions.is_empty() && buffer_length > 0 {
+                let head: Vec<String> = head.iter().map(|b| format!("{:02x}", b)).collect();
+
+                let message = format!(
+                    "No instruction could be decoded (first bytes: {}).",
+                    head.join(" ")
+                );
+                warn!(function = %function.name, "{}", message);
+                warnings.report(
+                    groundtruth::Diagnostic::warning(groundtruth::WARNING::DECODE_FAILURE, message)
+                        .in_function(&function.name)
+                        .at(
+                            base + function.offset,
+                            base + function.offset + function.size - 1,
+                        ),
+                );
+                continue;
+            }
+
+            // Capstone stops at the first byte it cannot decode
+            let decoded_length: u64 = instructions.iter().map(|i| i.length).sum();
+            if decoded_length < buffer_length {
+                let message = format!(
+                    "Disassembly stops after 0x{:x} of 0x{:x} bytes.",
+                    decoded_length, buffer_length
+                );
+                warn!(function = %function.name, "{}", message);
+                warnings.report(
+                    groundtruth::Diagnostic::warning(groundtruth::WARNING::DECODE_FAILURE, message)
+                        .in_function(&function.name)
+                        .at(
+                            base + section_offset(decoded_length),
+                            base + function.offset + function.size - 1,
+                        ),
+                );
+            }
+
+            // The first function tells if the bytes match the architecture at all
+            if !architecture_checked && !instructions.is_empty() {
+                architecture_checked = true;
+
+                let fraction = anomalous_fraction(&instructions, decoded_length < buffer_length);
+
+                if fraction > anomaly_threshold {
+                    let message = format!(
+                        "{:.0}% of the instructions are anomalous, the architecture ({}) may be wrong.",
+                        fraction * 100.0,
+                        architecture.machine_name
+                    );
+                    warn!(function = %function.name, "{}", message);
+                    warnings.report(
+                        groundtruth::Diagnostic::warning(
+                            groundtruth::WARNING::ARCHITECTURE_MISMATCH,
+                            message,
+                        )
+                        .in_function(&function.name),
+                    );
+                }
+            }
+
+            // Set instruction start and end, copy instruction flags
+            for mut instruction in instructions {
+                // Instruction End Example: Start 0x0, Size 0x8 => Instruction: 0x0-0x8 therefore the 8th byte (the last byte) is 0x7
+                let start = section_offset(instruction.offset);
+                let end = start + instruction.length.max(1) - 1;
+
+                // Guard: Instruction has to end within the section
+                if end as usize >= bytes.len() {
+                    let message = format!(
+                        "Instruction at offset 0x{:x} ends outside of the text section.",
+                        start
+                    );
+                    warn!(function = %function.name, "{}", message);
+                    warnings.report(
+                        groundtruth::Diagnostic::warning(
+                            groundtruth::WARNING::INSTRUCTION_OUT_OF_BOUNDS,
+                            message,
+                        )
+                        .in_function(&function.name)
+                        .at(base + start, base + end),
+                    );
+                    continue;
+                }
+
+                bytes[start as usize].set_flags(vec![groundtruth::FLAG::INSTRUCTION_START]);
+                bytes[end as usize].set_flags(vec![groundtruth::FLAG::INSTRUCTION_END]);
+
+                // TODO: Set instruction flags for not only the first byte of instruction
+                bytes[start as usize].set_flags(instruction.get_flags());
+
+                instruction.address = bytes[start as usize].offset;
+
+                // Only targets within the function are kept
+                instruction.target = instruction
+                    .target
+                    .filter(|target| *target < buffer_length)
+                    .map(|target| bytes[section_offset(target) as usize].offset);
+
+                // Append to instructions vector
+                all_instructions.push(instruction);
+            }
+        }
+
+        all_instructions
+    }
+
+    /// Decodes the instruction at `start` (a section offset) including the bytes behind the
+    /// function end. Returns it if it actually continues behind the end, which means the declared
+    /// function size cuts it off. Unclassified bytes behind the end are flagged as code.
+    fn decode_straddling_instruction(
+        bytes: &mut [groundtruth::Byte],
+        start: u64,
+        end: u64,
+        architecture: &groundtruth::ArchInfo,
+        disassembler: disassembler::DISASSEMBLER,
+    ) -> Option<groundtruth::Instruction> {
+        // Guard: The undecoded bytes have to reach up to the function end (no in-line data)
+        if start >= end
+            || end as usize > bytes.len()
+            || bytes[start as usize..end as usize]
+                .iter()
+                .any(|b| b.is_data())
+        {
+            return None;
+        }
+
+        let stop = (start as usize + MAX_INSTRUCTION_LENGTH).min(bytes.len());
+        let buffer = bytes[start as usize..stop]
+            .iter()
+            .map(|b| b.value)
+            .collect();
+
+        let instruction = disassembler::disassemble(buffer, architecture, disassembler)
+            .ok()?
+            .into_iter()
+            .next()?;
+
+        // Guard: The instruction fits into the function, the bytes are undecodable
+        if start + instruction.length <= end {
+            return None;
+        }
+
+        for byte in &mut bytes[end as usize..(start + instruction.length) as usize] {
+            if byte.get_flags().is_empty() {
+                byte.set_flags(vec![
+                    groundtruth::FLAG::CODE,
+                    groundtruth::FLAG::READABLE,
+                    groundtruth::FLAG::EXECUTABLE,
+                ]);
+            }
+        }
+
+        Some(instruction)
+    }
+
+    /// Fraction of instructions which are anomalous for compiler generated code. A decoding which
+    /// stopped early counts as one more anomalous instruction.
+    fn anomalous_fraction(instructions: &[groundtruth::Instruction], truncated: bool) -> f64 {
+        let mut anomalous = instructions
+            .iter()
+            .filter(|i| {
+                ANOMALOUS_MNEMONICS.contains(&i.mnemonic.as_str())
+                    || (i.length == 1 && (i.mnemonic == "inc" || i.mnemonic == "dec"))
+            })
+            .count();
+
+        if truncated {
+            anomalous += 1;
+        }
+
+        anomalous as f64 / (instructions.len() + truncated as usize) as f64
+    }
+
+    /// Compares the declared size of every function with the extent of its disassembly, which
+    /// ends with the last instruction that is not padding (nops, int3). With `trust_disassembly`
+    /// functions are shrunk to that extent: the bytes behind it lose their code and instruction
+    /// flags (in-line data stays data) and the labels and in-line data behind it are detached.
+    #[instrument(skip_all)]
+    pub fn reconcile_function_sizes(
+        bytes: &mut [groundtruth::Byte],
+        functions: &mut [groundtruth::Function],
+        instructions: &mut Vec<groundtruth::Instruction>,
+        trust_disassembly: bool,
+    ) -> Vec<groundtruth::SizeDiscrepancy> {
+        let mut discrepancies = Vec::new();
+        let base = bytes.first().map_or(0, |b| b.offset);
+
+        // Instruction ends sorted by address, padding does not count
+        let mut ends: Vec<(u64, u64)> = instructions
+            .iter()
+            .filter(|i| !i.is_alignment() && i.mnemonic != "int3")
+            .map(|i| (i.address, i.address + i.length))
+            .collect();
+        ends.sort_unstable();
+
+        for function in functions.iter_mut() {
+            let start = base + function.offset;
+            let end = start + function.size;
+
+            let first = ends.partition_point(|(address, _)| *address < start);
+            let last = ends.partition_point(|(address, _)| *address < end);
+
+            // Guard: Functions without any disassembly (e.g. outside of the section)
+            let extent = match ends[first..last].iter().map(|(_, e)| *e).max() {
+                Some(extent_end) => extent_end.min(end) - start,
+                None => continue,
+            };
+
+            if function.size - extent <= SIZE_DISCREPANCY_THRESHOLD {
+                continue;
+            }
+
+            debug!(
+                function = %function.name,
+                "Declared size 0x{:x} but disassembly ends after 0x{:x} bytes.",
+                function.size,
+                extent
+            );
+
+            discrepancies.push(groundtruth::SizeDiscrepancy {
+                name: function.name.clone(),
+                offset: function.offset,
+                declared_size: function.size,
+                disassembled_size: extent,
+                applied: trust_disassembly,
+            });
+
+            if trust_disassembly {
+                let new_end = start + extent;
+
+                // Only data and code flags are set so far, in-line data keeps its flags
+                for byte in &mut bytes[(new_end - base) as usize..(end - base) as usize] {
+                    byte.flags.retain(|f| f == &groundtruth::FLAG::DATA);
+
+                    if byte.flags.is_empty() {
+                        byte.provenance.clear();
+                    }
+                }
+                bytes[(new_end - base - 1) as usize]
+                    .set_flags(vec![groundtruth::FLAG::FUNCTION_END]);
+
+                instructions.retain(|i| i.address < new_end || i.address >= end);
+
+                // Labels and in-line data behind the new end no longer belong to the function
+                let function_end = function.offset + extent;
+                function.labels.retain(|l| l.offset < function_end);
+                function.data.retain(|d| d.offset < function_end);
+                function.size = extent;
+            }
+        }
+
+        info!(
+            "{} functions have a declared size which disagrees with their disassembly.",
+            discrepancies.len()
+        );
+
+        discrepancies
+    }
+
+    /// Flags instructions matching one of the given mnemonics (e.g. `rdtsc`) or full instructions
+    /// (e.g. `int 0x2d`) as suspicious.
+    #[instrument(skip_all)]
+    pub fn flag_suspicious_instructions(
+        bytes: &mut [groundtruth::Byte],
+        instructions: &mut [groundtruth::Instruction],
+        mnemonics: &[String],
+    ) {
+        // Guard: Nothing to look for
+        if mnemonics.is_empty() {
+            return;
+        }
+
+        let base = bytes.first().map_or(0, |b| b.offset);
+        let mut matches = 0;
+
+        for instruction in instructions.iter_mut() {
+            let text = format!("{} {}", instruction.mnemonic, instruction.operand);
+
+            if !mnemonics
+                .iter()
+                .any(|m| *m == instruction.mnemonic || *m == text.trim_end())
+            {
+                continue;
+            }
+
+            instruction.set_flags(vec![groundtruth::FLAG::SUSPICIOUS]);
+
+            if let Some(byte) = bytes.get_mut((instruction.address - base) as usize) {
+                byte.set_flags(vec![groundtruth::FLAG::SUSPICIOUS]);
+            }
+
+            info!(
+                "Suspicious instruction at 0x{:x}: {}",
+                instruction.address,
+                text.trim_end()
+            );
+            matches += 1;
+        }
+
+        info!("Found {} suspicious instructions.", matches);
+    }
+
+    /// Flags the first instruction of every basic block: function starts, direct branch targets
+    /// and instructions following a branch or return. Blocks which are reached by a branch as
+    /// well as by falling through from the preceding instruction are additionally flagged as
+    /// joins.
+    #[instrument(skip_all)]
+    pub fn detect_basic_blocks(
+        bytes: &mut [groundtruth::Byte],
+        instructions: &mut [groundtruth::Instruction],
+    ) {
+        let base = bytes.first().map_or(0, |b| b.offset);
+
+        // Incoming branch edges per address
+        let mut branches: HashMap<u64, u64> = HashMap::new();
+        for target in instructions.iter().filter_map(|i| i.target) {
+            *branches.entry(target).or_insert(0) += 1;
+        }
+
+        let mut order: Vec<usize> = (0..instructions.len()).collect();
+        order.sort_by_key(|&index| instructions[index].address);
+
+        let mut blocks = 0;
+        let mut joins = 0;
+        let mut previous: Option<usize> = None;
+
+        for index in order {
+            let address = instructions[index].address;
+
+            // Incoming fallthrough edge from the directly preceding instruction
+            let fallthrough = previous
+                .map(|p| &instructions[p])
+                .filter(|p| p.address + p.length == address && p.falls_through());
+            let ends_block =
+                fallthrough.is_none_or(|p| p.flags.contains(&groundtruth::FLAG::INSTRUCTION_JUMP));
+            let branched = branches.contains_key(&address);
+
+            let mut flags = Vec::new();
+
+            let function_start = bytes
+                .get((address - base) as usize)
+                .is_some_and(|b| b.is_function_start());
+
+            if branched || ends_block || function_start {
+                flags.push(groundtruth::FLAG::BLOCK_START);
+                blocks += 1;
+            }
+
+            if branched && fallthrough.is_some() {
+                flags.push(groundtruth::FLAG::BLOCK_JOIN);
+                joins += 1;
+            }
+
+            previous = Some(index);
+
+            // Guard: Nothing to flag
+            if flags.is_empty() {
+                continue;
+            }
+
+            if let Some(byte) = bytes.get_mut((address - base) as usize) {
+                byte.set_flags(flags.clone());
+            }
+            instructions[index].set_flags(flags);
+        }
+
+        info!(
+            "Found {} basic blocks, {} of them are joins.",
+            blocks, joins
+        );
+    }
+
+    /// Recognizes the in-line data of functions which is a jump table and determines how its
+    /// entries encode the targets. Every interpretation (see [`groundtruth::JUMP_TABLE_ENTRY`])
+    /// is tried and the one yielding the longest run of targets within the function (outside of
+    /// its in-line data) wins. In-line data sized by its case labels (4 bytes each) is resized to
+    /// the entry size of the winner.
+    #[instrument(skip_all)]
+    pub fn detect_jump_tables(
+        bytes: &[groundtruth::Byte],
+        functions: &mut [groundtruth::Function],
+        architecture: &groundtruth::ArchInfo,
+        space: dumper::AddressSpace,
+    ) -> Vec<groundtruth::JumpTable> {
+        let base = bytes.first().map_or(0, |b| b.offset);
+
+        // Entries are resolved into the address space of the byte vector
+        let (absolute_base, image_base) = match space.virtual_addresses {
+            true => (0, space.image_base),
+            false => (space.image_base, 0),
+        };
+
+        let kinds = [
+            (
+                groundtruth::JUMP_TABLE_ENTRY::ABSOLUTE,
+                architecture.bits as u64 / 8,
+            ),
+            (groundtruth::JUMP_TABLE_ENTRY::IMAGE_RELATIVE, 4),
+            (groundtruth::JUMP_TABLE_ENTRY::RELATIVE, 4),
+        ];
+
+        let mut jump_tables = Vec::new();
+
+        for function in functions.iter_mut() {
+            let start = function.offset;
+            let data_ranges: Vec<(u64, u64)> = function
+                .data
+                .iter()
+                .map(|d| (d.offset, d.offset + d.size))
+                .collect();
+
+            // In-line data at the end has been cut off the function already
+            let end = data_ranges
+                .iter()
+                .map(|(_, e)| *e)
+                .fold(function.offset + function.size, u64::max)
+                .min(bytes.len() as u64);
+
+            // Targets have to be code of the same function
+            let is_target = |index: u64| {
+                index >= start
+                    && index < end
+                    && !data_ranges.iter().any(|(s, e)| index >= *s && index < *e)
+            };
+
+            for data in function.data.iter_mut() {
+                let labeled = !data.name.is_empty();
+
+                // Case labels give the number of entries, tables at the end span the rest
+                let (available, table_end) = match labeled {
+                    true => (data.size / 4, end),
+                    false => (u64::MAX, (data.offset + data.size).min(end)),
+                };
+
+                let best = kinds
+                    .iter()
+                    .map(|&(kind, entry_size)| {
+                        let entries = (0..available)
+                            .map(|i| data.offset + i * entry_size)
+                            .take_while(|offset| offset + entry_size <= table_end)
+                            .take_while(|&offset| {
+                                let value = read_entry(bytes, offset, entry_size);
+                                let target = match kind {
+                                    groundtruth::JUMP_TABLE_ENTRY::ABSOLUTE => {
+                                        value.wrapping_sub(absolute_base)
+                                    }
+                                    groundtruth::JUMP_TABLE_ENTRY::IMAGE_RELATIVE => {
+                                        value.wrapping_add(image_base)
+                                    }
+                                    groundtruth::JUMP_TABLE_ENTRY::RELATIVE => (base + data.offset)
+                                        .wrapping_add(value as i32 as i64 as u64),
+                                };
+
+                                is_target(target.wrapping_sub(base))
+                            })
+                            .count() as u64;
+
+                        (kind, entry_size, entries)
+                    })
+                    .max_by_key(|&(_, _, entries)| entries);
+
+                let (entry, entry_size, entries) = match best {
+                    Some(best) if best.2 >= MIN_JUMP_TABLE_ENTRIES => best,
+                    _ => continue,
+                };
+
+                if labeled {
+                    data.size = entries * entry_size;
+                }
+
+                debug!(
+                    function = %function.name,
+                    "Jump table at 0x{:x} with {} {:?} entries.",
+                    base + data.offset,
+                    entries,
+                    entry
+                );
+
+                jump_tables.push(groundtruth::JumpTable {
+                    function: function.name.clone(),
+                    address: base + data.offset,
+                    entry,
+                    entry_size,
+                    entries,
+                });
+            }
+        }
+
+        info!(
+            "Found {} jump tables, {} with relative entries.",
+            jump_tables.len(),
+            jump_tables
+                .iter()
+                .filter(|t| t.entry == groundtruth::JUMP_TABLE_ENTRY::RELATIVE)
+                .count()
+        );
+
+        jump_tables
+    }
+
+    /// Adds the literal pools of ARM and Thumb functions (see
+    /// [`disassembler::literal_pool_entries`]) to their in-line data, so they are flagged as data
+    /// and excluded from the disassembly. Adjacent literals form a single pool, pools overlapping
+    /// known in-line data are dropped.
+    #[instrument(skip_all)]
+    pub fn detect_literal_pools(
+        bytes: &[groundtruth::Byte],
+        functions: &mut [groundtruth::Function],
+        architecture: &groundtruth::ArchInfo,
+    ) {
+        // Guard: Only ARM and Thumb code has literal pools
+        if !architecture.family.is_arm() {
+            return;
+        }
+
+        let mut pools = 0;
+
+        for function in functions.iter_mut() {
+            let start = function.offset as usize;
+            let end = (function.offset + function.size) as usize;
+
+            // Guard: Functions outside of the section are reported by the disassembly
+            if function.size == 0 || end > bytes.len() {
+                continue;
+            }
+
+            let buffer: Vec<u8> = bytes[start..end].iter().map(|b| b.value).collect();
+            let entries = disassembler::literal_pool_entries(
+                &buffer,
+                bytes[start].offset,
+                &architecture.of_function(function),
+            );
+
+            // Merge adjacent literals (sorted by offset) into pools
+            let mut ranges: Vec<(u64, u64)> = Vec::new();
+            for (offset, size) in entries {
+                match ranges.last_mut() {
+                    Some((_, end)) if offset <= *end => *end = (*end).max(offset + size),
+                    _ => ranges.push((offset, offset + size)),
+                }
+            }
+
+            for (pool_start, pool_end) in ranges {
+                let offset = function.offset + pool_start;
+                let size = pool_end - pool_start;
+
+                // Guard: Known in-line data takes priority
+                if function
+                    .data
+                    .iter()
+                    .any(|d| offset < d.offset + d.size && d.offset < offset + size)
+                {
+                    continue;
+                }
+
+                debug!(
+                    function = %function.name,
+                    "Literal pool at 0x{:x} with 0x{:x} bytes.",
+                    bytes[offset as usize].offset,
+                    size
+                );
+
+                function.data.push(groundtruth::Data {
+                    name: LITERAL_POOL.to_string(),
+                    offset,
+                    segment: function.segment,
+                    size,
+                    bytes: None,
+                });
+                pools += 1;
+            }
+
+            // The disassembly skips in-line data in order of the offsets
+            function.data.sort_by_key(|d| d.offset);
+        }
+
+        info!("Found {} literal pools.", pools);
+    }
+
+    /// Reads a little endian value of `size` bytes (at most 8) starting at `offset`.
+    fn read_entry(bytes: &[groundtruth::Byte], offset: u64, size: u64) -> u64 {
+        bytes[offset as usize..(offset + size) as usize]
+            .iter()
+            .rev()
+            .fold(0, |value, byte| (value << 8) | byte.value as u64)
+    }
+
+    /// Maps the data sections (see [`groundtruth::Section::is_data`]) from the byte vector of the
+    /// original binary (indexed by file offset). Their bytes are flagged as data with the
+    /// permissions of the section, the data symbols within a section are attached to it and NUL
+    /// terminated strings are collected. Addresses are the ones of the code section's byte vector
+    /// (RVAs for PE, virtual addresses for ELF).
+    #[instrument(skip_all)]
+    pub fn classify_data_sections(
+        original: &[groundtruth::Byte],
+        sections: &[groundtruth::Section],
+        symbols: &[groundtruth::DataSymbol],
+        explain: bool,
+    ) -> Vec<groundtruth::DataSection> {
+        let mut data_sections = Vec::new();
+
+        for section in sections.iter().filter(|s| s.is_data()) {
+            let start = section.raw_data_offset as usize;
+            let end = (start + section.raw_data_size as usize).min(original.len());
+
+            // Guard: Raw data lies outside of the binary
+            if start >= end {
+                warn!(section = %section.name, "Data section lies outside of the binary.");
+                continue;
+            }
+
+            let size = (end - start) as u64;
+            let permissions = section.permissions();
+
+            let mut bytes = original[start..end].to_vec();
+            for (index, byte) in bytes.iter_mut().enumerate() {
+                byte.offset = section.va + index as u64;
+                byte.set_flags(vec![groundtruth::FLAG::DATA]);
+                byte.set_flags(permissions.clone());
+
+                if explain {
+                    byte.explain(format!("DATA from section {}", section.name));
+                }
+            }
+
+            let section_symbols: Vec<groundtruth::DataSymbol> = symbols
+                .iter()
+                .filter(|s| s.address >= section.va && s.address < section.va + size)
+                .cloned()
+                .collect();
+
+            if explain {
+                for symbol in &section_symbols {
+                    let first = (symbol.address - section.va) as usize;
+                    let last = (first + symbol.size as usize).min(bytes.len());
+
+                    for byte in &mut bytes[first..last] {
+                        byte.explain(format!("DATA from symbol {}", symbol.name));
+                    }
+                }
+            }
+
+            let values: Vec<u8> = bytes.iter().map(|b| b.value).collect();
+            let strings: Vec<groundtruth::StringLiteral> = holes::find_strings(&values)
+                .into_iter()
+                .map(|(offset, length)| groundtruth::StringLiteral {
+                    address: section.va + offset as u64,
+                    length: length as u64,
+                    value: String::from_utf8_lossy(&values[offset..offset + length]).into_owned(),
+                })
+                .collect();
+
+            info!(
+                section = %section.name,
+                "Classified {} data bytes with {} symbols and {} strings.",
+                size,
+                section_symbols.len(),
+                strings.len()
+            );
+
+            data_sections.push(groundtruth::DataSection {
+                name: section.name.clone(),
+                address: section.va,
+                size,
+                flags: permissions,
+                symbols: section_symbols,
+                strings,
+                bytes,
+            });
+        }
+
+        data_sections
+    }
+
+    /// Finds the functions without any return instruction (`ret`, `iret`, `jr $ra`). They possibly
+    /// never return, e.g. because they end in a trap or a call to `abort`. Reachability is not
+    /// computed, so a function whose return is unreachable is not found, while a tail call is.
+    #[instrument(skip_all)]
+    pub fn detect_noreturn_functions(
+        bytes: &[groundtruth::Byte],
+        functions: &[groundtruth::Function],
+        instructions: &[groundtruth::Instruction],
+        warnings: &mut groundtruth::Warnings,
+    ) -> Vec<groundtruth::NoReturnFunction> {
+        let base = bytes.first().map_or(0, |b| b.offset);
+        let mut noreturn = Vec::new();
+
+        for function in functions {
+            let start = base + function.offset;
+            let end = start + function.size;
+
+            // Padding after the last instruction does not end the function
+            let body: Vec<&groundtruth::Instruction> = instructions
+                .iter()
+                .filter(|i| i.address >= start && i.address < end && !i.is_alignment())
+                .collect();
+
+            let returns = body.iter().any(|i| {
+                i.flags.iter().any(|f| {
+                    f == &groundtruth::FLAG::INSTRUCTION_RET
+                        || f == &groundtruth::FLAG::INSTRUCTION_IRET
+                })
+            });
+
+            // Guard: Functions without disassembly (e.g. timed out) are not judged
+            let last = match body.iter().max_by_key(|i| i.address) {
+                Some(last) if !returns => last,
+                _ => continue,
+            };
+
+            let reason = if matches!(last.mnemonic.as_str(), "int3" | "ud2" | "break") {
+                groundtruth::NORETURN_REASON::TRAP
+            } else if last.flags.contains(&groundtruth::FLAG::INSTRUCTION_CALL) {
+                groundtruth::NORETURN_REASON::CALL
+            } else if last.flags.contains(&groundtruth::FLAG::INSTRUCTION_JUMP) {
+                groundtruth::NORETURN_REASON::JUMP
+            } else {
+                groundtruth::NORETURN_REASON::OTHER
+            };
+
+            debug!(
+                function = %function.name,
+                "No return instruction, possibly noreturn ({:?}).",
+                reason
+            );
+            warnings.report(
+                groundtruth::Diagnostic::info(format!(
+                    "Function {} has no return instruction, possibly noreturn ({:?}).",
+                    function.name, reason
+                ))
+                .at(start, end.max(start + 1) - 1),
+            );
+
+            noreturn.push(groundtruth::NoReturnFunction {
+                name: function.name.clone(),
+                address: start,
+                reason,
+            });
+        }
+
+        noreturn
+    }
+
+    /// Finds direct branches whose target is a code byte which does not start an instruction. Such
+    /// targets indicate overlapping instructions or anti-disassembly tricks. The branches are
+    /// flagged as suspicious.
+    #[instrument(skip_all)]
+    pub fn detect_mid_instruction_targets(
+        bytes: &mut [groundtruth::Byte],
+        instructions: &mut [groundtruth::Instruction],
+        warnings: &mut groundtruth::Warnings,
+    ) -> Vec<groundtruth::MidInstructionTarget> {
+        let base = bytes.first().map_or(0, |b| b.offset);
+        let mut targets = Vec::new();
+
+        for instruction in instructions.iter_mut() {
+            let target = match instruction.target {
+                Some(target) => target,
+                None => continue,
+            };
+
+            let index = (target - base) as usize;
+
+            // Guard: Only code bytes within an instruction
+            match bytes.get(index) {
+                Some(byte) if byte.is_code() && !byte.is_instruction_start() => {}
+                _ => continue,
+            }
+
+            // The containing instruction starts at the closest instruction start before the target
+            let start = match bytes[..index]
+                .iter()
+                .rposition(|b| b.is_instruction_start())
+            {
+                Some(start) => bytes[start].offset,
+                None => continue,
+            };
+
+            let message = format!(
+                "Branch at 0x{:x} targets 0x{:x} within the instruction at 0x{:x}.",
+                instruction.address, target, start
+            );
+            warn!("{}", message);
+            warnings.report(
+                groundtruth::Diagnostic::warning(
+                    groundtruth::WARNING::MID_INSTRUCTION_TARGET,
+                    message,
+                )
+                .at(
+                    instruction.address,
+                    instruction.address + instruction.length.max(1) - 1,
+                ),
+            );
+
+            instruction.set_flags(vec![groundtruth::FLAG::SUSPICIOUS]);
+            if let Some(byte) = bytes.get_mut((instruction.address - base) as usize) {
+                byte.set_flags(vec![groundtruth::FLAG::SUSPICIOUS]);
+            }
+
+            targets.push(groundtruth::MidInstructionTarget {
+                source: instruction.address,
+                target,
+                instruction: start,
+            });
+        }
+
+        targets
+    }
+
+    /// Replaces addresses within the operands with a placeholder so the instruction text does not
+    /// depend on where the code is located: branch targets, rip-relative displacements and any
+    /// other value which looks like an absolute address. The original operand is kept as
+    /// `raw_operand`.
+    #[instrument(skip_all)]
+    pub fn normalize_operands(instructions: &mut [groundtruth::Instruction]) {
+        lazy_static! {
+            static ref VALUE: Regex = Regex::new("(rip [+-] )?0x([0-9a-f]+)(\\(%rip\\))?").unwrap();
+        }
+
+        let mut normalized = 0;
+
+        for instruction in instructions.iter_mut() {
+            let branch = instruction.is_branch();
+
+            let operand = VALUE.replace_all(&instruction.operand, |captures: &Captures| {
+                let value = u64::from_str_radix(&captures[2], 16).unwrap_or(u64::MAX);
+
+                match (captures.get(1), captures.get(3)) {
+                    (Some(rip), _) => format!("{}{}", rip.as_str(), ADDRESS_PLACEHOLDER),
+                    (None, Some(rip)) => format!("{}{}", ADDRESS_PLACEHOLDER, rip.as_str()),
+                    _ if branch || is_absolute_address(value) => ADDRESS_PLACEHOLDER.to_string(),
+                    _ => captures[0].to_string(),
+                }
+            });
+
+            if operand != instruction.operand {
+                let operand = operand.into_owned();
+                instruction.raw_operand = Some(mem::replace(&mut instruction.operand, operand));
+                normalized += 1;
+            }
+        }
+
+        info!("Normalized the operands of {} instructions.", normalized);
+    }
+
+    /// Whether an operand value looks like an address: at least `MIN_ABSOLUTE_ADDRESS` and not a
+    /// small negative number (e.g. the mask of `and rsp, 0xfffffffffffffff0`).
+    fn is_absolute_address(value: u64) -> bool {
+        let negative = if value <= u64::from(u32::MAX) {
+            u64::from((value as u32).wrapping_neg())
+        } else {
+            value.wrapping_neg()
+        };
+
+        value >= MIN_ABSOLUTE_ADDRESS && negative >= MIN_ABSOLUTE_ADDRESS
+    }
+
+    /// Asserts that every function boundary byte is also flagged as code.
+    pub fn check_flag_consistency(bytes: &[groundtruth::Byte]) {
+        for byte in bytes {
+            debug_assert!(
+                byte.is_code() || !(byte.is_function_start() || byte.is_function_end()),
+                "Byte 0x{:x} is a function boundary but not code.",
+                byte.offset
+            );
+        }
+    }
+
+    /// Removes the trailing run of 0x00 bytes (file alignment of the raw section data) which
+    /// follows the last code, data or non-zero byte of the section.
+    #[instrument(skip_all)]
+    pub fn detect_end_of_section(bytes: &mut Vec<groundtruth::Byte>) {
+        // Get current section (vector) size
+        let mut section_size = bytes.len();
+
+        // Check whole byte vector but start from the end
+        for byte in bytes.iter().rev() {
+            // Guard: Only if this byte currently does not have any purpose
+            if byte.is_code() || byte.is_data() {
+                break;
+            }
+
+            // Guard: Only 0x0 bytes belong to the empty tail
+            if byte.value != 0x0 {
+                break;
+            }
+
+            section_size -= 1;
+        }
+
+        // Remove the empty tail
+        bytes.truncate(section_size);
+    }
+
+    /// Flags padding bytes and multi-byte nops within holes as alignment. Looking for multi-byte
+    /// nops disassembles every hole and can be disabled with `multi_byte_nops`.
+    #[instrument(skip_all)]
+    pub fn detect_alignment_bytes(
+        bytes: &mut [groundtruth::Byte],
+        architecture: &groundtruth::ArchInfo,
+        disassembler: disassembler::DISASSEMBLER,
+        padding_bytes: &[u8],
+        multi_byte_nops: bool,
+        explain: bool,
+    ) {
+        // Check whole byte vector for known alignment bytes, single padding bytes (int3) are x86
+        // specific while other architectures pad with nop instructions
+        for byte in bytes.iter_mut() {
+            // Guard: Only if this byte currently does not have any purpose
+            if byte.is_code() || byte.is_data() || !architecture.family.is_x86() {
+                continue;
+            }
+
+            // Check if byte is a known padding byte (default: 0xCC int3)
+            if padding_bytes.contains(&byte.value) {
+                byte.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
+
+                if explain {
+                    byte.explain(format!("ALIGNMENT from padding byte 0x{:02x}", byte.value));
+                }
+            }
+        }
+
+        // Guard: Holes are not disassembled
+        if !multi_byte_nops {
+            return;
+        }
+
+        // Find holes and check of the holes are multi-byte nops
+        let holes = detect_holes(bytes);
+
+        for hole in holes {
+            // Get buffer of hole and disassemble it
+            let hole_buffer = bytes[hole.start as usize..hole.end as usize]
+                .iter()
+                .map(|b| b.value)
+                .collect();
+            let instructions =
+                match disassembler::disassemble(hole_buffer, architecture, disassembler) {
+                    Ok(instructions) => instructions,
+                    Err(e) => {
+                        error!("{}", e);
+                        process::exit(1);
+                    }
+                };
+
+            for instruction in instructions {
+                if instruction.is_alignment() {
+                    for offset in 0..instruction.length {
+                        let byte = &mut bytes[(hole.start + instruction.offset + offset) as usize];
+                        byte.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
+
+                        if explain {
+                            byte.explain(format!(
+                                "ALIGNMENT from multi-byte nop ({} {})",
+                                instruction.mnemonic, instruction.operand
+                            ));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Flags the first byte after every function as its boundary, be it padding, a hole or the
+    /// start of the next function. Runs after the classification since the marker does not
+    /// identify the byte.
+    #[instrument(skip_all)]
+    pub fn flag_function_boundaries(
+        bytes: &mut [groundtruth::Byte],
+        functions: &[groundtruth::Function],
+        explain: bool,
+    ) {
+        let mut boundaries = 0;
+
+        for function in functions.iter().filter(|f| f.size > 0) {
+            // Guard: Functions ending with the (truncated) section
+            let byte = match bytes.get_mut((function.offset + function.size) as usize) {
+                Some(byte) => byte,
+                None => continue,
+            };
+
+            byte.set_flags(vec![groundtruth::FLAG::FUNCTION_BOUNDARY]);
+            boundaries += 1;
+
+            if explain {
+                byte.explain(format!(
+                    "FUNCTION_BOUNDARY after function {}",
+                    function.name
+                ));
+            }
+        }
+
+        debug!("Flagged {} function boundaries.", boundaries);
+    }
+
+    /// Flags runs of a repeated multi-byte pattern (e.g. `0xDEADBEEF`) of at least `min_run` bytes
+    /// within holes as alignment. Single-byte fill is left to the padding bytes.
+    #[instrument(skip_all)]
+    pub fn detect_fill_patterns(bytes: &mut [groundtruth::Byte], min_run: usize, explain: bool) {
+        // Guard: Detection is disabled
+        if min_run == 0 {
+            return;
+        }
+
+        let mut runs = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let run = (2..=MAX_FILL_PERIOD).find_map(|period| {
+                let length = fill_run_length(bytes, i, period);
+                (length >= min_run).then_some((period, length))
+            });
+
+            let (period, length) = match run {
+                Some(run) => run,
+                None => {
+                    i += 1;
+                    continue;
+                }
+            };
+
+            let pattern: String = bytes[i..i + period]
+                .iter()
+                .map(|b| format!("{:02x}", b.value))
+                .collect();
+
+            for byte in &mut bytes[i..i + length] {
+                byte.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
+
+                if explain {
+                    byte.explain(format!("ALIGNMENT from fill pattern {}", pattern));
+                }
+            }
+
+            debug!(
+                "Fill pattern {} at 0x{:x} (0x{:x} bytes).",
+                pattern, bytes[i].offset, length
+            );
+
+            runs += 1;
+            i += length;
+        }
+
+        info!("Found {} runs of fill patterns.", runs);
+    }
+
+    /// Length of the run of unflagged bytes starting at `start` which repeats its first `period`
+    /// bytes, in whole repetitions (at least two). Patterns of a single repeated byte do not count.
+    fn fill_run_length(bytes: &[groundtruth::Byte], start: usize, period: usize) -> usize {
+        let is_hole = |b: &groundtruth::Byte| b.get_flags().is_empty();
+
+        let pattern = match bytes.get(start..start + period) {
+            Some(pattern) if pattern.iter().all(is_hole) => pattern,
+            _ => return 0,
+        };
+
+        if pattern.iter().all(|b| b.value == pattern[0].value) {
+            return 0;
+        }
+
+        let length = bytes[start..]
+            .iter()
+            .enumerate()
+            .take_while(|(index, b)| is_hole(b) && b.value == pattern[index % period].value)
+            .count();
+
+        match length - length % period {
+            length if length >= 2 * period => length,
+            _ => 0,
+        }
+    }
+
+    /// Scans the holes for common function prologues and flags the first byte of every match as a
+    /// recovered function start. Candidates have to start a hole (e.g. behind padding) or be
+    /// aligned to 16 bytes, which keeps matches within data rare.
+    #[instrument(skip_all)]
+    pub fn detect_prologues(
+        bytes: &mut [groundtruth::Byte],
+        architecture: &groundtruth::ArchInfo,
+        explain: bool,
+    ) -> Vec<groundtruth::RecoveredFunction> {
+        let mut recovered = Vec::new();
+
+        // Guard: The known prologues are x86 code
+        if !architecture.family.is_x86() {
+            warn!(
+                "Prologue detection is not supported for {}, no functions recovered.",
+                architecture.machine_name
+            );
+            return recovered;
+        }
+
+        for hole in detect_holes(bytes) {
+            let values: Vec<u8> = bytes[hole.start as usize..=hole.end as usize]
+                .iter()
+                .map(|b| b.value)
+                .collect();
+
+            let mut i = 0;
+
+            while i < values.len() {
+                let start = hole.start as usize + i;
+
+                // Guard: Function starts within a hole are aligned
+                if i > 0 && !bytes[start].offset.is_multiple_of(FUNCTION_ALIGNMENT) {
+                    i += 1;
+                    continue;
+                }
+
+                let (prologue, length) = match match_prologue(&values[i..], architecture.bits) {
+                    Some(prologue) => prologue,
+                    None => {
+                        i += 1;
+                        continue;
+                    }
+                };
+
+                // Guard: The prologue must not overlap classified bytes
+                if !bytes[start..start + length]
+                    .iter()
+                    .all(|b| b.get_flags().is_empty())
+                {
+                    i += 1;
+                    continue;
+                }
+
+                for byte in &mut bytes[start..start + length] {
+                    byte.set_flags(vec![
+                        groundtruth::FLAG::CODE,
+                        groundtruth::FLAG::READABLE,
+                        groundtruth::FLAG::EXECUTABLE,
+                        groundtruth::FLAG::RECOVERED,
+                    ]);
+
+                    if explain {
+                        byte.explain(format!("CODE from {:?} prologue", prologue));
+                    }
+                }
+
+                bytes[start].set_flags(vec![
+                    groundtruth::FLAG::FUNCTION_START,
+                    groundtruth::FLAG::INSTRUCTION_START,
+                ]);
+
+                debug!(
+                    "Recovered function at 0x{:x} ({:?}).",
+                    bytes[start].offset, prologue
+                );
+
+                recovered.push(groundtruth::RecoveredFunction {
+                    address: bytes[start].offset,
+                    prologue,
+                });
+
+                i += length;
+            }
+        }
+
+        info!(
+            "Recovered {} functions from their prologues.",
+            recovered.len()
+        );
+
+        recovered
+    }
+
+    /// Matches the bytes against the known prologues and returns the prologue and its length. An
+    /// `endbr64`/`endbr32` in front of the prologue (CET) is part of the match.
+    fn match_prologue(bytes: &[u8], bits: u8) -> Option<(groundtruth::PROLOGUE, usize)> {
+        let skip = match bytes {
+            [0xf3, 0x0f, 0x1e, 0xfa | 0xfb, ..] => 4,
+            _ => 0,
+        };
+
+        let (prologue, length) = match (bits, &bytes[skip..]) {
+            (64, [0x55, 0x48, 0x89, 0xe5, ..]) | (64, [0x55, 0x48, 0x8b, 0xec, ..]) => {
+                (groundtruth::PROLOGUE::FRAME_POINTER, 4)
+            }
+            (64, [0x48, 0x83, 0xec, _, ..]) => (groundtruth::PROLOGUE::STACK_ALLOCATION, 4),
+            (64, [0x48, 0x81, 0xec, _, _, _, _, ..]) => {
+                (groundtruth::PROLOGUE::STACK_ALLOCATION, 7)
+            }
+            (32, [0x8b, 0xff, 0x55, 0x8b, 0xec, ..]) => (groundtruth::PROLOGUE::HOTPATCH, 5),
+            (32, [0x55, 0x89, 0xe5, ..]) | (32, [0x55, 0x8b, 0xec, ..]) => {
+                (groundtruth::PROLOGUE::FRAME_POINTER, 3)
+            }
+            (32, [0x83, 0xec, _, ..]) => (groundtruth::PROLOGUE::STACK_ALLOCATION, 3),
+            (32, [0x81, 0xec, _, _, _, _, ..]) => (groundtruth::PROLOGUE::STACK_ALLOCATION, 6),
+            _ => return None,
+        };
+
+        Some((prologue, skip + length))
+    }
+
+    /// Flags the hotpatch area in front of every function as alignment. The linker reserves it
+    /// (`/FUNCTIONPADMIN`) for the jump of a hotpatch: 5 bytes on x86 and 6 bytes on x64.
+    #[instrument(skip_all)]
+    pub fn detect_hotpatch_padding(
+        bytes: &mut [groundtruth::Byte],
+        functions: &[groundtruth::Function],
+        architecture: &groundtruth::ArchInfo,
+        explain: bool,
+    ) {
+        let size = match architecture.bits {
+            32 => 5,
+            _ => 6,
+        };
+
+        let mut padded = 0;
+
+        for function in functions {
+            let start = function.offset.saturating_sub(size);
+
+            for offset in start..function.offset {
+                let byte = match bytes.get_mut(offset as usize) {
+                    Some(byte) => byte,
+                    None => break,
+                };
+
+                // Guard: Only if this byte currently does not have any purpose
+                if byte.is_code() || byte.is_data() {
+                    continue;
+                }
+
+                byte.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
+                padded += 1;
+
+                if explain {
+                    byte.explain(format!("ALIGNMENT from hotpatch area of {}", function.name));
+                }
+            }
+        }
+
+        debug!("Flagged {} hotpatch padding bytes.", padded);
+    }
+
+    /// Classifies the bytes between consecutive functions. Expects flagging, disassembly and
+    /// alignment detection to be done.
+    #[instrument(skip_all)]
+    pub fn detect_gaps(
+        bytes: &[groundtruth::Byte],
+        functions: &[groundtruth::Function],
+        architecture: &groundtruth::ArchInfo,
+        disassembler: disassembler::DISASSEMBLER,
+    ) -> Vec<groundtruth::Gap> {
+        let mut gaps = Vec::new();
+
+        let mut ranges: Vec<(u64, u64)> = functions
+            .iter()
+            .map(|f| (f.offset, f.offset + f.size))
+            .filter(|(_start, end)| *end as usize <= bytes.len())
+            .collect();
+        ranges.sort();
+
+        for pair in ranges.windows(2) {
+            let (start, end) = (pair[0].1, pair[1].0);
+
+            // Guard: Overlapping or adjacent functions do not have a gap
+            if start >= end {
+                if start == end {
+                    gaps.push(groundtruth::Gap {
+                        start: bytes[start as usize].offset,
+                        end: bytes[start as usize].offset,
+                        size: 0,
+                        kind: groundtruth::GAP_KIND::EMPTY,
+                    });
+                }
+                continue;
+            }
+
+            let gap_bytes = &bytes[start as usize..end as usize];
+
+            let kind = if gap_bytes.iter().all(|b| b.is_alignment()) {
+                groundtruth::GAP_KIND::PADDING
+            } else {
+                // Gap contains code if (almost) all of it decodes (padding decodes as int3/nop)
+                let buffer: Vec<u8> = gap_bytes.iter().map(|b| b.value).collect();
+
+                if decodable_fraction(&buffer, architecture, disassembler) >= GAP_CODE_THRESHOLD {
+                    groundtruth::GAP_KIND::CODE
+                } else {
+                    groundtruth::GAP_KIND::DATA
+                }
+            };
+
+            gaps.push(groundtruth::Gap {
+                start: gap_bytes[0].offset,
+                end: gap_bytes[gap_bytes.len() - 1].offset,
+                size: end - start,
+                kind,
+            });
+        }
+
+        gaps
+    }
+
+    /// Returns the fraction of bytes which decode as instructions, skipping undecodable bytes.
+    fn decodable_fraction(
+        buffer: &[u8],
+        architecture: &groundtruth::ArchInfo,
+        disassembler: disassembler::DISASSEMBLER,
+    ) -> f64 {
+        let mut position = 0;
+        let mut decoded = 0;
+
+        while position < buffer.len() {
+            let decoded_length: u64 = match disassembler::disassemble(
+                buffer[position..].to_vec(),
+                architecture,
+                disassembler,
+            ) {
+                Ok(instructions) => instructions.iter().map(|i| i.length).sum(),
+                Err(_e) => 0,
+            };
+
+            decoded += decoded_length as usize;
+
+            // Skip the byte which could not be decoded
+            position += decoded_length as usize + 1;
+        }
+
+        decoded as f64 / buffer.len() as f64
+    }
+
+    pub fn detect_holes(bytes: &[groundtruth::Byte]) -> Vec<groundtruth::Hole> {
+        let mut holes = Vec::new();
+        let mut hole_size = 0;
+
+        for (offset, byte) in bytes.iter().enumerate() {
+            // Check if this byte has currently no flags at all (besides a boundary marker)
+            if !byte.is_identified() {
+                hole_size += 1;
+            } else {
+                if hole_size > 0 {
+                    holes.push(groundtruth::Hole {
+                        start: (offset - hole_size) as u64,
+                        end: (offset - 1) as u64,
+                        size: hole_size as u64,
+                    });
+                }
+                hole_size = 0;
+            }
+        }
+
+        // If the loop exited while detecting a new hole, that means a hole which shared its end with the buffer itself it will be lost. Recover it manually.
+        if hole_size > 0 {
+            holes.push(groundtruth::Hole {
+                start: (bytes.len() - hole_size) as u64,
+                end: (bytes.len() - 1) as u64,
+                size: hole_size as u64,
+            });
+        }
+
+        holes
+    }
+
+    /// Combines the analyses of the run into a single score of how hard the section is to
+    /// disassemble (see [`groundtruth::Difficulty`]).
+    #[instrument(skip_all)]
+    pub fn assess_difficulty(
+        bytes: &[groundtruth::Byte],
+        functions: &[groundtruth::Function],
+        instructions: &[groundtruth::Instruction],
+        mid_instruction_targets: &[groundtruth::MidInstructionTarget],
+        warnings: &groundtruth::Warnings,
+        weights: &config::DifficultyWeights,
+    ) -> groundtruth::Difficulty {
+        let ratio = |count: f64, total: f64| {
+            if total > 0.0 {
+                (count / total).min(1.0)
+            } else {
+                0.0
+            }
+        };
+
+        // Code/data classification of the classified bytes in order
+        let classified: Vec<bool> = bytes
+            .iter()
+            .filter(|b| b.is_code() || b.is_data())
+            .map(|b| b.is_data())
+            .collect();
+        let data = classified.iter().filter(|is_data| **is_data).count();
+        let transitions = classified.windows(2).filter(|w| w[0] != w[1]).count();
+
+        // Entropy of all holes weighted by their size
+        let holes = detect_holes(bytes);
+        let hole_bytes: u64 = holes.iter().map(|h| h.size).sum();
+        let hole_entropy: f64 = holes
+            .iter()
+            .map(|h| {
+                let values: Vec<u8> = bytes[h.start as usize..=h.end as usize]
+                    .iter()
+                    .map(|b| b.value)
+                    .collect();
+
+                holes::entropy(&values) * h.size as f64
+            })
+            .sum();
+
+        let branches = instructions.iter().filter(|i| i.target.is_some()).count();
+
+        let mut difficulty = groundtruth::Difficulty {
+            score: 0.0,
+            inline_data: ratio(data as f64, classified.len() as f64),
+            decode_failures: ratio(
+                warnings.count(groundtruth::WARNING::DECODE_FAILURE) as f64,
+                functions.len() as f64,
+            ),
+            hole_entropy: ratio(hole_entropy, hole_bytes as f64 * 8.0),
+            mid_instruction_targets: ratio(mid_instruction_targets.len() as f64, branches as f64),
+            transitions: ratio(64.0 * transitions as f64, classified.len() as f64),
+        };
+
+        let components = [
+            (weights.inline_data, difficulty.inline_data),
+            (weights.decode_failures, difficulty.decode_failures),
+            (weights.hole_entropy, difficulty.hole_entropy),
+            (
+                weights.mid_instruction_targets,
+                difficulty.mid_instruction_targets,
+            ),
+            (weights.transitions, difficulty.transitions),
+        ];
+        let total: f64 = components.iter().map(|(weight, _)| weight).sum();
+        let weighted: f64 = components
+            .iter()
+            .map(|(weight, value)| weight * value)
+            .sum();
+
+        difficulty.score = 100.0 * ratio(weighted, total);
+
+        info!(
+            "Disassembly difficulty: {:.1} (in-line data {:.2}, decode failures {:.2}, hole entropy {:.2}, mid-instruction targets {:.2}, transitions {:.2}).",
+            difficulty.score,
+            difficulty.inline_data,
+            difficulty.decode_failures,
+            difficulty.hole_entropy,
+            difficulty.mid_instruction_targets,
+            difficulty.transitions
+        );
+
+        difficulty
+    }
+
+    #[instrument(skip_all)]
+    pub fn verify_bytes(
+        bytes: &[groundtruth::Byte],
+        original: &[groundtruth::Byte],
+        text_section: &groundtruth::Section,
+        base: u64,
+    ) {
+        let mut mismatches = 0;
+
+        for byte in bytes {
+            // Translate rebased offset back to the file offset it was read from
+            let file_offset = byte.offset - base + text_section.raw_data_offset;
+
+            match original.get(file_offset as usize) {
+                Some(original_byte) if original_byte.value == byte.value => {}
+                Some(original_byte) => {
+                    error!(
+                        offset = byte.offset,
+                        file_offset,
+                        "Byte is 0x{:02x} but binary has 0x{:02x}.",
+                        byte.value,
+                        original_byte.value
+                    );
+                    mismatches += 1;
+                }
+                None => {
+                    error!(
+                        offset = byte.offset,
+                        file_offset, "Byte lies outside of the binary."
+                    );
+                    mismatches += 1;
+                }
+            }
+        }
+
+        if mismatches > 0 {
+            error!(
+                "Verification failed: {}/{} bytes do not match the binary.",
+                mismatches,
+                bytes.len()
+            );
+            process::exit(1);
+        }
+
+        info!(
+            "Verification passed: {} bytes match the binary.",
+            bytes.len()
+        );
+    }
+
+    /// Sorts functions (and their labels and data) and instructions by address so dumps of the same
+    /// input are identical. Ties are broken by segment and name.
+    pub fn sort_for_output(
+        functions: &mut [groundtruth::Function],
+        instructions: &mut [groundtruth::Instruction],
+    ) {
+        functions
+            .sort_by(|a, b| (a.offset, a.segment, &a.name).cmp(&(b.offset, b.segment, &b.name)));
+
+        for function in functions.iter_mut() {
+            function
+                .labels
+                .sort_by(|a, b| (a.offset, &a.name).cmp(&(b.offset, &b.name)));
+            function
+                .data
+                .sort_by(|a, b| (a.offset, &a.name).cmp(&(b.offset, &b.name)));
+        }
+
+        instructions.sort_by_key(|i| i.address);
+    }
+
+    /// Logs a summary of all warnings emitted while processing.
+    pub fn summarize_warnings(warnings: &groundtruth::Warnings) {
+        if warnings.is_empty() {
+            info!("Finished without warnings.");
+        } else {
+            warn!("Finished with warnings: {}.", warnings);
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::testing::{bytes, function, instruction};
+
+        /// Disassembles the functions as x64 with the default configuration.
+        fn disassemble_x64(
+            bytes: &mut [groundtruth::Byte],
+            functions: &[groundtruth::Function],
+            warnings: &mut groundtruth::Warnings,
+        ) -> Vec<groundtruth::Instruction> {
+            let config = config::Config::default();
+
+            disassemble(
+                bytes,
+                functions,
+                &groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64),
+                config.anomaly_threshold,
+                None,
+                config.disassembler,
+                warnings,
+            )
+        }
+
+        /// A function declaring 0x30 bytes whose only instruction ends after 4 bytes, with in-line
+        /// data at 0x8 and a label at 0x20.
+        fn oversized_function() -> (
+            Vec<groundtruth::Byte>,
+            Vec<groundtruth::Function>,
+            Vec<groundtruth::Instruction>,
+        ) {
+            let mut bytes = bytes(0x1000, &[0xCC; 0x40]);
+            let mut function = function("oversized", 0, 0x30);
+
+            function.data.push(groundtruth::Data {
+                name: "table".to_string(),
+                offset: 0x8,
+                segment: 1,
+                size: 4,
+                bytes: None,
+            });
+            function.labels.push(groundtruth::Label {
+                name: "tail".to_string(),
+                offset: 0x20,
+                segment: 1,
+            });
+
+            let functions = vec![function];
+            set_byte_flags(&mut bytes, &functions, true);
+            bytes[0].set_flags(vec![groundtruth::FLAG::INSTRUCTION_START]);
+            bytes[3].set_flags(vec![groundtruth::FLAG::INSTRUCTION_END]);
+
+            (bytes, functions, vec![instruction("push", 0x1000, 4)])
+        }
+
+        /// Executable code section with `size` bytes of raw data.
+        fn section(name: &str, size: u64) -> groundtruth::Section {
+            groundtruth::Section {
+                name: name.to_string(),
+                va: 0x1000,
+                raw_data_offset: if size > 0 { 0x400 } else { 0 },
+                raw_data_size: size,
+                flags: vec![groundtruth::FLAG::CODE, groundtruth::FLAG::EXECUTABLE],
+                overlaps: Vec::new(),
+            }
+        }
+
+        #[test]
+        fn detect_fill_patterns_flags_whole_repetitions_in_holes() {
+            // 0xDEADBEEF three times and a half, a run of a single byte, a pattern in code
+            let mut values = [0xDE, 0xAD, 0xBE, 0xEF].repeat(3);
+            values.extend([0xDE, 0xAD, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+            values.extend([0x12, 0x34].repeat(4));
+            let mut bytes = bytes(0x1000, &values);
+            for byte in &mut bytes[22..] {
+                byte.set_flags(vec![groundtruth::FLAG::CODE]);
+            }
+
+            detect_fill_patterns(&mut bytes, 8, false);
+
+            let alignment: Vec<usize> = (0..bytes.len())
+                .filter(|i| bytes[*i].is_alignment())
+                .collect();
+            assert_eq!(alignment, (0..12).collect::<Vec<usize>>());
+        }
+
+        #[test]
+        fn detect_fill_patterns_ignores_short_runs() {
+            let mut bytes = bytes(0x1000, &[0xDE, 0xAD, 0xBE, 0xEF].repeat(2));
+
+            detect_fill_patterns(&mut bytes, 16, false);
+
+            assert!(bytes.iter().all(|b| !b.is_alignment()));
+        }
+
+        #[test]
+        fn normalize_operands_replaces_rip_relative_displacements_in_both_syntaxes() {
+            let mut instructions = vec![
+                instruction("mov", 0x1000, 7),
+                instruction("movq", 0x1007, 7),
+                instruction("add", 0x100e, 4),
+            ];
+            instructions[0].operand = "rax, qword ptr [rip + 0x2fe1]".to_string();
+            instructions[1].operand = "0x2fe1(%rip), %rax".to_string();
+            instructions[2].operand = "rsp, 0x28".to_string();
+
+            normalize_operands(&mut instructions);
+
+            assert_eq!(instructions[0].operand, "rax, qword ptr [rip + 0xADDR]");
+            assert_eq!(instructions[1].operand, "0xADDR(%rip), %rax");
+            assert_eq!(
+                instructions[1].raw_operand.as_deref(),
+                Some("0x2fe1(%rip), %rax")
+            );
+            assert_eq!(instructions[2].operand, "rsp, 0x28");
+            assert!(instructions[2].raw_operand.is_none());
+        }
+
+        /// A function of 0x10 bytes followed by a hole with an endbr64 and frame pointer prologue
+        /// at its start, a stack allocation at an unaligned and one at an aligned address.
+        fn prologues() -> Vec<groundtruth::Byte> {
+            let mut values = vec![0x90; 0x10];
+            values.extend([0xF3, 0x0F, 0x1E, 0xFA, 0x55, 0x48, 0x89, 0xE5]);
+            values.extend([0x00, 0x00, 0x48, 0x83, 0xEC, 0x28, 0x00, 0x00]);
+            values.extend([0x48, 0x81, 0xEC, 0x00, 0x01, 0x00, 0x00, 0xC3]);
+
+            let mut bytes = bytes(0x1000, &values);
+            for byte in &mut bytes[..0x10] {
+                byte.set_flags(vec![groundtruth::FLAG::CODE]);
+            }
+            bytes
+        }
+
+        #[test]
+        fn detect_prologues_recovers_hole_starts_and_aligned_prologues() {
+            let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+            let mut bytes = prologues();
+
+            let recovered = detect_prologues(&mut bytes, &architecture, false);
+
+            let found: Vec<(u64, groundtruth::PROLOGUE)> =
+                recovered.iter().map(|f| (f.address, f.prologue)).collect();
+            assert_eq!(
+                found,
+                vec![
+                    (0x1010, groundtruth::PROLOGUE::FRAME_POINTER),
+                    (0x1020, groundtruth::PROLOGUE::STACK_ALLOCATION),
+                ]
+            );
+            assert!(bytes[0x10].is_function_start());
+            assert!(bytes[0x10..0x18].iter().all(|b| b.is_code()));
+            assert!(bytes[0x1A..0x1E].iter().all(|b| !b.is_code()));
+        }
+
+        #[test]
+        fn detect_prologues_only_applies_to_x86() {
+            let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::MIPS);
+            let mut bytes = prologues();
+
+            assert!(detect_prologues(&mut bytes, &architecture, false).is_empty());
+        }
+
+        #[test]
+        fn select_code_section_skips_sections_without_raw_data() {
+            let sections = [section(".text", 0), section(".text$mn", 0x100)];
+
+            let selected = select_code_section(&sections, &[]).unwrap();
+
+            assert_eq!(selected.name, ".text$mn");
+        }
+
+        #[cfg(unix)]
+        #[test]
+        fn file_stem_of_a_path_which_is_not_utf8() {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+
+            let path = Path::new(OsStr::from_bytes(b"/corpus/bin\xFFary.exe"));
+
+            assert_eq!(file_stem(path), "bin\u{FFFD}ary");
+        }
+
+        #[test]
+        fn file_stem_without_file_name() {
+            assert_eq!(file_stem(Path::new("/")), "binary");
+        }
+
+        #[test]
+        fn largest_executable_section_without_text_section() {
+            let mut data = section(".data", 0x400);
+            data.flags = vec![groundtruth::FLAG::READABLE, groundtruth::FLAG::WRITEABLE];
+            let sections = [
+                section(".init", 0x20),
+                section(".code", 0x200),
+                data,
+                section(".fini", 0x10),
+            ];
+
+            let selected = select_code_section(&sections, &[]).unwrap();
+            assert_eq!(selected.name, ".code");
+
+            let exclude = vec![".code".to_string()];
+            let selected = select_code_section(&sections, &exclude).unwrap();
+            assert_eq!(selected.name, ".init");
+        }
+
+        #[test]
+        fn code_sections_skip_excluded_sections() {
+            let sections = [
+                section(".text$di", 0x10),
+                section(".text$mn", 0x100),
+                section(".text$x", 0x20),
+            ];
+            let exclude = vec![".text$di".to_string()];
+
+            let names: Vec<String> = code_sections(&sections, &exclude)
+                .into_iter()
+                .map(|s| s.name)
+                .collect();
+
+            assert_eq!(names, vec![".text$mn", ".text$x"]);
+            assert_eq!(
+                select_code_section(&sections, &exclude).unwrap().name,
+                ".text$mn"
+            );
+            assert_eq!(
+                select_code_section(&sections, &[]).unwrap().name,
+                ".text$di"
+            );
+        }
+
+        #[test]
+        fn excluding_every_code_section_leaves_no_code_section() {
+            let mut init = section(".init", 0x10);
+            init.flags = vec![groundtruth::FLAG::READABLE];
+            let sections = [section(".text", 0x100), init];
+            let exclude = vec![".text".to_string()];
+
+            assert!(code_sections(&sections, &exclude).is_empty());
+            assert!(select_code_section(&sections, &exclude).is_none());
+        }
+
+        #[test]
+        fn no_code_section_without_raw_data() {
+            let sections = [section(".text", 0), section(".init", 0)];
+
+            assert!(select_code_section(&sections, &[]).is_none());
+        }
+
+        #[test]
+        fn detect_alignment_bytes_flags_only_the_padding_bytes() {
+            let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+            let mut bytes = bytes(0x1000, &[0xCC, 0x00, 0x90, 0x00]);
+
+            detect_alignment_bytes(
+                &mut bytes,
+                &architecture,
+                disassembler::DISASSEMBLER::CAPSTONE,
+                &[0x00, 0xCC],
+                false,
+                false,
+            );
+
+            let alignment: Vec<bool> = bytes.iter().map(|b| b.is_alignment()).collect();
+            assert_eq!(alignment, vec![true, true, false, true]);
+        }
+
+        #[test]
+        fn function_starting_with_data_has_no_function_start() {
+            // int3 (in-line data at offset 0), push rbp, pop rbp, ret
+            let mut bytes = bytes(0x1000, &[0xCC, 0x55, 0x5D, 0xC3]);
+            let mut function = function("data_first", 0, 4);
+            function.data.push(groundtruth::Data {
+                name: String::new(),
+                offset: 0,
+                segment: 1,
+                size: 1,
+                bytes: None,
+            });
+            let functions = vec![function];
+            let mut warnings = groundtruth::Warnings::default();
+
+            set_byte_flags(&mut bytes, &functions, false);
+            disassemble_x64(&mut bytes, &functions, &mut warnings);
+
+            assert!(!bytes[0].is_function_start());
+            assert!(bytes[3].is_function_end());
+            assert_eq!(
+                warnings.count(groundtruth::WARNING::FUNCTION_WITHOUT_CODE),
+                1
+            );
+        }
+
+        #[test]
+        fn instructions_behind_in_line_data_start_at_their_address() {
+            // push rbp, in-line data, pop rbp, ret
+            let mut bytes = bytes(0x1000, &[0x55, 0xAA, 0xAA, 0x5D, 0xC3]);
+            let mut function = function("with_data", 0, 5);
+            function.data.push(groundtruth::Data {
+                name: String::new(),
+                offset: 1,
+                segment: 1,
+                size: 2,
+                bytes: None,
+            });
+            let functions = vec![function];
+            let mut warnings = groundtruth::Warnings::default();
+
+            set_byte_flags(&mut bytes, &functions, false);
+            let instructions = disassemble_x64(&mut bytes, &functions, &mut warnings);
+
+            let addresses: Vec<u64> = instructions.iter().map(|i| i.address).collect();
+            assert_eq!(addresses, vec![0x1000, 0x1003, 0x1004]);
+
+            let starts: Vec<bool> = bytes.iter().map(|b| b.is_instruction_start()).collect();
+            assert_eq!(starts, vec![true, false, false, true, true]);
+        }
+
+        #[test]
+        fn instructions_ending_outside_of_the_section_are_skipped() {
+            // nop, ret, the in-line data is declared behind the end of the section
+            let mut bytes = bytes(0x1000, &[0x90, 0xC3, 0xCC, 0xCC]);
+            let mut function = function("beyond", 0, 2);
+            function.data.push(groundtruth::Data {
+                name: String::new(),
+                offset: 0,
+                segment: 1,
+                size: 8,
+                bytes: None,
+            });
+            let mut warnings = groundtruth::Warnings::default();
+
+            let instructions = disassemble_x64(&mut bytes, &[function], &mut warnings);
+
+            assert!(instructions.is_empty());
+            assert!(bytes.iter().all(|b| !b.is_instruction_start()));
+            assert_eq!(
+                warnings.count(groundtruth::WARNING::INSTRUCTION_OUT_OF_BOUNDS),
+                2
+            );
+        }
+
+        #[test]
+        fn functions_covered_by_in_line_data_are_dropped() {
+            let mut bytes = bytes(0x1000, &[0x00, 0x10, 0x00, 0x00, 0xC3]);
+            let mut table = function("table", 0, 4);
+            table.data.push(groundtruth::Data {
+                name: "entries".to_string(),
+                offset: 0,
+                segment: 1,
+                size: 4,
+                bytes: None,
+            });
+            let mut functions = vec![table, function("code", 4, 1)];
+            let mut warnings = groundtruth::Warnings::default();
+
+            set_byte_flags(&mut bytes, &functions, false);
+            drop_data_only_functions(&bytes, &mut functions, &mut warnings);
+
+            assert_eq!(functions.len(), 1);
+            assert_eq!(functions[0].name, "code");
+            assert!(bytes[..4].iter().all(|b| b.is_data() && !b.is_code()));
+            assert_eq!(warnings.count(groundtruth::WARNING::DATA_ONLY_FUNCTION), 1);
+        }
+
+        #[test]
+        fn instruction_cut_off_by_the_function_size_is_decoded() {
+            // push rbp, mov rbp, rsp with a declared size of 3 bytes, int3
+            let mut bytes = bytes(0x1000, &[0x55, 0x48, 0x89, 0xE5, 0xCC]);
+            let functions = vec![function("short", 0, 3)];
+            let mut warnings = groundtruth::Warnings::default();
+
+            set_byte_flags(&mut bytes, &functions, false);
+            let instructions = disassemble_x64(&mut bytes, &functions, &mut warnings);
+
+            let decoded: Vec<(u64, u64)> =
+                instructions.iter().map(|i| (i.address, i.length)).collect();
+            assert_eq!(decoded, vec![(0x1000, 1), (0x1001, 3)]);
+            assert!(bytes[3].is_code());
+            assert!(bytes[3].flags.contains(&groundtruth::FLAG::INSTRUCTION_END));
+            assert!(bytes[4].get_flags().is_empty());
+            assert_eq!(
+                warnings.count(groundtruth::WARNING::STRADDLING_INSTRUCTION),
+                1
+            );
+            assert_eq!(warnings.count(groundtruth::WARNING::DECODE_FAILURE), 0);
+        }
+
+        #[test]
+        fn in_line_data_is_not_decoded_as_a_straddling_instruction() {
+            // push rbp, the first byte of mov rbp, rsp followed by in-line data
+            let mut bytes = bytes(0x1000, &[0x55, 0x48, 0x89, 0xE5]);
+            let mut function = function("with_data", 0, 3);
+            function.data.push(groundtruth::Data {
+                name: String::new(),
+                offset: 2,
+                segment: 1,
+                size: 1,
+                bytes: None,
+            });
+            let functions = vec![function];
+            let mut warnings = groundtruth::Warnings::default();
+
+            set_byte_flags(&mut bytes, &functions, false);
+            let instructions = disassemble_x64(&mut bytes, &functions, &mut warnings);
+
+            assert_eq!(instructions.len(), 1);
+            assert!(!bytes[3].is_code());
+            assert_eq!(
+                warnings.count(groundtruth::WARNING::STRADDLING_INSTRUCTION),
+                0
+            );
+        }
+
+        #[test]
+        fn function_without_decodable_instruction_is_reported() {
+            // push es and pop es are invalid in 64-bit mode
+            let mut bytes = bytes(0x1000, &[0x06, 0x07, 0x90, 0x06]);
+            let functions = vec![function("invalid", 0, 2), function("partial", 2, 2)];
+            let mut warnings = groundtruth::Warnings::default();
+
+            set_byte_flags(&mut bytes, &functions, false);
+            let instructions = disassemble_x64(&mut bytes, &functions, &mut warnings);
+
+            // Only the nop of partial is decoded, the bytes of invalid stay code
+            assert_eq!(instructions.len(), 1);
+            assert_eq!(instructions[0].address, 0x1002);
+            assert!(bytes[0].is_code() && !bytes[0].is_instruction_start());
+            assert_eq!(warnings.count(groundtruth::WARNING::DECODE_FAILURE), 2);
+        }
+
+        #[test]
+        fn branch_target_reached_by_fallthrough_is_a_join() {
+            // push rbp, je 0x1004, nop, pop rbp (target), ret
+            let mut bytes = bytes(0x1000, &[0x55, 0x74, 0x01, 0x90, 0x5D, 0xC3]);
+            let functions = vec![function("branch", 0, 6)];
+            let mut warnings = groundtruth::Warnings::default();
+
+            set_byte_flags(&mut bytes, &functions, false);
+            let mut instructions = disassemble_x64(&mut bytes, &functions, &mut warnings);
+            detect_basic_blocks(&mut bytes, &mut instructions);
+
+            let flagged = |flag: groundtruth::FLAG| -> Vec<u64> {
+                instructions
+                    .iter()
+                    .filter(|i| i.flags.contains(&flag))
+                    .map(|i| i.address)
+                    .collect()
+            };
+            assert_eq!(
+                flagged(groundtruth::FLAG::BLOCK_START),
+                vec![0x1000, 0x1003, 0x1004]
+            );
+            assert_eq!(flagged(groundtruth::FLAG::BLOCK_JOIN), vec![0x1004]);
+            assert!(bytes[4].flags.contains(&groundtruth::FLAG::BLOCK_JOIN));
+        }
+
+        #[test]
+        fn sort_for_output_orders_by_address_then_segment_and_name() {
+            let mut functions = vec![
+                function("b", 0x10, 4),
+                function("a", 0x10, 4),
+                function("first", 0, 4),
+            ];
+            functions[1].segment = 2;
+            functions[2].labels = ["z", "y"]
+                .iter()
+                .map(|name| groundtruth::Label {
+                    name: name.to_string(),
+                    offset: 2,
+                    segment: 1,
+                })
+                .collect();
+            let mut instructions =
+                vec![instruction("ret", 0x1010, 1), instruction("nop", 0x1000, 1)];
+
+            sort_for_output(&mut functions, &mut instructions);
+
+            let names: Vec<&str> = functions.iter().map(|f| f.name.as_str()).collect();
+            assert_eq!(names, vec!["first", "b", "a"]);
+            let labels: Vec<&str> = functions[0]
+                .labels
+                .iter()
+                .map(|l| l.name.as_str())
+                .collect();
+            assert_eq!(labels, vec!["y", "z"]);
+            assert_eq!(instructions[0].address, 0x1000);
+        }
+
+        #[test]
+        fn reconcile_function_sizes_reports_without_applying() {
+            let (mut bytes, mut functions, mut instructions) = oversized_function();
+
+            let discrepancies =
+                reconcile_function_sizes(&mut bytes, &mut functions, &mut instructions, false);
+
+            assert_eq!(discrepancies.len(), 1);
+            assert_eq!(discrepancies[0].declared_size, 0x30);
+            assert_eq!(discrepancies[0].disassembled_size, 4);
+            assert!(!discrepancies[0].applied);
+            assert_eq!(functions[0].size, 0x30);
+            assert!(bytes[0x10].is_code());
+        }
+
+        #[test]
+        fn reconcile_function_sizes_keeps_in_line_data_when_applied() {
+            let (mut bytes, mut functions, mut instructions) = oversized_function();
+
+            let discrepancies =
+                reconcile_function_sizes(&mut bytes, &mut functions, &mut instructions, true);
+
+            assert!(discrepancies[0].applied);
+            assert_eq!(functions[0].size, 4);
+            assert!(bytes[3].is_function_end());
+
+            // The data behind the new end stays data, the code behind it is unflagged
+            assert_eq!(bytes[0x8].flags, vec![groundtruth::FLAG::DATA]);
+            assert!(!bytes[0x8].provenance.is_empty());
+            assert!(bytes[0x10].flags.is_empty());
+            assert!(bytes[0x10].provenance.is_empty());
+
+            // Neither the data nor the label belong to the function any longer
+            assert!(functions[0].data.is_empty());
+            assert!(functions[0].labels.is_empty());
+        }
+    }
+}
+
+/// Stages shared by the PE and ELF pipelines. Both only implement the format specific bits (the
+/// symbol source, rebasing, reading the binary and writing the outputs).
+pub mod pipeline {
+    use std::process;
+    use std::time::Duration;
+    use tracing::{debug, error, warn};
+
+    use crate::b2g::common;
+    use crate::config;
+    use crate::disassembler;
+    use crate::dumper;
+    use crate::groundtruth;
+    use crate::repl;
+
+    /// Borrows the parts of a pipeline the shared stages work on.
+    pub struct State<'a> {
+        pub config: &'a config::Config,
+        /// Architecture of the symbol source.
+        pub architecture: &'a groundtruth::ArchInfo,
+        pub sections: &'a [groundtruth::Section],
+        pub bytes: &'a mut Vec<groundtruth::Byte>,
+        pub functions: &'a mut Vec<groundtruth::Function>,
+        pub instructions: &'a mut Vec<groundtruth::Instruction>,
+        pub gaps: &'a mut Vec<groundtruth::Gap>,
+        pub size_discrepancies: &'a mut Vec<groundtruth::SizeDiscrepancy>,
+        pub mid_instruction_targets: &'a mut Vec<groundtruth::MidInstructionTarget>,
+        pub recovered_functions: &'a mut Vec<groundtruth::RecoveredFunction>,
+        pub noreturn_functions: &'a mut Vec<groundtruth::NoReturnFunction>,
+        pub data_sections: &'a mut Vec<groundtruth::DataSection>,
+        pub difficulty: &'a mut groundtruth::Difficulty,
+        pub warnings: &'a mut groundtruth::Warnings,
+    }
+
+    pub trait Pipeline {
+        fn state(&mut self) -> State<'_>;
+
+        /// Offset of the first byte of the code section after rebasing.
+        fn base(&self, text_section: &groundtruth::Section) -> u64;
+
+        /// Reads the byte vector of the original binary.
+        fn read_binary(&self) -> Result<Vec<groundtruth::Byte>, &'static str>;
+
+        fn create_dump(&self) -> dumper::Dump;
+
+        /// Writes the outputs of the configured format.
+        fn write_outputs(&self);
+
+        /// Logs the symbols only known to the symbol source (e.g. thunks).
+        fn print_symbols(&self) {}
+
+        /// Data symbols of the symbol source with addresses of the byte vector.
+        fn data_symbols(&self) -> Vec<groundtruth::DataSymbol>;
+
+        /// Selects the code section and trims (and rebases) the byte vector to it.
+        fn select_code_section(&mut self) -> groundtruth::Section {
+            let state = self.state();
+
+            let text_section =
+                match common::select_code_section(state.sections, &state.config.exclude_sections) {
+                    Some(text_section) => text_section,
+                    None => {
+                        error!("Binary does not have a text section with raw data!");
+                        process::exit(1);
+                    }
+                };
+
+            debug!(
+                ".text section identified (start: {:x}, size: {:x}, va: {:x}).",
+                text_section.raw_data_offset, text_section.raw_data_size, text_section.va
+            );
+
+            // Trim byte vector (we only need the data of text section) that means cut before raw
+            // data start and after raw data end
+            common::trim_byte_vector(
+                state.bytes,
+                text_section.raw_data_offset,
+                text_section.raw_data_offset + text_section.raw_data_size,
+            );
+
+            let base = self.base(&text_section);
+            common::rebase_byte_vector(self.state().bytes, base);
+
+            text_section
+        }
+
+        /// Flags the functions as code, disassembles them and analyzes the instructions.
+        fn classify_code(&mut self) {
+            let state = self.state();
+
+            // Set byte flags (code/data is already known)
+            common::set_byte_flags(state.bytes, state.functions, state.config.explain);
+
+            // Functions which are completely covered by their in-line data are no functions
+            common::drop_data_only_functions(state.bytes, state.functions, state.warnings);
+
+            // Disassemble code bytes (functions)
+            *state.instructions = common::disassemble(
+                state.bytes,
+                state.functions,
+                state.architecture,
+                state.config.anomaly_threshold,
+                state.config.function_timeout.map(Duration::from_millis),
+                state.config.disassembler,
+                state.warnings,
+            );
+            common::check_flag_consistency(state.bytes);
+
+            // Compare declared function sizes with the disassembly
+            *state.size_discrepancies = common::reconcile_function_sizes(
+                state.bytes,
+                state.functions,
+                state.instructions,
+                state.config.trust_disassembly,
+            );
+
+            // Flag the starts of basic blocks and joins
+            common::detect_basic_blocks(state.bytes, state.instructions);
+
+            // Look for branches into the middle of instructions
+            *state.mid_instruction_targets = common::detect_mid_instruction_targets(
+                state.bytes,
+                state.instructions,
+                state.warnings,
+            );
+
+            // Functions without a return possibly never return
+            *state.noreturn_functions = common::detect_noreturn_functions(
+                state.bytes,
+                state.functions,
+                state.instructions,
+                state.warnings,
+            );
+
+            // Describe the operands while the instructions still hold their original bytes
+            if state.config.detailed_operands {
+                disassembler::describe_operands(state.instructions, state.architecture);
+            }
+
+            // Render the instruction text, the user looks for instructions in the chosen syntax
+            disassembler::render_syntax(
+                state.instructions,
+                state.architecture,
+                state.config.syntax,
+            );
+
+            // Flag instructions the user is looking for
+            common::flag_suspicious_instructions(
+                state.bytes,
+                state.instructions,
+                &state.config.flag_mnemonics,
+            );
+
+            // Make the instruction text independent of the code location
+            if state.config.normalize_operands {
+                common::normalize_operands(state.instructions);
+            }
+        }
+
+        /// Classifies the bytes which are neither code nor data.
+        fn classify_remaining(&mut self) {
+            let state = self.state();
+
+            // Without functions the whole section is a single hole, disassembling it for
+            // multi-byte nops would be wasted work on a mismatched dump
+            let resolved = !state.functions.is_empty();
+
+            if !resolved {
+                warn!(
+                    "0 functions resolved: The dump has no symbols within the code section, all \
+                     {} bytes stay unknown. Check that the dump belongs to the binary.",
+                    state.bytes.len()
+                );
+                state.warnings.report(groundtruth::Diagnostic::warning(
+                    groundtruth::WARNING::NO_FUNCTIONS,
+                    "The dump has no symbols within the code section.".to_string(),
+                ));
+            }
+
+            // Detect alignment/filler bytes
+            common::detect_alignment_bytes(
+                state.bytes,
+                state.architecture,
+                state.config.disassembler,
+                &state.config.padding_bytes,
+                resolved,
+                state.config.explain,
+            );
+
+            // Detect repeated multi-byte fill patterns
+            common::detect_fill_patterns(
+                state.bytes,
+                state.config.fill_min_run,
+                state.config.explain,
+            );
+
+            // Propose function starts the symbol source does not know
+            if state.config.detect_prologues {
+                *state.recovered_functions =
+                    common::detect_prologues(state.bytes, state.architecture, state.config.explain);
+
+                for function in state.recovered_functions.iter() {
+                    state.warnings.report(
+                        groundtruth::Diagnostic::info(format!(
+                            "Function start recovered from its prologue ({:?}).",
+                            function.prologue
+                        ))
+                        .at(function.address, function.address),
+                    );
+                }
+            }
+
+            // Classify the bytes between functions
+            *state.gaps = common::detect_gaps(
+                state.bytes,
+                state.functions,
+                state.architecture,
+                state.config.disassembler,
+            );
+
+            // Detect end of section
+            if !state.config.no_truncate {
+                common::detect_end_of_section(state.bytes);
+            }
+
+            // Rate how hard the section is to disassemble
+            *state.difficulty = common::assess_difficulty(
+                state.bytes,
+                state.functions,
+                state.instructions,
+                state.mid_instruction_targets,
+                state.warnings,
+                &state.config.difficulty_weights,
+            );
+
+            // Mark the byte after every function, last since it identifies nothing
+            common::flag_function_boundaries(state.bytes, state.functions, state.config.explain);
+        }
+
+        /// Maps the data sections next to the code section (see `--include-data-sections`).
+        fn classify_data_sections(&mut self) {
+            // The byte vector only holds the code section
+            let original = match self.read_binary() {
+                Ok(byte_vector) => byte_vector,
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            let symbols = self.data_symbols();
+            let state = self.state();
+
+            *state.data_sections = common::classify_data_sections(
+                &original,
+                state.sections,
+                &symbols,
+                state.config.explain,
+            );
+        }
+
+        /// Checks that trimming/rebasing did not corrupt any byte values.
+        fn verify_bytes(&mut self, text_section: &groundtruth::Section) {
+            // Re-read the binary, the byte vector has been trimmed and rebased since
+            let original = match self.read_binary() {
+                Ok(byte_vector) => byte_vector,
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            let base = self.base(text_section);
+            common::verify_bytes(self.state().bytes, &original, text_section, base);
+        }
+
+        /// Restricts the mapping to the selected function, writes the outputs and drops into the
+        /// interactive prompt.
+        fn finish(&mut self, text_section: &groundtruth::Section) {
+            let default_base = self.base(text_section);
+            let state = self.state();
+
+            // Only keep the bytes of the selected function
+            let base = match state.config.function {
+                Some(_) => common::restrict_to_function(state.bytes, state.functions),
+                None => default_base,
+            };
+
+            // Keep the dumps of the same input identical
+            common::sort_for_output(state.functions, state.instructions);
+
+            // Create debug print
+            self.print();
+
+            // Create final mapping
+            self.write_outputs();
+
+            // Give a quick health check of the run
+            let state = self.state();
+            common::summarize_warnings(state.warnings);
+
+            // Drop into interactive prompt (function offsets are relative to the rebased section)
+            if state.config.repl {
+                repl::run(&self.create_dump(), base);
+            }
+        }
+
+        fn print(&mut self) {
+            let state = self.state();
+
+            debug!("######## META ###########");
+            debug!("{}", state.architecture);
+
+            debug!("######## SECTIONS #########");
+            for section in state.sections {
+                debug!("{:x?}", section);
+            }
+
+            debug!("######## FUNCTIONS #########");
+            for function in state.functions.iter() {
+                debug!("{:x?}", function);
+            }
+
+            debug!("### DATA IN FUNCTION ###");
+            for function in state.functions.iter().filter(|f| !f.data.is_empty()) {
+                debug!(
+                    "{:?} {:x?} {:x?}",
+                    function.name, function.offset, function.size
+                );
+                for data in &function.data {
+                    debug!("\t{:x?}", data);
+                }
+            }
+
+            self.print_symbols();
+
+            let state = self.state();
+
+            debug!("######## GAPS #########");
+            for gap in state.gaps.iter() {
+                debug!("{:x?}", gap);
+            }
+
+            let holes = common::detect_holes(state.bytes);
+            debug!("######## HOLES #########");
+            let mut unknown_bytes = 0;
+            for hole in holes {
+                debug!("{:x?}", hole);
+                unknown_bytes += hole.size;
+            }
+
+            debug!("####### COUNT ########");
+            debug!("Functions: {}", state.functions.len());
+
+            debug!("##### STATISTICS ######");
+            debug!(
+                "Identified bytes {:.2}/{:.2} ({:.2}%)",
+                (state.bytes.len() as u64 - unknown_bytes),
+                state.bytes.len(),
+                100.0 * (state.bytes.len() as u64 - unknown_bytes) as f64
+                    / state.bytes.len() as f64
+            );
+            debug!("Tail: 0x{:x}", state.bytes.len())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::testing;
+
+        /// Every instruction starts at a byte flagged as its start and holds the bytes found at
+        /// its address, so trimming and rebasing happened before the flags were set.
+        fn assert_flags_follow_instructions(
+            bytes: &[groundtruth::Byte],
+            instructions: &[groundtruth::Instruction],
+        ) {
+            let base = bytes.first().unwrap().offset;
+            assert!(!instructions.is_empty());
+
+            for instruction in instructions {
+                let start = (instruction.address - base) as usize;
+                let end = start + instruction.length as usize;
+
+                assert!(bytes[start].is_instruction_start());
+                assert!(bytes[start..end].iter().all(|b| b.is_code()));
+
+                let values: Vec<u8> = bytes[start..end].iter().map(|b| b.value).collect();
+                assert_eq!(values, instruction.bytes, "0x{:x}", instruction.address);
+            }
+        }
+
+        #[test]
+        fn pe_flags_the_trimmed_and_rebased_bytes() {
+            let dump = include_str!("../fixtures/selftest/pe.pdb.yaml");
+            let binary = include_bytes!("../fixtures/selftest/pe.exe");
+            let pe = testing::process_pe("pipeline-pe", dump, binary, testing::config());
+
+            assert_flags_follow_instructions(&pe.bytes, &pe.instructions);
+        }
+
+        #[test]
+        fn elf_flags_the_trimmed_and_rebased_bytes() {
+            let dump = include_str!("../fixtures/selftest/elf.obj.yaml");
+            let binary = include_bytes!("../fixtures/selftest/elf");
+            let elf = testing::process_elf("pipeline-elf", dump, binary, testing::config());
+
+            assert_flags_follow_instructions(&elf.bytes, &elf.instructions);
+
+            // Function offsets are relative to the code section like the offsets of a PDB
+            let size = elf.bytes.len() as u64;
+            assert!(elf
+                .dwarf
+                .functions
+                .iter()
+                .all(|f| f.offset + f.size <= size));
+        }
+    }
+}
+
 pub mod pe {
-    use log::{debug, error, info, warn};
+    use std::collections::BTreeMap;
+    use std::fs;
     use std::path;
     use std::process;
+    use tracing::{debug, error, info, instrument, warn};
 
+    use crate::b2g::common;
+    use crate::b2g::pipeline::{Pipeline, State};
+    use crate::config;
     use crate::disassembler;
     use crate::dumper;
     use crate::groundtruth;
     use crate::parser;
     use crate::pe;
 
+    /// Maximum size of the tail merge helper of a delay loaded DLL, which saves the argument
+    /// registers, calls `__delayLoadHelper2` and jumps to the resolved import.
+    const TAIL_MERGE_MAX_SIZE: usize = 256;
+
+    /// Number of symbol names given as an example in summaries.
+    const SAMPLE_SIZE: usize = 5;
+
     pub struct PE {
-        pub architecture: groundtruth::ARCHITECTURE,
+        pub architecture: groundtruth::ArchInfo,
+        pub config: config::Config,
         pub file_name: String,
+        /// Original contents of the binary.
+        pub binary: Vec<u8>,
         pub pdb: groundtruth::PDB,
         pub sections: Vec<groundtruth::Section>,
         pub bytes: Vec<groundtruth::Byte>,
         pub instructions: Vec<groundtruth::Instruction>,
+        pub gaps: Vec<groundtruth::Gap>,
+        pub imports: Vec<groundtruth::Import>,
+        pub label_to_function: Vec<groundtruth::LabelMapping>,
+        /// PDB segments and the sections they refer to.
+        pub segment_to_section: Vec<groundtruth::SegmentMapping>,
+        /// Symbols in none of the processed sections (see `--list-symbols-not-in-section`).
+        pub symbols_not_in_section: Vec<groundtruth::UnattributedSymbol>,
+        /// In-line data recognized as jump tables.
+        pub jump_tables: Vec<groundtruth::JumpTable>,
+        pub size_discrepancies: Vec<groundtruth::SizeDiscrepancy>,
+        pub mid_instruction_targets: Vec<groundtruth::MidInstructionTarget>,
+        /// Function starts recovered from their prologues.
+        pub recovered_functions: Vec<groundtruth::RecoveredFunction>,
+        /// Functions without a return instruction.
+        pub noreturn_functions: Vec<groundtruth::NoReturnFunction>,
+        pub difficulty: groundtruth::Difficulty,
+        /// Data sections mapped by `--include-data-sections`.
+        pub data_sections: Vec<groundtruth::DataSection>,
+        /// Address space of the outputs.
+        pub address_space: dumper::AddressSpace,
+        /// Warnings emitted while processing.
+        pub warnings: groundtruth::Warnings,
     }
 
     impl PE {
-        pub fn new(path_to_yaml: &str, path_to_pe: &str) -> Self {
+        pub fn new(path_to_yaml: &str, path_to_pe: &str, config: config::Config) -> Self {
             // Grab filename from path
-            let file_name = path::Path::new(path_to_pe)
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_string();
+            let file_name = common::file_stem(path::Path::new(path_to_pe));
 
-            // Retrieve architecture from PE header
-            let architecture = match pe::get_architecture(path_to_pe) {
-                Ok(architecture) => architecture,
-                Err(e) => {
-                    error!("{}", e);
+            // Several dumps can be given as a comma-separated list
+            let mut dump_yamls = Vec::new();
+
+            for path in path_to_yaml.split(',') {
+                match parser::yaml::pdb::read_pdb(path) {
+                    Ok(dump_yaml) => dump_yamls.push(dump_yaml),
+                    Err(_e) => {
+                        error!("Could not read file {}!", path);
+                        process::exit(1);
+                    }
+                }
+            }
+
+            let binary = match fs::read(path_to_pe) {
+                Ok(binary) => binary,
+                Err(_e) => {
+                    error!("Could not read file {}!", path_to_pe);
                     process::exit(1);
                 }
             };
 
-            // Collect symbols from PDB
-            let pdb = match parser::yaml::pdb::load_pdb(path_to_yaml) {
-                Ok(pdb) => pdb,
+            match PE::from_buffers(&dump_yamls, &binary, config) {
+                Ok(pe) => PE { file_name, ..pe },
                 Err(e) => {
                     error!("{}", e);
                     process::exit(1);
                 }
-            };
+            }
+        }
+
+        /// Creates the pipeline from a YAML dump and a binary which are already in memory. The
+        /// file name used for the outputs defaults to `binary`.
+        pub fn from_buffer(
+            dump_yaml: &str,
+            binary: &[u8],
+            config: config::Config,
+        ) -> Result<Self, &'static str> {
+            PE::from_buffers(&[dump_yaml.to_string()], binary, config)
+        }
+
+        /// Like [`PE::from_buffer`] but merges the symbols of several YAML dumps. Earlier dumps
+        /// take priority if two dumps provide a symbol at the same location.
+        pub fn from_buffers(
+            dump_yamls: &[String],
+            binary: &[u8],
+            config: config::Config,
+        ) -> Result<Self, &'static str> {
+            // Guard: Inputs have to fit into the memory limit
+            common::check_memory_limit(config.max_memory, dump_yamls, binary)?;
+
+            // Collect symbols from PDB
+            let pdb = parser::yaml::pdb::load_pdbs_from_strs(dump_yamls)?;
+
+            // Retrieve architecture from PE header
+            let architecture = pe::get_architecture_from_buffer(binary)?;
 
             // Collect sections from PE header
             // Note: PE header sections start at 0 while PDB segments start at 1
-            let sections = match pe::parse_sections(path_to_pe) {
-                Ok(sections) => sections,
-                Err(e) => {
-                    error!("{}", e);
-                    process::exit(1);
-                }
-            };
+            let sections = pe::parse_sections_from_buffer(binary)?;
 
             // Create raw byte vector from binary
-            let bytes = match pe::read_pe(path_to_pe) {
-                Ok(byte_vector) => byte_vector,
-                Err(e) => {
-                    error!("{}", e);
-                    process::exit(1);
-                }
-            };
+            let bytes = pe::read_pe_from_buffer(binary)?;
+
+            // Symbols the parser had to skip are the first warnings of the run
+            let mut warnings = groundtruth::Warnings::default();
+            warnings.add_many(groundtruth::WARNING::SKIPPED_SYMBOL, pdb.skipped_symbols);
+            common::report_section_overlaps(&sections, &mut warnings);
 
-            PE {
-                file_name,
+            Ok(PE {
+                file_name: "binary".to_string(),
+                binary: binary.to_vec(),
                 architecture,
+                config,
                 pdb,
                 sections,
                 bytes,
                 instructions: Vec::new(),
-            }
+                gaps: Vec::new(),
+                imports: Vec::new(),
+                label_to_function: Vec::new(),
+                segment_to_section: Vec::new(),
+                symbols_not_in_section: Vec::new(),
+                jump_tables: Vec::new(),
+                size_discrepancies: Vec::new(),
+                mid_instruction_targets: Vec::new(),
+                recovered_functions: Vec::new(),
+                noreturn_functions: Vec::new(),
+                difficulty: groundtruth::Difficulty::default(),
+                data_sections: Vec::new(),
+                address_space: dumper::AddressSpace::default(),
+                warnings,
+            })
         }
 
+        #[instrument(skip_all, fields(binary = %self.file_name))]
         pub fn process(&mut self) {
+            // Adapt the heuristics to the compilers which built the binary
+            self.configure_for_compilers();
+
+            // Make the translation of PDB segments to sections explicit
+            self.map_segments();
+
             // Grab text section
-            let text_section = match self.sections.iter().find(|s| s.name == ".text") {
-                Some(text_section) => text_section.clone(),
-                None => {
-                    error!("[-] Binary does not have a text section!");
-                    process::exit(1);
-                }
-            };
+            let text_section = self.select_code_section();
+            self.address_space = dumper::address_space_pe(self, &text_section);
 
-            // Trim byte vector (we only need the data of text section) that means cut before raw
-            // data start and after raw data end
-            self.trim_byte_vector(
-                text_section.raw_data_offset,
-                text_section.raw_data_offset + text_section.raw_data_size,
-            );
+            // Add functions from other tools
+            if self.config.functions_from.is_some() {
+                self.import_functions();
+            }
+
+            if self.config.demangle {
+                common::demangle_functions(&mut self.pdb.functions);
+            }
 
-            self.rebase_byte_vector(0x1000);
+            // Check all symbols against their sections before anything indexes the bytes
+            if self.config.validate_offsets {
+                self.validate_offsets();
+            }
 
             // Pre-process functions
             self.preprocess_functions();
 
+            // Add and validate function boundaries from the exception directory
+            if self.config.use_pdata {
+                self.apply_runtime_functions(&text_section);
+            }
+
             // Connect found symbols  (e.g. add data or labels within a function to its parent function)
             self.create_relationships();
 
+            // Explain symbols which are never attributed to a section
+            if self.config.list_symbols_not_in_section {
+                self.report_symbols_not_in_section(&text_section);
+            }
+
+            // Only count the symbols
+            if self.config.count_only {
+                self.print_census(&text_section);
+                return;
+            }
+
+            // Report data symbols which overlap code without being in-line data
+            self.report_data_overlaps(&text_section);
+
+            // Restrict processing to a single function
+            if let Some(name) = &self.config.function {
+                common::select_function(&mut self.pdb.functions, name);
+            }
+
             // Cut in-line data which is at the end of a function (jump tables)
             self.cut_in_line_data_end();
 
             // Cut in-line data which is in the middle of a function (jump tables)
             self.cut_in_line_data_mid();
 
-            // Set byte flags (code/data is already known)
-            self.set_byte_flags();
+            // Determine the entry kind of jump tables (absolute or relative)
+            self.jump_tables = common::detect_jump_tables(
+                &self.bytes,
+                &mut self.pdb.functions,
+                &self.architecture,
+                self.address_space,
+            );
 
-            // Disassemble code bytes (functions)
-            self.disassemble();
+            // Disassemble the functions and analyze the instructions
+            self.classify_code();
 
-            // Detect alignment/filler bytes
-            self.detect_alignment_bytes();
+            // Flag IAT entries and import thunks located in the text section
+            if self.config.include_imports {
+                self.classify_imports(&text_section);
+            }
 
-            // Detect end of section
-            self.detect_end_of_section();
+            // Detect the hotpatch area in front of functions
+            if self.config.hotpatch_padding {
+                common::detect_hotpatch_padding(
+                    &mut self.bytes,
+                    &self.pdb.functions,
+                    &self.pdb.architecture,
+                    self.config.explain,
+                );
+            }
 
-            // Create debug print
-            self.print();
+            // Detect alignment, gaps and the end of the section
+            self.classify_remaining();
 
-            // Create final mapping
-            dumper::plain::dump_pe(&self);
-            dumper::yaml::dump_pe(&self);
+            // Map the data sections next to the code section
+            if self.config.include_data_sections {
+                self.classify_data_sections();
+            }
+
+            // Check that trimming/rebasing did not corrupt any byte values
+            if self.config.verify {
+                self.verify_bytes(&text_section);
+            }
+
+            self.finish(&text_section);
         }
 
-        fn disassemble(&mut self) {
-            for function in &mut self.pdb.functions {
-                let mut function_buffer = Vec::new();
+        /// Maps every PDB segment to the section of the PE header it refers to and counts the
+        /// symbols per segment. Symbols in segments without a section are reported.
+        #[instrument(skip_all)]
+        fn map_segments(&mut self) {
+            // Location of every symbol (segment, offset)
+            let locations = self
+                .pdb
+                .functions
+                .iter()
+                .map(|f| (f.segment, f.offset))
+                .chain(self.pdb.thunks.iter().map(|t| (t.segment, t.offset)))
+                .chain(self.pdb.data.iter().map(|d| (d.segment, d.offset)))
+                .chain(self.pdb.labels.iter().map(|l| (l.segment, l.offset)));
+
+            // PDB segments start at 1
+            let mut mappings: Vec<groundtruth::SegmentMapping> = self
+                .sections
+                .iter()
+                .enumerate()
+                .map(|(index, section)| groundtruth::SegmentMapping {
+                    segment: (index + 1) as u8,
+                    section: Some(section.name.clone()),
+                    index: Some(index),
+                    symbols: 0,
+                    beyond_raw_data: 0,
+                })
+                .collect();
+
+            for (segment, offset) in locations {
+                let index = (segment as usize).checked_sub(1);
+
+                match index.and_then(|index| self.sections.get(index)) {
+                    Some(section) => {
+                        let mapping = &mut mappings[segment as usize - 1];
+                        mapping.symbols += 1;
+
+                        if offset >= section.raw_data_size {
+                            mapping.beyond_raw_data += 1;
+                        }
+                    }
+                    None => {
+                        debug!(
+                            segment,
+                            offset, "Symbol lies in a segment without a section."
+                        );
+                        self.warnings.report(groundtruth::Diagnostic::warning(
+                            groundtruth::WARNING::SEGMENT_WITHOUT_SECTION,
+                            format!(
+                                "Symbol at {:x}:0x{:x} lies in a segment without a section.",
+                                segment, offset
+                            ),
+                        ));
+
+                        match mappings.iter_mut().find(|m| m.segment == segment) {
+                            Some(mapping) => mapping.symbols += 1,
+                            None => mappings.push(groundtruth::SegmentMapping {
+                                segment,
+                                section: None,
+                                index: None,
+                                symbols: 1,
+                                beyond_raw_data: 0,
+                            }),
+                        }
+                    }
+                }
+            }
+
+            mappings.sort_by_key(|m| m.segment);
 
-                for offset in 0..function.size {
-                    // Guard: Byte already flagged as data
-                    if self.bytes[(function.offset + offset) as usize].is_data() {
+            for mapping in mappings.iter().filter(|m| m.section.is_none()) {
+                warn!(
+                    segment = mapping.segment,
+                    "{} symbols refer to a segment without a section ({} sections).",
+                    mapping.symbols,
+                    self.sections.len()
+                );
+            }
+
+            self.segment_to_section = mappings;
+        }
+
+        /// Drops the symbols which do not fit the raw data of the section of their segment. Data
+        /// symbols only need a section, uninitialized data lies behind the raw data.
+        #[instrument(skip_all)]
+        fn validate_offsets(&mut self) {
+            let sections = &self.sections;
+            let bounds = |segment: u8| {
+                (segment as usize)
+                    .checked_sub(1)
+                    .and_then(|index| sections.get(index))
+                    .map(|section| (0, section.raw_data_size))
+            };
+
+            let mut violations = Vec::new();
+
+            self.pdb.functions.retain(|f| {
+                common::fits_section(
+                    "Function",
+                    &f.name,
+                    f.offset,
+                    f.size,
+                    bounds(f.segment),
+                    &mut violations,
+                )
+            });
+            self.pdb.thunks.retain(|t| {
+                common::fits_section(
+                    "Thunk",
+                    "<Thunk>",
+                    t.offset,
+                    t.size,
+                    bounds(t.segment),
+                    &mut violations,
+                )
+            });
+            self.pdb.labels.retain(|l| {
+                common::fits_section(
+                    "Label",
+                    &l.name,
+                    l.offset,
+                    0,
+                    bounds(l.segment),
+                    &mut violations,
+                )
+            });
+            self.pdb.data.retain(|d| {
+                common::fits_section(
+                    "Data",
+                    &d.name,
+                    d.offset,
+                    0,
+                    bounds(d.segment).map(|_| (0, u64::MAX)),
+                    &mut violations,
+                )
+            });
+
+            common::report_offset_violations(violations, self.config.strict, &mut self.warnings);
+        }
+
+        /// Adds the functions of `--functions-from`. Their addresses are converted to offsets
+        /// relative to the section containing them. Functions which the PDB already provides at
+        /// the same location are dropped.
+        #[instrument(skip_all)]
+        fn import_functions(&mut self) {
+            let image_base = match pe::get_image_base_from_buffer(&self.binary) {
+                Ok(image_base) => image_base,
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            let mut functions = Vec::new();
+
+            for mut function in common::load_function_list(&self.config) {
+                let rva = function.offset.wrapping_sub(image_base);
+
+                let index = match self
+                    .sections
+                    .iter()
+                    .position(|s| rva >= s.va && rva < s.va + s.raw_data_size)
+                {
+                    Some(index) => index,
+                    None => {
+                        debug!(function = %function.name, "Function lies outside of all sections.");
                         continue;
                     }
+                };
 
-                    // Set specific flags
-                    self.bytes[(function.offset + offset) as usize].set_flags(vec![
-                        groundtruth::FLAG::CODE,
-                        groundtruth::FLAG::READABLE,
-                        groundtruth::FLAG::EXECUTABLE,
-                    ]);
+                // PDB segments start at 1
+                function.offset = rva - self.sections[index].va;
+                function.segment = (index + 1) as u8;
+                functions.push(function);
+            }
 
-                    // Add byte to function buffer
-                    function_buffer.push(self.bytes[(function.offset + offset) as usize].value);
+            parser::yaml::merge_by_location(&mut self.pdb.functions, functions, |f| {
+                (f.segment, f.offset)
+            });
+            self.pdb.functions.sort_by_key(|a| a.offset);
+        }
+
+        /// Uses the RUNTIME_FUNCTION entries of `.pdata` (x64 only): entries which start at a PDB
+        /// function validate its end, entries outside of all PDB functions are added as functions
+        /// and UNWIND_INFO structures located within the code section are flagged as data.
+        #[instrument(skip_all)]
+        fn apply_runtime_functions(&mut self, text_section: &groundtruth::Section) {
+            let runtime_functions = match pe::parse_runtime_functions_from_buffer(&self.binary) {
+                Ok(runtime_functions) => runtime_functions,
+                Err(e) => {
+                    error!("{}", e);
+                    process::exit(1);
                 }
+            };
 
-                // Set function start and end
-                self.bytes[function.offset as usize]
-                    .set_flags(vec![groundtruth::FLAG::FUNCTION_START]);
-                self.bytes[(function.offset + function.size - 1) as usize]
-                    .set_flags(vec![groundtruth::FLAG::FUNCTION_END]);
+            let segment = match self
+                .sections
+                .iter()
+                .position(|s| s.name == text_section.name)
+            {
+                Some(index) => (index + 1) as u8,
+                None => return,
+            };
 
-                // Disassemble function bytes
-                let instructions = match disassembler::disassemble(
-                    function_buffer,
-                    &self.pdb.architecture,
-                    disassembler::DISASSEMBLER::CAPSTONE,
-                ) {
-                    Ok(instructions) => instructions,
-                    Err(e) => {
-                        error!("{}", e);
-                        process::exit(1);
+            let text_start = text_section.va;
+            let text_end = text_section.va + text_section.raw_data_size;
+
+            let mut added = Vec::new();
+            let mut mismatches = 0;
+            let mut unwind_bytes = 0;
+
+            for entry in &runtime_functions {
+                // Guard: Only entries describing code of the processed section
+                if entry.begin < text_start || entry.end > text_end || entry.end <= entry.begin {
+                    continue;
+                }
+
+                let offset = entry.begin - text_start;
+                let size = entry.end - entry.begin;
+
+                let known = self.pdb.functions.iter().find(|f| {
+                    f.segment == segment && offset >= f.offset && offset < f.offset + f.size
+                });
+
+                match known {
+                    // Later entries of a function (e.g. chained unwind info of split functions)
+                    Some(function) if function.offset != offset => {}
+                    // The first entry may end before the function but never behind it
+                    Some(function) if offset + size > function.offset + function.size => {
+                        warn!(
+                            function = %function.name,
+                            "Function ends at 0x{:x} but its unwind information covers up to 0x{:x}.",
+                            function.offset + function.size,
+                            offset + size
+                        );
+                        mismatches += 1;
                     }
-                };
-                // Set instruction start and end, copy instruction flags
-                for instruction in instructions {
-                    // Since we (may have) cut our function buffer in the middle our instruction offset will become "wrong"
-                    // the moment we come to the first instruction after the "hole" we created by erasing some bytes in the middle
-                    // since they were data bytes. Therefore we need to account for the additional offset created by the size of the
-                    // removed bytes.
-                    // TODO: Handle multiple holes in the middle.
-                    let mut additional_offset = 0;
-
-                    for data in &function.data {
-                        // Check current instruction has a offset which would in theory place in the inline data hole
-                        if (instruction.offset + function.offset + additional_offset) >= data.offset
-                        {
-                            additional_offset += data.size;
+                    Some(_) => {}
+                    None => added.push(groundtruth::Function {
+                        name: format!("pdata_{:x}", entry.begin),
+                        demangled_name: None,
+                        offset,
+                        segment,
+                        size,
+                        labels: Vec::new(),
+                        data: Vec::new(),
+                        frame: None,
+                        thumb: false,
+                    }),
+                }
+
+                // Flag the unwind information if the linker merged it into the code section
+                if entry.unwind_info >= text_start
+                    && entry.unwind_info + entry.unwind_info_size <= text_end
+                {
+                    for rva in entry.unwind_info..entry.unwind_info + entry.unwind_info_size {
+                        let byte = &mut self.bytes[(rva - text_start) as usize];
+                        byte.set_flags(vec![groundtruth::FLAG::DATA]);
+                        unwind_bytes += 1;
+
+                        if self.config.explain {
+                            byte.explain(format!(
+                                "DATA from unwind info of function at 0x{:x}",
+                                entry.begin
+                            ));
                         }
                     }
+                }
+            }
+
+            info!(
+                "{} runtime functions: {} functions added, {} end behind their function, {} unwind info bytes in the code section.",
+                runtime_functions.len(),
+                added.len(),
+                mismatches,
+                unwind_bytes
+            );
+
+            self.pdb.functions.extend(added);
+            self.pdb.functions.sort_by_key(|a| a.offset);
+        }
+
+        /// Enables compiler-specific detections based on the S_COMPILE3 records of the PDB.
+        fn configure_for_compilers(&mut self) {
+            for compiler in &self.pdb.compilers {
+                info!(
+                    flags = ?compiler.flags,
+                    "Compiled by {} {} ({}).",
+                    compiler.name,
+                    compiler.frontend_version,
+                    compiler.machine
+                );
+            }
+
+            if !self.config.hotpatch_padding
+                && self.pdb.compilers.iter().any(|c| c.is_hotpatchable())
+            {
+                info!(
+                    "Modules compiled with /hotpatch found, enabling hotpatch padding detection."
+                );
+                self.config.hotpatch_padding = true;
+            }
+        }
 
-                    self.bytes[(additional_offset + function.offset + instruction.offset) as usize]
-                        .set_flags(vec![groundtruth::FLAG::INSTRUCTION_START]);
+        #[instrument(skip_all)]
+        fn classify_imports(&mut self, text_section: &groundtruth::Section) {
+            let imports = pe::parse_imports_from_buffer(&self.binary);
+            let delay_imports = pe::parse_delay_imports_from_buffer(&self.binary);
+            let image_base = pe::get_image_base_from_buffer(&self.binary);
 
-                    // Instruction End Example: Start 0x0, Size 0x8 => Instruction: 0x0-0x8 therefore the 8th byte (the last byte) is 0x7
-                    self.bytes[(additional_offset
-                        + function.offset
-                        + instruction.offset
-                        + instruction.length
-                        - 1) as usize]
-                        .set_flags(vec![groundtruth::FLAG::INSTRUCTION_END]);
+            let (mut imports, image_base) = match (imports, delay_imports, image_base) {
+                (Ok(mut imports), Ok(delay_imports), Ok(image_base)) => {
+                    imports.extend(delay_imports);
+                    (imports, image_base)
+                }
+                (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => {
+                    error!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            // Bytes are indexed relative to the start of the section
+            let start = text_section.va;
+            let end = text_section.va + self.bytes.len() as u64;
 
-                    // TODO: Set instruction flags for not only the first byte of instruction
-                    self.bytes[(additional_offset + function.offset + instruction.offset) as usize]
-                        .set_flags(instruction.get_flags());
+            // Flag IAT entries which are merged into the text section (e.g. /MERGE:.rdata=.text)
+            for import in imports
+                .iter()
+                .filter(|i| i.iat >= start && i.iat + i.size <= end)
+            {
+                for offset in import.iat..import.iat + import.size {
+                    let byte = &mut self.bytes[(offset - start) as usize];
+
+                    // Guard: Never override bytes which belong to a function
+                    if !byte.get_flags().is_empty() {
+                        continue;
+                    }
 
-                    // debug!("{:x?}", instruction);
+                    byte.set_flags(vec![groundtruth::FLAG::DATA, groundtruth::FLAG::READABLE]);
 
-                    // Append to instructions vector
-                    self.instructions.push(instruction);
+                    if self.config.explain {
+                        byte.explain(format!(
+                            "DATA from IAT entry {}!{}",
+                            import.dll, import.name
+                        ));
+                    }
                 }
             }
-        }
 
-        fn preprocess_functions(&mut self) {
-            self.pdb.functions.retain(|ref f| f.size > 0)
+            // Find import thunks: jmp [iat] (FF 25), the operand is RIP relative on x64 and an
+            // absolute address on x86
+            for index in 0..self.bytes.len().saturating_sub(5) {
+                if self.bytes[index].value != 0xFF || self.bytes[index + 1].value != 0x25 {
+                    continue;
+                }
+
+                // Guard: Thunk bytes have to be unidentified so far
+                if self.bytes[index..index + 6]
+                    .iter()
+                    .any(|b| !b.get_flags().is_empty())
+                {
+                    continue;
+                }
+
+                let operand = u32::from_le_bytes([
+                    self.bytes[index + 2].value,
+                    self.bytes[index + 3].value,
+                    self.bytes[index + 4].value,
+                    self.bytes[index + 5].value,
+                ]);
+
+                let address = start + index as u64;
+                let target = match self.architecture.family {
+                    groundtruth::ARCHITECTURE::X86 => (operand as u64).wrapping_sub(image_base),
+                    _ => (address + 6).wrapping_add(operand as i32 as u64),
+                };
+
+                let import = match imports.iter_mut().find(|i| i.iat == target) {
+                    Some(import) => import,
+                    None => continue,
+                };
+
+                import.thunk = Some(address);
+
+                let reason = format!("CODE from import thunk of {}", import.name);
+                self.flag_thunk_instruction(
+                    index,
+                    6,
+                    vec![
+                        groundtruth::FLAG::INSTRUCTION_JUMP,
+                        groundtruth::FLAG::MEMORY_READ,
+                    ],
+                    &reason,
+                );
+            }
+
+            let (load_thunks, tail_merges) =
+                self.classify_delay_load_thunks(&imports, start, end, image_base);
+
+            info!(
+                "Found {} imports, {} with a thunk in the text section.",
+                imports.len(),
+                imports.iter().filter(|i| i.thunk.is_some()).count()
+            );
+            info!(
+                "Found {} delay imports, {} load thunks and {} tail merge helpers in the text \
+                 section.",
+                imports.iter().filter(|i| i.delayed).count(),
+                load_thunks,
+                tail_merges
+            );
+
+            self.imports = imports;
         }
 
-        fn set_byte_flags(&mut self) {
-            for function in &self.pdb.functions {
-                // Set data flags
-                // Attention: we have to use the child data of a function and not from the normal
-                // data collection because ONLY the child data has a up-to-date size value.
-                for data in &function.data {
-                    for i in 0..data.size {
-                        self.bytes[(data.offset + i) as usize]
-                            .set_flags(vec![groundtruth::FLAG::DATA]);
+        /// Flags the load thunks of delay imports (`lea rax, [iat]` on x64 or `mov eax, iat` on
+        /// x86, followed by `jmp __tailMerge_<dll>`) and the tail merge helpers they jump to as
+        /// code. Returns the number of load thunks and tail merge helpers found.
+        fn classify_delay_load_thunks(
+            &mut self,
+            imports: &[groundtruth::Import],
+            start: u64,
+            end: u64,
+            image_base: u64,
+        ) -> (usize, usize) {
+            let mut load_thunks = 0;
+            let mut tail_merges: Vec<u64> = Vec::new();
+
+            for import in imports.iter().filter(|i| i.delayed) {
+                let address = match import.load_thunk {
+                    Some(address) if address >= start && address < end => address,
+                    _ => continue,
+                };
+                let index = (address - start) as usize;
+
+                // The first instruction loads the address of the IAT entry into the accumulator
+                let (load_length, target) = match self.architecture.family {
+                    groundtruth::ARCHITECTURE::X86 => (
+                        5,
+                        self.read_operand(index, &[0xB8])
+                            .map(|operand| operand.wrapping_sub(image_base)),
+                    ),
+                    _ => (
+                        7,
+                        self.read_operand(index, &[0x48, 0x8D, 0x05])
+                            .map(|operand| (address + 7).wrapping_add(operand as i32 as u64)),
+                    ),
+                };
+
+                // Guard: Only thunks loading their own IAT entry and jumping on
+                let tail_merge = match (target, self.read_operand(index + load_length, &[0xE9])) {
+                    (Some(target), Some(operand)) if target == import.iat => {
+                        (address + load_length as u64 + 5).wrapping_add(operand as i32 as u64)
                     }
+                    _ => continue,
+                };
+
+                // Guard: Thunk bytes have to be unidentified so far
+                if self
+                    .bytes
+                    .get(index..index + load_length + 5)
+                    .is_none_or(|thunk| thunk.iter().any(|b| !b.get_flags().is_empty()))
+                {
+                    continue;
                 }
 
-                // Set data and code flags
-                for i in 0..function.size {
-                    // Guard: Check if byte is already data (because there is data within the function)
-                    if self.bytes[(function.offset + i) as usize].is_data() {
-                        continue;
-                    }
+                let reason = format!("CODE from delay load thunk of {}", import.name);
+                self.flag_thunk_instruction(index, load_length, Vec::new(), &reason);
+                self.flag_thunk_instruction(
+                    index + load_length,
+                    5,
+                    vec![groundtruth::FLAG::INSTRUCTION_JUMP],
+                    &reason,
+                );
+                load_thunks += 1;
 
-                    self.bytes[(function.offset + i) as usize]
-                        .set_flags(vec![groundtruth::FLAG::CODE]);
+                if tail_merge >= start && tail_merge < end && !tail_merges.contains(&tail_merge) {
+                    tail_merges.push(tail_merge);
                 }
             }
+
+            let tail_merges = tail_merges
+                .iter()
+                .filter(|address| self.classify_tail_merge((**address - start) as usize))
+                .count();
+
+            (load_thunks, tail_merges)
+        }
+
+        /// Disassembles the tail merge helper at the given index up to its final `jmp rax` (`jmp
+        /// eax` on x86) and flags it as code. Nothing is flagged if it runs into identified bytes.
+        fn classify_tail_merge(&mut self, index: usize) -> bool {
+            let end = (index + TAIL_MERGE_MAX_SIZE).min(self.bytes.len());
+            let buffer: Vec<u8> = self.bytes[index..end].iter().map(|b| b.value).collect();
+
+            let instructions = match disassembler::disassemble(
+                buffer,
+                &self.architecture,
+                self.config.disassembler,
+            ) {
+                Ok(instructions) => instructions,
+                Err(_e) => return false,
+            };
+
+            let last = match instructions
+                .iter()
+                .position(|i| i.mnemonic == "jmp" && matches!(i.operand.as_str(), "rax" | "eax"))
+            {
+                Some(last) => last,
+                None => return false,
+            };
+
+            let length: u64 = instructions[..=last].iter().map(|i| i.length).sum();
+
+            // Guard: Helper bytes have to be unidentified so far
+            if self.bytes[index..index + length as usize]
+                .iter()
+                .any(|b| !b.get_flags().is_empty())
+            {
+                return false;
+            }
+
+            for instruction in &instructions[..=last] {
+                self.flag_thunk_instruction(
+                    index + instruction.offset as usize,
+                    instruction.length as usize,
+                    instruction.flags.clone(),
+                    "CODE from delay load tail merge helper",
+                );
+            }
+
+            true
         }
 
-        fn trim_byte_vector(&mut self, start: u64, end: u64) {
-            // Cut current start to new start and new end to current end
-            self.bytes.drain(..start as usize);
-            self.bytes.drain((end - start) as usize..);
+        /// Reads the 32 bit operand behind the given opcode bytes at the index.
+        fn read_operand(&self, index: usize, opcode: &[u8]) -> Option<u64> {
+            let bytes: Vec<u8> = self
+                .bytes
+                .get(index..index + opcode.len() + 4)?
+                .iter()
+                .map(|b| b.value)
+                .collect();
+
+            if !bytes.starts_with(opcode) {
+                return None;
+            }
+
+            let operand = &bytes[opcode.len()..];
+            Some(u64::from(u32::from_le_bytes([
+                operand[0], operand[1], operand[2], operand[3],
+            ])))
         }
 
-        fn rebase_byte_vector(&mut self, base: u64) {
-            // Reset offsets
-            for (offset, byte) in self.bytes.iter_mut().enumerate() {
-                byte.offset = offset as u64 + base;
+        /// Flags the bytes of an instruction found outside of the functions (e.g. a thunk) as
+        /// code.
+        fn flag_thunk_instruction(
+            &mut self,
+            index: usize,
+            length: usize,
+            flags: Vec<groundtruth::FLAG>,
+            reason: &str,
+        ) {
+            for byte in &mut self.bytes[index..index + length] {
+                byte.set_flags(vec![
+                    groundtruth::FLAG::CODE,
+                    groundtruth::FLAG::READABLE,
+                    groundtruth::FLAG::EXECUTABLE,
+                ]);
+
+                if self.config.explain {
+                    byte.explain(reason.to_string());
+                }
             }
+
+            let mut first = vec![groundtruth::FLAG::INSTRUCTION_START];
+            first.extend(flags);
+            self.bytes[index].set_flags(first);
+            self.bytes[index + length - 1].set_flags(vec![groundtruth::FLAG::INSTRUCTION_END]);
+        }
+
+        #[instrument(skip_all)]
+        fn preprocess_functions(&mut self) {
+            self.pdb.functions.retain(|f| f.size > 0);
+
+            common::limit_functions(&mut self.pdb.functions, self.config.limit_functions);
         }
 
+        #[instrument(skip_all)]
         fn cut_in_line_data_end(&mut self) {
             // Check for every function if there is in-line data at its end
             for function in &mut self.pdb.functions {
                 for data in &mut function.data {
                     // Guard: Data which is in the middle of function has always an empty name
-                    if data.name != "" {
+                    if !data.name.is_empty() {
                         continue;
                     }
 
@@ -271,12 +4013,16 @@ pub mod pe {
             }
         }
 
+        #[instrument(skip_all)]
         fn cut_in_line_data_mid(&mut self) {
+            // Offsets are relative to the first byte, the diagnostics hold addresses
+            let base = self.bytes.first().map_or(0, |b| b.offset);
+
             // Check for every function if there is in-line data at its end
             for function in &mut self.pdb.functions {
                 for data in &mut function.data {
                     // Guard: Data which is in the middle of function never has an empty name
-                    if data.name == "" {
+                    if data.name.is_empty() {
                         continue;
                     }
 
@@ -296,12 +4042,37 @@ pub mod pe {
                         }
                     }
 
-                    // Set calculated size for data
-                    data.size = label_counter * 0x4;
+                    // Set calculated size for data, which never reaches into the next function
+                    let size = label_counter * 0x4;
+                    let remaining = (function.offset + function.size).saturating_sub(data.offset);
+
+                    if size > remaining {
+                        let message = format!(
+                            "Jump table {} implies {} entries ({} bytes) but only {} bytes of the \
+                             function remain, clamped.",
+                            data.name, label_counter, size, remaining
+                        );
+
+                        warn!(function = %function.name, "{}", message);
+                        self.warnings.report(
+                            groundtruth::Diagnostic::warning(
+                                groundtruth::WARNING::JUMP_TABLE_OVERRUN,
+                                message,
+                            )
+                            .in_function(&function.name)
+                            .at(
+                                base + function.offset + function.size,
+                                base + data.offset + size - 1,
+                            ),
+                        );
+                    }
+
+                    data.size = size.min(remaining);
                 }
             }
         }
 
+        #[instrument(skip_all)]
         fn create_relationships(&mut self) {
             // Add relationships between labels/data and its parent functions
             for function in &mut self.pdb.functions {
@@ -335,20 +4106,297 @@ pub mod pe {
                     }
                 }
             }
+
+            // Flat mapping of every label to its function, orphaned labels included
+            self.label_to_function = self
+                .pdb
+                .labels
+                .iter()
+                .map(|label| {
+                    let function = self.pdb.functions.iter().find(|f| f.labels.contains(label));
+
+                    groundtruth::LabelMapping {
+                        name: label.name.clone(),
+                        offset: label.offset,
+                        segment: label.segment,
+                        function: function.map(|f| f.name.clone()),
+                        offset_in_function: function.map(|f| label.offset - f.offset),
+                    }
+                })
+                .collect();
         }
 
-        fn print(&self) {
-            debug!("######## META ###########");
-            debug!("{:?}", self.pdb.architecture);
+        /// Lists the functions, labels and data symbols which lie in none of the processed sections:
+        /// the code section and, with `--include-data-sections`, the data sections (data symbols
+        /// only). Logs how many there are per section and a sample of their names.
+        #[instrument(skip_all)]
+        fn report_symbols_not_in_section(&mut self, text_section: &groundtruth::Section) {
+            // PDB segments start at 1
+            let sections = &self.sections;
+            let section_of = |segment: u8| {
+                (segment as usize)
+                    .checked_sub(1)
+                    .and_then(|index| sections.get(index))
+            };
 
-            debug!("######## SECTIONS #########");
-            for section in &self.sections {
-                debug!("{:x?}", section);
+            let in_text = |segment: u8, offset: u64| {
+                section_of(segment)
+                    .is_some_and(|s| s.name == text_section.name && offset < s.raw_data_size)
+            };
+            let include_data = self.config.include_data_sections;
+            let in_data =
+                |segment: u8| include_data && section_of(segment).is_some_and(|s| s.is_data());
+
+            let symbols = self
+                .pdb
+                .functions
+                .iter()
+                .filter(|f| !in_text(f.segment, f.offset))
+                .map(|f| ("Function", &f.name, f.segment, f.offset))
+                .chain(
+                    self.pdb
+                        .labels
+                        .iter()
+                        .filter(|l| !in_text(l.segment, l.offset))
+                        .map(|l| ("Label", &l.name, l.segment, l.offset)),
+                )
+                .chain(
+                    self.pdb
+                        .data
+                        .iter()
+                        .filter(|d| !(in_text(d.segment, d.offset) || in_data(d.segment)))
+                        .map(|d| ("Data", &d.name, d.segment, d.offset)),
+                );
+
+            let mut unattributed = Vec::new();
+            let mut per_section: BTreeMap<String, usize> = BTreeMap::new();
+
+            for (kind, name, segment, offset) in symbols {
+                let section = section_of(segment).map(|s| s.name.clone());
+                let message = format!(
+                    "{} {} at {}:0x{:x} lies in {}.",
+                    kind,
+                    name,
+                    segment,
+                    offset,
+                    match &section {
+                        Some(section) => format!("section {}, which is not processed", section),
+                        None => "no section".to_string(),
+                    }
+                );
+
+                warn!("{}", message);
+                self.warnings.report(groundtruth::Diagnostic::warning(
+                    groundtruth::WARNING::SYMBOL_NOT_IN_SECTION,
+                    message,
+                ));
+
+                *per_section
+                    .entry(section.clone().unwrap_or_else(|| "no section".to_string()))
+                    .or_insert(0) += 1;
+
+                unattributed.push(groundtruth::UnattributedSymbol {
+                    kind: kind.to_string(),
+                    name: name.clone(),
+                    segment,
+                    offset,
+                    section,
+                });
+            }
+
+            // Guard: Every symbol is attributed
+            if unattributed.is_empty() {
+                info!("All symbols lie in the processed sections.");
+                return;
+            }
+
+            warn!(
+                "{} symbols lie outside of the processed sections ({}), e.g. {}.",
+                unattributed.len(),
+                per_section
+                    .iter()
+                    .map(|(section, count)| format!("{}: {}", section, count))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                unattributed
+                    .iter()
+                    .take(SAMPLE_SIZE)
+                    .map(|s| s.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", ")
+            );
+
+            self.symbols_not_in_section = unattributed;
+        }
+
+        /// Prints how many symbols are located in the code section and how many of the labels and
+        /// data symbols belong to a function.
+        fn print_census(&self, text_section: &groundtruth::Section) {
+            // PDB segments start at 1
+            let segment = self
+                .sections
+                .iter()
+                .position(|s| s.name == text_section.name)
+                .map_or(0, |index| (index + 1) as u8);
+
+            let in_text = |s: u8, offset: u64| s == segment && offset < text_section.raw_data_size;
+
+            let functions = &self.pdb.functions;
+            let labels: usize = functions.iter().map(|f| f.labels.len()).sum();
+            let data: usize = functions.iter().map(|f| f.data.len()).sum();
+
+            println!("Functions:         {}", functions.len());
+            println!(
+                "  in {}:         {}",
+                text_section.name,
+                functions
+                    .iter()
+                    .filter(|f| in_text(f.segment, f.offset))
+                    .count()
+            );
+            println!("Thunks:            {}", self.pdb.thunks.len());
+            println!("Labels:            {}", self.pdb.labels.len());
+            println!("  in functions:    {}", labels);
+            println!(
+                "  orphaned:        {}",
+                self.pdb
+                    .labels
+                    .iter()
+                    .filter(|l| in_text(l.segment, l.offset))
+                    .count()
+                    - labels
+            );
+            println!("Data:              {}", self.pdb.data.len());
+            println!("  in functions:    {}", data);
+            println!(
+                "  in {}:         {}",
+                text_section.name,
+                self.pdb
+                    .data
+                    .iter()
+                    .filter(|d| in_text(d.segment, d.offset))
+                    .count()
+            );
+            println!("Skipped symbols:   {}", self.pdb.skipped_symbols);
+        }
+
+        #[instrument(skip_all)]
+        fn report_data_overlaps(&mut self, text_section: &groundtruth::Section) {
+            let base = text_section.va;
+            let mut inline = 0;
+            let mut suspicious = 0;
+
+            for data in &self.pdb.data {
+                // Data symbols without a size still occupy their first byte
+                let data_end = data.offset + data.size.max(1);
+
+                for function in &self.pdb.functions {
+                    // Guard: Check if same segment and ranges overlap
+                    if data.segment != function.segment
+                        || data_end <= function.offset
+                        || data.offset >= function.offset + function.size
+                    {
+                        continue;
+                    }
+
+                    if function.data.contains(data) {
+                        inline += 1;
+                        continue;
+                    }
+
+                    let message = format!(
+                        "Data symbol {} at {:x}:0x{:x} (size 0x{:x}) overlaps code of function at 0x{:x} but is not in-line data.",
+                        data.name,
+                        data.segment,
+                        data.offset,
+                        data.size,
+                        function.offset
+                    );
+                    warn!(function = %function.name, "{}", message);
+                    self.warnings.report(
+                        groundtruth::Diagnostic::warning(
+                            groundtruth::WARNING::DATA_OVERLAP,
+                            message,
+                        )
+                        .in_function(&function.name)
+                        .at(base + data.offset, base + data_end - 1),
+                    );
+                    suspicious += 1;
+                }
+            }
+
+            info!(
+                "Data symbols overlapping code: {} in-line data, {} suspicious.",
+                inline, suspicious
+            );
+        }
+    }
+
+    impl Pipeline for PE {
+        fn state(&mut self) -> State<'_> {
+            State {
+                config: &self.config,
+                architecture: &self.pdb.architecture,
+                sections: &self.sections,
+                bytes: &mut self.bytes,
+                functions: &mut self.pdb.functions,
+                instructions: &mut self.instructions,
+                gaps: &mut self.gaps,
+                size_discrepancies: &mut self.size_discrepancies,
+                mid_instruction_targets: &mut self.mid_instruction_targets,
+                recovered_functions: &mut self.recovered_functions,
+                noreturn_functions: &mut self.noreturn_functions,
+                data_sections: &mut self.data_sections,
+                difficulty: &mut self.difficulty,
+                warnings: &mut self.warnings,
+            }
+        }
+
+        fn base(&self, text_section: &groundtruth::Section) -> u64 {
+            text_section.va
+        }
+
+        fn read_binary(&self) -> Result<Vec<groundtruth::Byte>, &'static str> {
+            pe::read_pe_from_buffer(&self.binary)
+        }
+
+        fn create_dump(&self) -> dumper::Dump {
+            dumper::create_dump_pe(self)
+        }
+
+        fn write_outputs(&self) {
+            match self.config.format {
+                config::FORMAT::YAML => {
+                    dumper::plain::dump_pe(self);
+                    dumper::yaml::dump_pe(self);
+                }
+                config::FORMAT::OBJDUMP => dumper::objdump::dump_pe(self),
+                config::FORMAT::DOT => dumper::dot::dump_pe(self),
+                config::FORMAT::LABELS_BIN => dumper::labels::dump_pe(self),
             }
+        }
+
+        // PDB segments start at 1
+        fn data_symbols(&self) -> Vec<groundtruth::DataSymbol> {
+            self.pdb
+                .data
+                .iter()
+                .filter_map(|d| {
+                    let section = self.sections.get((d.segment as usize).checked_sub(1)?)?;
+
+                    Some(groundtruth::DataSymbol {
+                        name: d.name.clone(),
+                        address: section.va + d.offset,
+                        size: d.size,
+                    })
+                })
+                .collect()
+        }
 
-            debug!("######## FUNCTIONS #########");
-            for function in &self.pdb.functions {
-                debug!("{:x?}", function);
+        fn print_symbols(&self) {
+            debug!("######## COMPILERS ########");
+            for compiler in &self.pdb.compilers {
+                debug!("{:?}", compiler);
             }
 
             debug!("######## THUNKS ###########");
@@ -366,527 +4414,671 @@ pub mod pe {
                 debug!("{:x?}", label);
             }
 
-            debug!("### DATA IN FUNCTION ###");
-            for function in self.pdb.functions.iter().filter(|f| f.data.len() > 0) {
-                debug!(
-                    "{:?} {:x?} {:x?}",
-                    function.name, function.offset, function.size
-                );
-                for data in &function.data {
-                    debug!("\t{:x?}", data);
-                }
-            }
-
-            let holes = self.detect_holes();
-            debug!("######## HOLES #########");
-            let mut unknown_bytes = 0;
-            for hole in holes {
-                debug!("{:x?}", hole);
-                unknown_bytes += hole.size;
+            debug!("######## SEGMENTS #########");
+            for mapping in &self.segment_to_section {
+                debug!("{:?}", mapping);
             }
 
-            debug!("####### COUNT ########");
-            debug!("Functions: {}", self.pdb.functions.len());
-            debug!("Thunks: {}", self.pdb.thunks.len());
-            debug!("Data: {}", self.pdb.data.len());
-            debug!("Labels: {}", self.pdb.labels.len());
-
-            debug!("##### STATISTICS ######");
             debug!(
-                "Identified bytes {:.2}/{:.2} ({:.2}%)",
-                (self.bytes.len() as u64 - unknown_bytes),
-                self.bytes.len(),
-                100.0 * (self.bytes.len() as u64 - unknown_bytes) as f64 / self.bytes.len() as f64
+                "Thunks: {}, Data: {}, Labels: {}",
+                self.pdb.thunks.len(),
+                self.pdb.data.len(),
+                self.pdb.labels.len()
             );
-            debug!("Tail: 0x{:x}", self.bytes.len())
         }
+    }
 
-        fn detect_end_of_section(&mut self) {
-            // Get current section (vector) size
-            let mut section_size = self.bytes.len();
-
-            // Check whole byte vector but start from the end
-            for byte in self.bytes.iter().rev() {
-                // Guard: Only if this byte currently does not have any purpose
-                if byte.is_code() || byte.is_data() {
-                    break;
-                }
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::testing;
 
-                // Check if byte is 0x0 and reduce vector size
-                if byte.value == 0x0 {
-                    section_size -= 1;
-                }
-            }
+        const DUMP: &str = include_str!("../fixtures/selftest/pe.pdb.yaml");
+        const BINARY: &[u8] = include_bytes!("../fixtures/selftest/pe.exe");
 
-            // Remove the empty tail
-            self.bytes.truncate(section_size);
+        fn names(pe: &PE) -> Vec<&str> {
+            pe.pdb.functions.iter().map(|f| f.name.as_str()).collect()
         }
 
-        fn detect_alignment_bytes(&mut self) {
-            // Check whole byte vector for known alignment bytes
-            for byte in &mut self.bytes {
-                // Guard: Only if this byte currently does not have any purpose
-                if byte.is_code() || byte.is_data() {
-                    continue;
-                }
+        #[test]
+        fn thunk_is_represented_by_its_function() {
+            let dump = include_str!("../fixtures/selftest/pe-thunk.pdb.yaml");
+            let pe = testing::process_pe("pe-thunk", dump, BINARY, testing::config());
 
-                // Check if byte is 0xCC (int3)
-                if byte.value == 0xCC {
-                    byte.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
-                }
-            }
+            assert_eq!(names(&pe), vec!["funcA", "funcB"]);
+            assert_eq!(pe.instructions.len(), 10);
+        }
 
-            // Find holes and check of the holes are multi-byte nops
-            let holes = self.detect_holes();
+        #[test]
+        fn validate_offsets_drops_functions_outside_of_the_sections() {
+            let dump = include_str!("../fixtures/selftest/pe-bounds.pdb.yaml");
+            let config = config::Config {
+                validate_offsets: true,
+                ..testing::config()
+            };
+            let pe = testing::process_pe("pe-bounds", dump, BINARY, config);
 
-            for hole in holes {
-                // Get buffer of hole and disassemble it
-                let hole_buffer = self.bytes[hole.start as usize..hole.end as usize]
-                    .iter()
-                    .map(|b| b.value)
-                    .collect();
-                let instructions = match disassembler::disassemble(
-                    hole_buffer,
-                    &self.pdb.architecture,
-                    disassembler::DISASSEMBLER::CAPSTONE,
-                ) {
-                    Ok(instructions) => instructions,
-                    Err(e) => {
-                        error!("{}", e);
-                        process::exit(1);
-                    }
-                };
+            assert_eq!(names(&pe), vec!["funcA", "funcB"]);
+        }
 
-                for instruction in instructions {
-                    if instruction.is_alignment() {
-                        for offset in 0..instruction.length {
-                            self.bytes[(hole.start + instruction.offset + offset) as usize]
-                                .set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
-                        }
-                    }
-                }
-            }
+        #[test]
+        fn symbols_outside_of_the_processed_sections_are_listed() {
+            let dump = include_str!("../fixtures/selftest/pe-sections.pdb.yaml");
+            let config = config::Config {
+                list_symbols_not_in_section: true,
+                ..testing::config()
+            };
+            let pe = testing::process_pe("pe-sections", dump, BINARY, config);
+
+            let names: Vec<&str> = pe
+                .symbols_not_in_section
+                .iter()
+                .map(|s| s.name.as_str())
+                .collect();
+            assert_eq!(names, vec!["lblInPdata"]);
         }
 
-        fn detect_holes(&self) -> Vec<groundtruth::Hole> {
-            let mut holes = Vec::new();
-            let mut hole_size = 0;
+        #[test]
+        fn load_thunk_of_a_delay_import_is_code() {
+            let binary = include_bytes!("../fixtures/selftest/pe-delay.exe");
+            let pe = testing::process_pe("pe-delay", DUMP, binary, testing::config());
+            let dump = dumper::create_dump_pe(&pe);
+
+            let import = dump
+                .imports
+                .iter()
+                .find(|i| i.name == "DelayedFunc")
+                .unwrap();
+            assert!(import.delayed);
+
+            let thunk = dump.byte_at(import.load_thunk.unwrap()).unwrap();
+            assert!(thunk.is_code());
+            assert!(thunk.is_instruction_start());
+        }
 
-            for (offset, byte) in self.bytes.iter().enumerate() {
-                // Check if this byte has currently no flags at all
-                if byte.get_flags().len() == 0 {
-                    hole_size += 1;
-                } else {
-                    if hole_size > 0 {
-                        holes.push(groundtruth::Hole {
-                            start: (offset - hole_size) as u64,
-                            end: (offset - 1) as u64,
-                            size: hole_size as u64,
-                        });
-                    }
-                    hole_size = 0;
-                }
-            }
+        #[test]
+        fn use_pdata_adds_functions_and_flags_unwind_info() {
+            let config = config::Config {
+                use_pdata: true,
+                ..testing::config()
+            };
+            let pe = testing::process_pe("pe-pdata", DUMP, BINARY, config);
 
-            // If the loop exited while detecting a new hole, that means a hole which shared its end with the buffer itself it will be lost. Recover it manually.
-            if hole_size > 0 {
-                holes.push(groundtruth::Hole {
-                    start: (self.bytes.len() - 1 - hole_size) as u64,
-                    end: (self.bytes.len() - 1) as u64,
-                    size: hole_size as u64,
-                });
+            assert_eq!(names(&pe), vec!["funcA", "funcB", "pdata_1020"]);
+
+            // The unwind info of pdata_1020 follows it within .text
+            assert!(pe.bytes[0x28..0x30].iter().all(|b| b.is_data()));
+        }
+
+        #[test]
+        fn jump_table_entries_are_relative_or_absolute() {
+            let fixtures = [
+                (
+                    "pe-jump-relative",
+                    include_str!("../fixtures/selftest/pe-jump-relative.pdb.yaml"),
+                    &include_bytes!("../fixtures/selftest/pe-jump-relative.exe")[..],
+                    groundtruth::JUMP_TABLE_ENTRY::RELATIVE,
+                ),
+                (
+                    "pe-jump-absolute",
+                    include_str!("../fixtures/selftest/pe-jump-absolute.pdb.yaml"),
+                    &include_bytes!("../fixtures/selftest/pe-jump-absolute.exe")[..],
+                    groundtruth::JUMP_TABLE_ENTRY::ABSOLUTE,
+                ),
+            ];
+
+            for (test, dump, binary, entry) in fixtures {
+                let pe = testing::process_pe(test, dump, binary, testing::config());
+
+                let entries: Vec<groundtruth::JUMP_TABLE_ENTRY> =
+                    pe.jump_tables.iter().map(|t| t.entry).collect();
+                assert_eq!(entries, vec![entry], "{}", test);
+                assert_eq!(pe.instructions.len(), 18, "{}", test);
             }
+        }
+
+        #[test]
+        fn jump_table_overrunning_its_function_is_clamped() {
+            let dump = include_str!("../fixtures/selftest/pe-jump-overrun.pdb.yaml");
+            let pe = testing::process_pe("pe-jump-overrun", dump, BINARY, testing::config());
 
-            holes
+            assert_eq!(
+                pe.warnings.count(groundtruth::WARNING::JUMP_TABLE_OVERRUN),
+                1
+            );
+
+            // The table ends with funcA, funcB is still disassembled
+            let func_b = pe.pdb.functions.iter().find(|f| f.name == "funcB").unwrap();
+            let dump = dumper::create_dump_pe(&pe);
+            let table = dump.functions.iter().flat_map(|f| &f.data).next().unwrap();
+            assert!(table.offset + table.size <= func_b.offset);
         }
     }
 }
 
 pub mod elf {
-    use log::{debug, error, info, warn};
+    use std::fs;
     use std::path;
     use std::process;
+    use tracing::{debug, error, info, instrument, warn};
 
-    use crate::disassembler;
+    use crate::b2g::common;
+    use crate::b2g::pipeline::{Pipeline, State};
+    use crate::config;
     use crate::dumper;
     use crate::elf;
     use crate::groundtruth;
     use crate::parser;
 
     pub struct ELF {
-        pub architecture: groundtruth::ARCHITECTURE,
+        pub architecture: groundtruth::ArchInfo,
+        pub config: config::Config,
         pub file_name: String,
+        /// Original contents of the binary.
+        pub binary: Vec<u8>,
         pub dwarf: groundtruth::DWARF,
         pub sections: Vec<groundtruth::Section>,
         pub bytes: Vec<groundtruth::Byte>,
         pub instructions: Vec<groundtruth::Instruction>,
+        pub gaps: Vec<groundtruth::Gap>,
+        pub size_discrepancies: Vec<groundtruth::SizeDiscrepancy>,
+        pub mid_instruction_targets: Vec<groundtruth::MidInstructionTarget>,
+        /// Function starts recovered from their prologues.
+        pub recovered_functions: Vec<groundtruth::RecoveredFunction>,
+        /// Functions without a return instruction.
+        pub noreturn_functions: Vec<groundtruth::NoReturnFunction>,
+        pub difficulty: groundtruth::Difficulty,
+        /// Data sections mapped by `--include-data-sections`.
+        pub data_sections: Vec<groundtruth::DataSection>,
+        /// Address space of the outputs.
+        pub address_space: dumper::AddressSpace,
+        /// Warnings emitted while processing.
+        pub warnings: groundtruth::Warnings,
     }
 
     impl ELF {
-        pub fn new(path_to_yaml: &str, path_to_elf: &str) -> Self {
+        pub fn new(path_to_yaml: &str, path_to_elf: &str, config: config::Config) -> Self {
             // Grab filename from path
-            let file_name = path::Path::new(path_to_elf)
-                .file_stem()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_string();
+            let file_name = common::file_stem(path::Path::new(path_to_elf));
 
-            // Collect symbols from DWARF debugging information.
-            let elf = match parser::yaml::elf::load_elf(path_to_yaml) {
-                Ok(elf) => elf,
-                Err(e) => {
-                    error!("{}", e);
-                    process::exit(1);
+            // Several dumps can be given as a comma-separated list
+            let mut dump_yamls = Vec::new();
+
+            for path in path_to_yaml.split(',') {
+                match parser::yaml::elf::read_elf(path) {
+                    Ok(dump_yaml) => dump_yamls.push(dump_yaml),
+                    Err(_e) => {
+                        error!("Could not read file {}!", path);
+                        process::exit(1);
+                    }
                 }
-            };
+            }
 
-            // Retrieve architecture.
-            let architecture = match elf::get_architecture(path_to_elf) {
-                Ok(architecture) => architecture,
-                Err(e) => {
-                    error!("{}", e);
+            let binary = match fs::read(path_to_elf) {
+                Ok(binary) => binary,
+                Err(_e) => {
+                    error!("Could not read file {}!", path_to_elf);
                     process::exit(1);
                 }
             };
 
-            // Collect sections.
-            let sections = match elf::parse_sections(path_to_elf) {
-                Ok(sections) => sections,
+            match ELF::from_buffers(&dump_yamls, &binary, config) {
+                Ok(elf) => ELF { file_name, ..elf },
                 Err(e) => {
                     error!("{}", e);
                     process::exit(1);
                 }
-            };
+            }
+        }
+
+        /// Creates the pipeline from a YAML dump and a binary which are already in memory. The
+        /// file name used for the outputs defaults to `binary`.
+        pub fn from_buffer(
+            dump_yaml: &str,
+            binary: &[u8],
+            config: config::Config,
+        ) -> Result<Self, &'static str> {
+            ELF::from_buffers(&[dump_yaml.to_string()], binary, config)
+        }
+
+        /// Like [`ELF::from_buffer`] but merges the symbols of several YAML dumps. Earlier dumps
+        /// take priority if two dumps provide a symbol at the same location.
+        pub fn from_buffers(
+            dump_yamls: &[String],
+            binary: &[u8],
+            config: config::Config,
+        ) -> Result<Self, &'static str> {
+            // Guard: Inputs have to fit into the memory limit
+            common::check_memory_limit(config.max_memory, dump_yamls, binary)?;
+
+            // Collect symbols from DWARF debugging information.
+            let dwarf = parser::yaml::elf::load_elfs_from_strs(dump_yamls)?;
+
+            // Retrieve architecture.
+            let architecture = elf::get_architecture_from_buffer(binary)?;
+
+            // Collect sections.
+            let sections = elf::parse_sections_from_buffer(binary)?;
 
             // Create raw byte vector from binary.
-            let bytes = match elf::read_elf(path_to_elf) {
-                Ok(byte_vector) => byte_vector,
-                Err(e) => {
-                    error!("{}", e);
-                    process::exit(1);
-                }
-            };
+            let bytes = elf::read_elf_from_buffer(binary)?;
 
-            ELF {
-                file_name,
+            // Symbols the parser had to skip are the first warnings of the run
+            let mut warnings = groundtruth::Warnings::default();
+            warnings.add_many(groundtruth::WARNING::SKIPPED_SYMBOL, dwarf.skipped_symbols);
+            common::report_section_overlaps(&sections, &mut warnings);
+
+            Ok(ELF {
+                file_name: "binary".to_string(),
+                binary: binary.to_vec(),
                 architecture,
-                dwarf: elf,
+                config,
+                dwarf,
                 sections,
                 bytes,
                 instructions: Vec::new(),
-            }
+                gaps: Vec::new(),
+                size_discrepancies: Vec::new(),
+                mid_instruction_targets: Vec::new(),
+                recovered_functions: Vec::new(),
+                noreturn_functions: Vec::new(),
+                difficulty: groundtruth::Difficulty::default(),
+                data_sections: Vec::new(),
+                address_space: dumper::AddressSpace::default(),
+                warnings,
+            })
         }
 
+        #[instrument(skip_all, fields(binary = %self.file_name))]
         pub fn process(&mut self) {
             // Grab text section
-            let text_section = match self.sections.iter().find(|s| s.name == ".text") {
-                Some(text_section) => text_section.clone(),
-                None => {
-                    error!("[-] Binary does not have a text section.");
-                    process::exit(1);
-                }
-            };
-
-            debug!(
-                "[+] .text section identified (start: {:x}, size: {:x}, va: {:x}).",
-                text_section.raw_data_offset, text_section.raw_data_size, text_section.va
-            );
+            let text_section = self.select_code_section();
+            self.address_space = dumper::address_space_elf(self, &text_section);
 
-            // Pre-process functions
-            self.preprocess_functions();
+            // Add functions from other tools
+            if self.config.functions_from.is_some() {
+                self.import_functions();
+            }
 
-            // Set byte flags (code/data is already known)
-            self.set_byte_flags();
+            if self.config.demangle {
+                common::demangle_functions(&mut self.dwarf.functions);
+            }
 
-            // Disassemble code bytes (functions)
-            self.disassemble();
+            // Check all symbols against their sections before anything indexes the bytes
+            if self.config.validate_offsets {
+                self.validate_offsets();
+            }
 
-            // Trim byte vector (we only need the data of text section) that means cut before raw
-            // data start and after raw data end
-            self.trim_byte_vector(
-                text_section.raw_data_offset,
-                text_section.raw_data_offset + text_section.raw_data_size,
-            );
+            let function_count = self.dwarf.functions.len();
 
-            self.rebase_byte_vector(text_section.va);
+            // Pre-process functions
+            self.preprocess_functions(&text_section);
+
+            // Only count the symbols
+            if self.config.count_only {
+                println!("Functions:         {}", function_count);
+                println!(
+                    "  in {}:         {}",
+                    text_section.name,
+                    self.dwarf.functions.len()
+                );
+                println!("Skipped symbols:   {}", self.dwarf.skipped_symbols);
+                return;
+            }
 
-            // Detect alignment/filler bytes
-            self.detect_alignment_bytes();
+            // Restrict processing to a single function
+            if let Some(name) = &self.config.function {
+                common::select_function(&mut self.dwarf.functions, name);
+            }
 
-            // Detect end of section
-            self.detect_end_of_section();
+            // Flag the literal pools of ARM and Thumb functions as in-line data
+            common::detect_literal_pools(
+                &self.bytes,
+                &mut self.dwarf.functions,
+                &self.dwarf.architecture,
+            );
 
-            // Create debug print
-            self.print();
+            // Disassemble the functions and analyze the instructions
+            self.classify_code();
 
-            // Create final mapping
-            dumper::plain::dump_elf(&self);
-            dumper::yaml::dump_elf(&self);
-        }
+            // Map instructions to source lines
+            if self.config.source_lines {
+                self.annotate_source_lines();
+            }
 
-        fn disassemble(&mut self) {
-            for function in &mut self.dwarf.functions {
-                let mut function_buffer = Vec::new();
+            // Detect alignment, gaps and the end of the section
+            self.classify_remaining();
 
-                for offset in 0..function.size {
-                    // Guard: TODO
-                    if (function.offset + offset) as usize >= self.bytes.len() {
-                        warn!(
-                            "[-] Function {} (allegedly) ends outside of the text section.",
-                            function.name
-                        );
-                        return;
-                    }
+            // Map the data sections next to the code section
+            if self.config.include_data_sections {
+                self.classify_data_sections();
+            }
 
-                    // Guard: Byte already flagged as data
-                    if self.bytes[(function.offset + offset) as usize].is_data() {
-                        continue;
-                    }
+            // Check that trimming/rebasing did not corrupt any byte values
+            if self.config.verify {
+                self.verify_bytes(&text_section);
+            }
 
-                    // Set specific flags
-                    self.bytes[(function.offset + offset) as usize].set_flags(vec![
-                        groundtruth::FLAG::CODE,
-                        groundtruth::FLAG::READABLE,
-                        groundtruth::FLAG::EXECUTABLE,
-                    ]);
+            self.finish(&text_section);
+        }
 
-                    // Add byte to function buffer
-                    function_buffer.push(self.bytes[(function.offset + offset) as usize].value);
+        #[instrument(skip_all)]
+        fn annotate_source_lines(&mut self) {
+            let ranges = match elf::parse_line_ranges_from_buffer(&self.binary) {
+                Ok(ranges) => ranges,
+                Err(e) => {
+                    warn!("{} Instructions are not mapped to source lines.", e);
+                    return;
                 }
+            };
 
-                // Set function start and end
-                self.bytes[function.offset as usize]
-                    .set_flags(vec![groundtruth::FLAG::FUNCTION_START]);
-                self.bytes[(function.offset + function.size - 1) as usize]
-                    .set_flags(vec![groundtruth::FLAG::FUNCTION_END]);
-
-                // Disassemble function bytes
-                let instructions = match disassembler::disassemble(
-                    function_buffer,
-                    &self.dwarf.architecture,
-                    disassembler::DISASSEMBLER::CAPSTONE,
-                ) {
-                    Ok(instructions) => instructions,
-                    Err(e) => {
-                        error!("{}", e);
-                        process::exit(1);
-                    }
-                };
-                // Set instruction start and end, copy instruction flags
-                for instruction in instructions {
-                    self.bytes[(function.offset + instruction.offset) as usize]
-                        .set_flags(vec![groundtruth::FLAG::INSTRUCTION_START]);
+            let annotated = map_source_lines(&mut self.instructions, &ranges);
 
-                    // Instruction End Example: Start 0x0, Size 0x8 => Instruction: 0x0-0x8 therefore the 8th byte (the last byte) is 0x7
-                    self.bytes
-                        [(function.offset + instruction.offset + instruction.length - 1) as usize]
-                        .set_flags(vec![groundtruth::FLAG::INSTRUCTION_END]);
+            info!(
+                "Mapped {}/{} instructions to source lines.",
+                annotated,
+                self.instructions.len()
+            );
+        }
 
-                    // TODO: Set instruction flags for not only the first byte of instruction
-                    self.bytes[(function.offset + instruction.offset) as usize]
-                        .set_flags(instruction.get_flags());
+        /// Drops the symbols which do not fit a section. Symbol values are virtual addresses, the
+        /// section indices of the dump do not match the sections of the binary.
+        #[instrument(skip_all)]
+        fn validate_offsets(&mut self) {
+            let sections = &self.sections;
+            let bounds = |address: u64| {
+                sections
+                    .iter()
+                    .filter(|s| s.va > 0)
+                    .find(|s| address >= s.va && address < s.va + s.raw_data_size)
+                    .map(|s| (s.va, s.va + s.raw_data_size))
+            };
 
-                    // Append to instructions vector
-                    self.instructions.push(instruction);
-                }
-            }
+            let mut violations = Vec::new();
+
+            self.dwarf.functions.retain(|f| {
+                common::fits_section(
+                    "Function",
+                    &f.name,
+                    f.offset,
+                    f.size,
+                    bounds(f.offset),
+                    &mut violations,
+                )
+            });
+            self.dwarf.data.retain(|d| {
+                common::fits_section(
+                    "Data",
+                    &d.name,
+                    d.offset,
+                    d.size,
+                    bounds(d.offset),
+                    &mut violations,
+                )
+            });
+
+            common::report_offset_violations(violations, self.config.strict, &mut self.warnings);
         }
 
-        fn preprocess_functions(&mut self) {
-            self.dwarf.functions.retain(|ref f| f.size > 0)
+        /// Adds the functions of `--functions-from`. Functions which the dump already provides at
+        /// the same address are dropped.
+        #[instrument(skip_all)]
+        fn import_functions(&mut self) {
+            let functions = common::load_function_list(&self.config);
+
+            // Section indices of the dump and the function list do not match, only compare addresses
+            parser::yaml::merge_by_location(&mut self.dwarf.functions, functions, |f| {
+                (0, f.offset)
+            });
+            self.dwarf.functions.sort_by_key(|a| a.offset);
         }
 
-        fn set_byte_flags(&mut self) {
-            for function in &self.dwarf.functions {
-                // Set data flags
-                // Attention: we have to use the child data of a function and not from the normal
-                // data collection because ONLY the child data has a up-to-date size value.
-                for data in &function.data {
-                    for i in 0..data.size {
-                        self.bytes[(data.offset + i) as usize]
-                            .set_flags(vec![groundtruth::FLAG::DATA]);
-                    }
-                }
+        #[instrument(skip_all)]
+        fn preprocess_functions(&mut self, text_section: &groundtruth::Section) {
+            // Symbol values are virtual addresses, which only equal the file offsets of the
+            // section for some (e.g. position independent) binaries
+            let start = text_section.va;
+            let end = text_section.va + text_section.raw_data_size;
 
-                // Set data and code flags
-                for i in 0..function.size {
-                    // Guard: Check if function size is greater than section size.
-                    if (function.offset + i) as usize >= self.bytes.len() {
-                        warn!(
-                            "[-] Function {} (allegedly) ends outside of the text section.",
-                            function.name
-                        );
-                        break;
-                    }
+            let function_count = self.dwarf.functions.len();
 
-                    // Guard: Check if byte is already data (because there is data within the function)
-                    if self.bytes[(function.offset + i) as usize].is_data() {
-                        continue;
-                    }
+            // Only keep functions which start within the text section
+            self.dwarf
+                .functions
+                .retain(|f| f.size > 0 && f.offset >= start && f.offset < end);
 
-                    self.bytes[(function.offset + i) as usize]
-                        .set_flags(vec![groundtruth::FLAG::CODE]);
-                }
+            debug!(
+                "{} functions are not located in the text section.",
+                function_count - self.dwarf.functions.len()
+            );
+
+            // Make function offsets relative to the text section like the PDB offsets
+            for function in &mut self.dwarf.functions {
+                function.offset -= start;
             }
-        }
 
-        fn trim_byte_vector(&mut self, start: u64, end: u64) {
-            // Cut current start to new start and new end to current end
-            self.bytes.drain(..start as usize);
-            self.bytes.drain((end - start) as usize..);
+            common::limit_functions(&mut self.dwarf.functions, self.config.limit_functions);
         }
+    }
 
-        fn rebase_byte_vector(&mut self, base: u64) {
-            // Reset offsets
-            for (offset, byte) in self.bytes.iter_mut().enumerate() {
-                byte.offset = offset as u64 + base;
+    /// Sets the source location of every instruction covered by one of the line ranges (sorted by
+    /// start) and returns their number. Instructions outside of every range (e.g. of units compiled
+    /// without line information) keep no location.
+    fn map_source_lines(
+        instructions: &mut [groundtruth::Instruction],
+        ranges: &[groundtruth::LineRange],
+    ) -> usize {
+        let mut annotated = 0;
+
+        for instruction in instructions {
+            // Find the last range starting at or before the instruction
+            let index = ranges.partition_point(|r| r.start <= instruction.address);
+
+            if let Some(range) = index.checked_sub(1).map(|i| &ranges[i]) {
+                if instruction.address < range.end {
+                    instruction.source = Some(range.location.clone());
+                    annotated += 1;
+                }
             }
         }
 
-        fn print(&self) {
-            debug!("######## META ###########");
-            debug!("{:?}", self.dwarf.architecture);
-
-            debug!("######## SECTIONS #########");
-            for section in &self.sections {
-                debug!("{:x?}", section);
-            }
-
-            debug!("######## FUNCTIONS #########");
-            for function in &self.dwarf.functions {
-                debug!("{:x?}", function);
-            }
-
-            debug!("### DATA IN FUNCTION ###");
-            for function in self.dwarf.functions.iter().filter(|f| f.data.len() > 0) {
-                debug!(
-                    "{:?} {:x?} {:x?}",
-                    function.name, function.offset, function.size
-                );
-                for data in &function.data {
-                    debug!("\t{:x?}", data);
-                }
-            }
+        annotated
+    }
 
-            let holes = self.detect_holes();
-            debug!("######## HOLES #########");
-            let mut unknown_bytes = 0;
-            for hole in holes {
-                debug!("{:x?}", hole);
-                unknown_bytes += hole.size;
+    impl Pipeline for ELF {
+        fn state(&mut self) -> State<'_> {
+            State {
+                config: &self.config,
+                architecture: &self.dwarf.architecture,
+                sections: &self.sections,
+                bytes: &mut self.bytes,
+                functions: &mut self.dwarf.functions,
+                instructions: &mut self.instructions,
+                gaps: &mut self.gaps,
+                size_discrepancies: &mut self.size_discrepancies,
+                mid_instruction_targets: &mut self.mid_instruction_targets,
+                recovered_functions: &mut self.recovered_functions,
+                noreturn_functions: &mut self.noreturn_functions,
+                data_sections: &mut self.data_sections,
+                difficulty: &mut self.difficulty,
+                warnings: &mut self.warnings,
             }
-
-            debug!("####### COUNT ########");
-            debug!("Functions: {}", self.dwarf.functions.len());
-
-            debug!("##### STATISTICS ######");
-            debug!(
-                "Identified bytes {:.2}/{:.2} ({:.2}%)",
-                (self.bytes.len() as u64 - unknown_bytes),
-                self.bytes.len(),
-                100.0 * (self.bytes.len() as u64 - unknown_bytes) as f64 / self.bytes.len() as f64
-            );
-            debug!("Tail: 0x{:x}", self.bytes.len())
         }
 
-        fn detect_end_of_section(&mut self) {
-            // Get current section (vector) size
-            let mut section_size = self.bytes.len();
-
-            // Check whole byte vector but start from the end
-            for byte in self.bytes.iter().rev() {
-                // Guard: Only if this byte currently does not have any purpose
-                if byte.is_code() || byte.is_data() {
-                    break;
-                }
+        // Symbol values are virtual addresses
+        fn base(&self, text_section: &groundtruth::Section) -> u64 {
+            text_section.va
+        }
 
-                // Check if byte is 0x0 and reduce vector size
-                if byte.value == 0x0 {
-                    section_size -= 1;
-                }
-            }
+        fn read_binary(&self) -> Result<Vec<groundtruth::Byte>, &'static str> {
+            elf::read_elf_from_buffer(&self.binary)
+        }
 
-            // Remove the empty tail
-            self.bytes.truncate(section_size);
+        // Symbol values are virtual addresses
+        fn data_symbols(&self) -> Vec<groundtruth::DataSymbol> {
+            self.dwarf
+                .data
+                .iter()
+                .map(|d| groundtruth::DataSymbol {
+                    name: d.name.clone(),
+                    address: d.offset,
+                    size: d.size,
+                })
+                .collect()
         }
 
-        fn detect_alignment_bytes(&mut self) {
-            // Check whole byte vector for known alignment bytes
-            for byte in &mut self.bytes {
-                // Guard: Only if this byte currently does not have any purpose
-                if byte.is_code() || byte.is_data() {
-                    continue;
-                }
+        fn create_dump(&self) -> dumper::Dump {
+            dumper::create_dump_elf(self)
+        }
 
-                // Check if byte is 0xCC (int3)
-                if byte.value == 0xCC {
-                    byte.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
+        fn write_outputs(&self) {
+            match self.config.format {
+                config::FORMAT::YAML => {
+                    dumper::plain::dump_elf(self);
+                    dumper::yaml::dump_elf(self);
                 }
+                config::FORMAT::OBJDUMP => dumper::objdump::dump_elf(self),
+                config::FORMAT::DOT => dumper::dot::dump_elf(self),
+                config::FORMAT::LABELS_BIN => dumper::labels::dump_elf(self),
             }
+        }
+    }
 
-            // Find holes and check of the holes are multi-byte nops
-            let holes = self.detect_holes();
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::testing;
+        use crate::testing::instruction;
+
+        const DUMP: &str = include_str!("../fixtures/selftest/elf.obj.yaml");
+        const BINARY: &[u8] = include_bytes!("../fixtures/selftest/elf");
+
+        #[test]
+        fn map_source_lines_skips_instructions_without_line_information() {
+            let binary = include_bytes!("../fixtures/tests/lines");
+            let ranges = elf::parse_line_ranges_from_buffer(binary).unwrap();
+
+            // with_lines, without_lines (no line information) and behind the section
+            let mut instructions = vec![
+                instruction("nop", 0x401000, 1),
+                instruction("nop", 0x401012, 1),
+                instruction("nop", 0x500000, 1),
+            ];
+
+            assert_eq!(map_source_lines(&mut instructions, &ranges), 1);
+
+            let source = instructions[0].source.as_ref().unwrap();
+            assert!(source.file.ends_with("lines.c"));
+            assert_eq!(source.line, 6);
+            assert!(instructions[1].source.is_none());
+            assert!(instructions[2].source.is_none());
+        }
 
-            for hole in holes {
-                // Get buffer of hole and disassemble it
-                let hole_buffer = self.bytes[hole.start as usize..hole.end as usize]
-                    .iter()
-                    .map(|b| b.value)
-                    .collect();
-                let instructions = match disassembler::disassemble(
-                    hole_buffer,
-                    &self.dwarf.architecture,
-                    disassembler::DISASSEMBLER::CAPSTONE,
-                ) {
-                    Ok(instructions) => instructions,
-                    Err(e) => {
-                        error!("{}", e);
-                        process::exit(1);
-                    }
-                };
+        #[test]
+        fn functions_are_located_by_their_virtual_address() {
+            // .text starts at 0x401020 but at file offset 0x1020
+            let elf = testing::process_elf("elf-va", DUMP, BINARY, testing::config());
+
+            let main = elf
+                .dwarf
+                .functions
+                .iter()
+                .find(|f| f.name == "main")
+                .unwrap();
+            assert_eq!(main.offset, 0x401122 - 0x401020);
+
+            let first = elf.instructions.iter().find(|i| i.address == 0x401122);
+            assert!(first.is_some());
+            assert!(elf.bytes[main.offset as usize].is_function_start());
+        }
 
-                for instruction in instructions {
-                    if instruction.is_alignment() {
-                        for offset in 0..instruction.length {
-                            self.bytes[(hole.start + instruction.offset + offset) as usize]
-                                .set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
-                        }
-                    }
-                }
+        #[test]
+        fn data_symbols_are_attributed_to_their_section() {
+            let dump = include_str!("../fixtures/selftest/data.obj.yaml");
+            let binary = include_bytes!("../fixtures/selftest/data");
+            let elf = testing::process_elf("elf-data", dump, binary, testing::config());
+
+            let data = elf
+                .data_sections
+                .iter()
+                .find(|s| s.name == ".data")
+                .unwrap();
+            for name in ["limits", "greeting", "counter"] {
+                assert!(data.symbols.iter().any(|s| s.name == name), "{}", name);
             }
         }
 
-        fn detect_holes(&self) -> Vec<groundtruth::Hole> {
-            let mut holes = Vec::new();
-            let mut hole_size = 0;
+        #[test]
+        fn functions_without_return_and_how_they_end() {
+            let dump = include_str!("../fixtures/selftest/noreturn.obj.yaml");
+            let binary = include_bytes!("../fixtures/selftest/noreturn");
+            let elf = testing::process_elf("elf-noreturn", dump, binary, testing::config());
+
+            let noreturn: Vec<(&str, groundtruth::NORETURN_REASON)> = elf
+                .noreturn_functions
+                .iter()
+                .map(|f| (f.name.as_str(), f.reason))
+                .collect();
+            assert_eq!(
+                noreturn,
+                vec![
+                    ("_start", groundtruth::NORETURN_REASON::OTHER),
+                    ("breakpoint", groundtruth::NORETURN_REASON::TRAP),
+                    ("fail", groundtruth::NORETURN_REASON::CALL),
+                ]
+            );
+        }
 
-            for (offset, byte) in self.bytes.iter().enumerate() {
-                // Check if this byte has currently no flags at all
-                if byte.get_flags().len() == 0 {
-                    hole_size += 1;
-                } else {
-                    if hole_size > 0 {
-                        holes.push(groundtruth::Hole {
-                            start: (offset - hole_size) as u64,
-                            end: (offset - 1) as u64,
-                            size: hole_size as u64,
-                        });
-                    }
-                    hole_size = 0;
+        #[test]
+        fn literal_pools_of_thumb_functions_are_data() {
+            let dump = include_str!("../fixtures/selftest/thumb.obj.yaml");
+            let binary = include_bytes!("../fixtures/selftest/thumb");
+            let elf = testing::process_elf("elf-thumb", dump, binary, testing::config());
+
+            // The Thumb bit is cleared from the symbol values
+            let functions: Vec<(&str, u64, bool)> = elf
+                .dwarf
+                .functions
+                .iter()
+                .map(|f| (f.name.as_str(), f.offset, f.thumb))
+                .collect();
+            assert_eq!(functions, vec![("answer", 0, true), ("twice", 0x10, true)]);
+
+            let pools: Vec<(u64, u64)> = elf
+                .dwarf
+                .functions
+                .iter()
+                .flat_map(|f| &f.data)
+                .map(|d| (d.offset, d.size))
+                .collect();
+            assert_eq!(pools, vec![(0x8, 8), (0x20, 4)]);
+
+            for (offset, size) in pools {
+                for byte in &elf.bytes[offset as usize..(offset + size) as usize] {
+                    assert!(byte.is_data() && !byte.is_instruction_start());
                 }
             }
 
-            // If the loop exited while detecting a new hole, that means a hole which shared its end with the buffer itself it will be lost. Recover it manually.
-            if hole_size > 0 {
-                holes.push(groundtruth::Hole {
-                    start: (self.bytes.len() - 1 - hole_size) as u64,
-                    end: (self.bytes.len() - 1) as u64,
-                    size: hole_size as u64,
-                });
-            }
+            let returns: Vec<&str> = elf
+                .instructions
+                .iter()
+                .filter(|i| !i.falls_through())
+                .map(|i| i.mnemonic.as_str())
+                .collect();
+            assert_eq!(elf.instructions.len(), 11);
+            assert_eq!(returns, vec!["bx", "pop"]);
+        }
+
+        #[test]
+        fn function_timeout_skips_every_function() {
+            let config = config::Config {
+                function_timeout: Some(0),
+                ..testing::config()
+            };
+            let elf = testing::process_elf("elf-timeout", DUMP, BINARY, config);
 
-            holes
+            assert_eq!(elf.dwarf.functions.len(), 5);
+            assert!(elf.instructions.is_empty());
         }
     }
 }
@@ -0,0 +1,197 @@
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+use crate::b2g::common;
+
+/// Offset of the `ustar` magic within the header of a tar member.
+const TAR_MAGIC_OFFSET: usize = 257;
+
+/// Represents a binary and its YAML dump read from a single archive (see `--bundle`).
+pub struct Bundle {
+    /// Member name of the binary.
+    pub binary_name: String,
+    pub binary: Vec<u8>,
+    /// Member name of the dump.
+    pub dump_name: String,
+    pub dump: String,
+}
+
+impl Bundle {
+    /// File name of the binary without directories and extension, names the outputs.
+    pub fn file_stem(&self) -> String {
+        common::file_stem(Path::new(&self.binary_name))
+    }
+}
+
+/// Whether the buffer holds a tar or zip archive.
+pub fn is_bundle(buffer: &[u8]) -> bool {
+    is_tar(buffer) || is_zip(buffer)
+}
+
+fn is_tar(buffer: &[u8]) -> bool {
+    buffer
+        .get(TAR_MAGIC_OFFSET..TAR_MAGIC_OFFSET + 5)
+        .is_some_and(|magic| magic == b"ustar")
+}
+
+fn is_zip(buffer: &[u8]) -> bool {
+    buffer.starts_with(b"PK\x03\x04")
+}
+
+/// Reads a bundle from a tar or zip archive on disk.
+pub fn read(path: &str) -> Result<Bundle, String> {
+    let buffer = fs::read(path).map_err(|e| format!("Could not read bundle {}: {}", path, e))?;
+    from_buffer(&buffer)
+}
+
+/// Extracts the binary and the dump of a tar or zip archive held in memory. The dump is the only
+/// member with a `.yaml` (or `.yml`) extension, the binary the only other file. Directories and
+/// hidden files (e.g. `__MACOSX/._binary`) are ignored.
+pub fn from_buffer(buffer: &[u8]) -> Result<Bundle, String> {
+    let members = if is_tar(buffer) {
+        tar_members(buffer)?
+    } else if is_zip(buffer) {
+        zip_members(buffer)?
+    } else {
+        return Err("Bundle is neither a tar nor a zip archive.".to_string());
+    };
+
+    let (dumps, binaries): (Vec<_>, Vec<_>) = members
+        .into_iter()
+        .filter(|(name, _)| !is_hidden(name))
+        .partition(|(name, _)| is_dump(name));
+
+    let (dump_name, dump) = single(dumps, "YAML dump (.yaml)")?;
+    let (binary_name, binary) = single(binaries, "binary")?;
+
+    let dump = String::from_utf8(dump)
+        .map_err(|_e| format!("Dump {} of the bundle is not valid UTF-8.", dump_name))?;
+
+    Ok(Bundle {
+        binary_name,
+        binary,
+        dump_name,
+        dump,
+    })
+}
+
+fn tar_members(buffer: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let error = |e: std::io::Error| format!("Could not read tar bundle: {}", e);
+
+    let mut archive = tar::Archive::new(Cursor::new(buffer));
+    let mut members = Vec::new();
+
+    for entry in archive.entries().map_err(error)? {
+        let mut entry = entry.map_err(error)?;
+
+        // Guard: Only regular files (no directories or links)
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let name = entry.path().map_err(error)?.to_string_lossy().into_owned();
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(error)?;
+
+        members.push((name, contents));
+    }
+
+    Ok(members)
+}
+
+fn zip_members(buffer: &[u8]) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(buffer))
+        .map_err(|e| format!("Could not read zip bundle: {}", e))?;
+    let mut members = Vec::new();
+
+    for index in 0..archive.len() {
+        let mut file = archive
+            .by_index(index)
+            .map_err(|e| format!("Could not read zip bundle: {}", e))?;
+
+        // Guard: Only files (no directories)
+        if !file.is_file() {
+            continue;
+        }
+
+        let name = file.name().to_string();
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .map_err(|e| format!("Could not read {} of the zip bundle: {}", name, e))?;
+
+        members.push((name, contents));
+    }
+
+    Ok(members)
+}
+
+fn is_dump(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    name.ends_with(".yaml") || name.ends_with(".yml")
+}
+
+fn is_hidden(name: &str) -> bool {
+    name.split('/')
+        .any(|part| (part.starts_with('.') && part != ".") || part == "__MACOSX")
+}
+
+/// The only member of the kind, an error if there is none or several.
+fn single(members: Vec<(String, Vec<u8>)>, kind: &str) -> Result<(String, Vec<u8>), String> {
+    match members.len() {
+        0 => Err(format!("Bundle does not contain a {}.", kind)),
+        1 => Ok(members.into_iter().next().unwrap()),
+        _ => Err(format!(
+            "Bundle contains several candidates for the {}: {}",
+            kind,
+            members
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<&str>>()
+                .join(", ")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn bundles_hold_the_pe_fixture_and_its_dump() {
+        let binary = include_bytes!("../fixtures/selftest/pe.exe");
+        let dump = include_str!("../fixtures/selftest/pe.pdb.yaml");
+        let expected = testing::process_pe("bundle-direct", dump, binary, testing::config());
+
+        for (test, path) in [
+            ("bundle-tar", "fixtures/selftest/pe-bundle.tar"),
+            ("bundle-zip", "fixtures/selftest/pe-bundle.zip"),
+        ] {
+            let bundle = read(path).unwrap();
+
+            assert_eq!(bundle.binary_name, "pe.exe", "{}", path);
+            assert_eq!(bundle.dump_name, "pe.pdb.yaml", "{}", path);
+            assert_eq!(bundle.file_stem(), "pe", "{}", path);
+
+            let pe = testing::process_pe(test, &bundle.dump, &bundle.binary, testing::config());
+
+            let names: Vec<&str> = pe.pdb.functions.iter().map(|f| f.name.as_str()).collect();
+            assert_eq!(names, vec!["funcA", "funcB"], "{}", path);
+            assert_eq!(
+                pe.instructions.len(),
+                expected.instructions.len(),
+                "{}",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn buffer_without_archive_is_rejected() {
+        let binary = include_bytes!("../fixtures/selftest/pe.exe");
+
+        assert!(!is_bundle(binary));
+        assert!(from_buffer(binary).is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use std::fs;
+
+use serde_derive::{Deserialize, Serialize};
+
+/// Offset basis of the 64-bit FNV-1a hash.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Prime of the 64-bit FNV-1a hash.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Represents a (dump, binary) pair which has been processed completely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Entry {
+    /// Hash of the contents of all inputs (see [`input_hash`]).
+    pub hash: String,
+    pub dump: String,
+    pub binary: String,
+}
+
+/// Records which inputs of a long (batch) run are done, so an interrupted run can be resumed.
+#[derive(Debug, Default)]
+pub struct Checkpoint {
+    path: String,
+    entries: Vec<Entry>,
+}
+
+impl Checkpoint {
+    /// Loads the checkpoint file, a missing file is an empty checkpoint.
+    pub fn load(path: &str) -> Result<Self, &'static str> {
+        let entries = match fs::read_to_string(path) {
+            Ok(s) => match serde_json::from_str(&s) {
+                Ok(entries) => entries,
+                Err(_e) => return Err("Could not parse checkpoint!"),
+            },
+            Err(_e) => Vec::new(),
+        };
+
+        Ok(Checkpoint {
+            path: path.to_string(),
+            entries,
+        })
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.entries.iter().any(|e| e.hash == hash)
+    }
+
+    /// Adds a completed pair and writes the checkpoint. The file is replaced atomically so an
+    /// interruption never leaves a truncated checkpoint behind.
+    pub fn record(&mut self, entry: Entry) -> Result<(), &'static str> {
+        if !self.contains(&entry.hash) {
+            self.entries.push(entry);
+        }
+
+        let s = match serde_json::to_string_pretty(&self.entries) {
+            Ok(s) => s,
+            Err(_e) => return Err("Could not serialize checkpoint!"),
+        };
+
+        let temporary = format!("{}.tmp", self.path);
+
+        match fs::write(&temporary, s).and_then(|_| fs::rename(&temporary, &self.path)) {
+            Ok(_) => Ok(()),
+            Err(_e) => Err("Could not write checkpoint!"),
+        }
+    }
+}
+
+/// Hashes the contents of the given files (FNV-1a, 64 bit) so renamed or copied inputs are still
+/// recognized and changed inputs are processed again.
+pub fn input_hash(paths: &[&str]) -> Result<String, &'static str> {
+    let mut hash = FNV_OFFSET_BASIS;
+
+    for path in paths {
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(_e) => return Err("Could not read file!"),
+        };
+
+        // Separate the files, otherwise moving bytes between them keeps the hash
+        for byte in contents
+            .iter()
+            .chain(&(contents.len() as u64).to_le_bytes())
+        {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    Ok(format!("{:016x}", hash))
+}
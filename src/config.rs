@@ -0,0 +1,327 @@
+use serde_derive::{Deserialize, Serialize};
+
+use crate::disassembler;
+
+/// Describes the output formats.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FORMAT {
+    /// Plain byte mapping (`.txt`) and YAML dump (`.yaml`).
+    YAML,
+    /// Disassembly listing like `objdump -d -M intel` (`.objdump`).
+    OBJDUMP,
+    /// Control-flow graph of every function in the DOT format of Graphviz (`.dot`).
+    DOT,
+    /// Flags of every byte as a little-endian bitfield (`.labels.bin`), see `dumper::labels`.
+    LABELS_BIN,
+}
+
+/// Describes the formats of function lists exported from other tools.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FUNCTIONS_FORMAT {
+    /// CSV export of the Ghidra Functions window (`Name`, `Location`, `Function Size`).
+    GHIDRA,
+    /// Tab-separated copy of the IDA Functions window (`Function name`, `Segment`, `Start`,
+    /// `Length`).
+    IDA,
+}
+
+/// Describes which parts of the mapping are written to the YAML dump.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum OUTPUT_KIND {
+    /// Everything.
+    #[default]
+    ALL,
+    /// Code bytes, functions and instructions.
+    CODE,
+    /// Data bytes and the functions containing in-line data.
+    DATA,
+    /// Functions and instructions only.
+    INSTRUCTIONS,
+}
+
+/// Describes the order of the functions and instructions in the YAML dump.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SORT_ORDER {
+    /// Ascending address, the only order which is stable for diffing.
+    #[default]
+    ADDRESS,
+    /// Function name (demangled if available).
+    NAME,
+    /// Function size, largest first.
+    SIZE,
+}
+
+/// Describes the address space of the addresses written to the outputs.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ADDRESS_MODE {
+    /// Relative to the image base.
+    RVA,
+    /// Image base plus RVA (the address without relocation).
+    #[default]
+    VA,
+    /// Offset within the binary file.
+    FILE,
+}
+
+/// Compression applied to the written outputs.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum COMPRESSION {
+    /// Plain files.
+    NONE,
+    /// gzip (`.gz`).
+    GZIP,
+    /// Zstandard (`.zst`).
+    ZSTD,
+}
+
+/// Describes the assembly syntax of the instruction text.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SYNTAX {
+    /// `mov rax, qword ptr [rbp - 8]`
+    INTEL,
+    /// `movq -8(%rbp), %rax` (default of objdump and GDB).
+    ATT,
+}
+
+/// Weights of the components of the disassembly difficulty score.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyWeights {
+    pub inline_data: f64,
+    pub decode_failures: f64,
+    pub hole_entropy: f64,
+    pub mid_instruction_targets: f64,
+    pub transitions: f64,
+}
+
+impl Default for DifficultyWeights {
+    fn default() -> Self {
+        DifficultyWeights {
+            inline_data: 1.0,
+            decode_failures: 1.0,
+            hole_entropy: 1.0,
+            mid_instruction_targets: 1.0,
+            transitions: 1.0,
+        }
+    }
+}
+
+/// Options controlling how the groundtruth mapping is generated.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Re-read the processed section from the binary and check every emitted byte value.
+    pub verify: bool,
+    /// Byte values treated as single-byte padding fill when found in a hole.
+    pub padding_bytes: Vec<u8>,
+    /// Drop into an interactive prompt after processing.
+    pub repl: bool,
+    /// Keep the trailing 0x00 bytes of the section instead of cutting them off.
+    pub no_truncate: bool,
+    /// Names of code sections which are never processed.
+    pub exclude_sections: Vec<String>,
+    /// Serializes address-like fields in the YAML dump as hex strings instead of integers.
+    pub hex_addresses: bool,
+    /// Serializes instruction bytes in the YAML dump as hex strings instead of integer sequences.
+    pub hex_bytes: bool,
+    /// Appends `function+0xNN` to the addresses of the RAW mapping and the objdump listing.
+    pub symbolic_addresses: bool,
+    /// Processes only the first N functions (ordered by offset) for quick test runs.
+    pub limit_functions: Option<usize>,
+    /// Flags IAT entries and import thunks within the code section (PE only).
+    pub include_imports: bool,
+    /// Restricts the output to the function with this name.
+    pub function: Option<String>,
+    /// Output format written after processing.
+    pub format: FORMAT,
+    /// Records why every byte got its flags and adds it to the YAML dump.
+    pub explain: bool,
+    /// Mnemonics (e.g. `rdtsc`) or full instructions (e.g. `int 0x2d`) flagged as suspicious.
+    pub flag_mnemonics: Vec<String>,
+    /// Shrinks functions to the extent of their disassembly if the declared size is larger.
+    pub trust_disassembly: bool,
+    /// Flags the hotpatch area in front of every function as alignment. Enabled automatically
+    /// if the PDB reports a module compiled with `/hotpatch`.
+    pub hotpatch_padding: bool,
+    /// Replaces addresses in instruction operands with a placeholder for fuzzy diffing.
+    pub normalize_operands: bool,
+    /// Adds the explicit operands of every instruction with their kind and access to the dump.
+    pub detailed_operands: bool,
+    /// Function list exported from Ghidra or IDA whose functions are added to the symbols.
+    pub functions_from: Option<String>,
+    /// Format of the function list given with `functions_from`.
+    pub functions_format: FUNCTIONS_FORMAT,
+    /// Fraction of anomalous instructions in the first function above which the architecture is
+    /// reported as possibly wrong.
+    pub anomaly_threshold: f64,
+    /// Parts of the mapping written to the YAML dump.
+    pub output_kind: OUTPUT_KIND,
+    /// Order of the functions and instructions in the YAML dump.
+    pub sort_output: SORT_ORDER,
+    /// Adds and validates function boundaries with the `.pdata` exception directory (PE x64).
+    pub use_pdata: bool,
+    /// Only prints how many symbols are located in the code section, without disassembling or
+    /// writing any output.
+    pub count_only: bool,
+    /// Lists the symbols which lie in none of the processed sections (PE only).
+    pub list_symbols_not_in_section: bool,
+    /// Compression of the written outputs.
+    pub compression: COMPRESSION,
+    /// Sets the timestamp of the dump to 0 so repeated runs produce identical outputs.
+    pub deterministic: bool,
+    /// Soft limit of the estimated memory usage in MB. Processing is aborted before it is
+    /// exceeded.
+    pub max_memory: Option<u64>,
+    /// Minimum length in bytes of a repeated multi-byte pattern (e.g. `0xDEADBEEF`) within a hole
+    /// to be flagged as fill. 0 disables the detection.
+    pub fill_min_run: usize,
+    /// Assembly syntax of the mnemonics and operands. The flags are derived from the Intel
+    /// decoding regardless.
+    pub syntax: SYNTAX,
+    /// Backend decoding the functions, holes and gaps.
+    pub disassembler: disassembler::DISASSEMBLER,
+    /// Scans the holes for common function prologues and flags them as recovered function
+    /// starts.
+    pub detect_prologues: bool,
+    /// Address space of every address written to the outputs.
+    pub address_mode: ADDRESS_MODE,
+    /// Maps the data sections (bytes, data symbols and strings) next to the code section.
+    pub include_data_sections: bool,
+    /// Adds the contents of every in-line data region to the YAML dump.
+    pub include_data_bytes: bool,
+    /// Maps every instruction to its source line using the DWARF line program (ELF only).
+    pub source_lines: bool,
+    /// Weights of the components of the disassembly difficulty score.
+    pub difficulty_weights: DifficultyWeights,
+    /// Adds the demangled name of every function to the outputs.
+    pub demangle: bool,
+    /// Time budget in milliseconds for disassembling a single function. Functions taking longer
+    /// are skipped.
+    pub function_timeout: Option<u64>,
+    /// Checks every symbol against the bounds of its section before processing and drops the
+    /// ones which do not fit.
+    pub validate_offsets: bool,
+    /// Aborts instead of dropping symbols which do not fit their section.
+    pub strict: bool,
+    /// Splits the YAML dump into N shards by address range.
+    pub shard_by: Option<usize>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            verify: false,
+            padding_bytes: vec![0xCC],
+            repl: false,
+            no_truncate: false,
+            exclude_sections: Vec::new(),
+            hex_addresses: false,
+            hex_bytes: false,
+            symbolic_addresses: false,
+            limit_functions: None,
+            include_imports: false,
+            function: None,
+            format: FORMAT::YAML,
+            explain: false,
+            flag_mnemonics: Vec::new(),
+            trust_disassembly: false,
+            hotpatch_padding: false,
+            normalize_operands: false,
+            detailed_operands: false,
+            functions_from: None,
+            functions_format: FUNCTIONS_FORMAT::GHIDRA,
+            anomaly_threshold: 0.1,
+            output_kind: OUTPUT_KIND::ALL,
+            sort_output: SORT_ORDER::ADDRESS,
+            use_pdata: false,
+            count_only: false,
+            list_symbols_not_in_section: false,
+            compression: COMPRESSION::NONE,
+            deterministic: false,
+            max_memory: None,
+            fill_min_run: 16,
+            syntax: SYNTAX::INTEL,
+            disassembler: disassembler::DISASSEMBLER::CAPSTONE,
+            detect_prologues: false,
+            include_data_sections: false,
+            include_data_bytes: false,
+            source_lines: false,
+            address_mode: ADDRESS_MODE::VA,
+            difficulty_weights: DifficultyWeights::default(),
+            demangle: false,
+            function_timeout: None,
+            validate_offsets: false,
+            strict: false,
+            shard_by: None,
+        }
+    }
+}
+
+/// Parses the five comma separated weights of the difficulty score (inline data, decode failures,
+/// hole entropy, mid-instruction targets, transitions; e.g. "1,1,0.5,1,1").
+pub fn parse_difficulty_weights(list: &str) -> Result<DifficultyWeights, String> {
+    let mut weights = Vec::new();
+
+    for value in list.split(',').map(|v| v.trim()) {
+        match value.parse::<f64>() {
+            Ok(weight) if weight >= 0.0 => weights.push(weight),
+            _ => return Err(format!("Invalid weight: {}", value)),
+        }
+    }
+
+    match weights[..] {
+        [inline_data, decode_failures, hole_entropy, mid_instruction_targets, transitions] => {
+            Ok(DifficultyWeights {
+                inline_data,
+                decode_failures,
+                hole_entropy,
+                mid_instruction_targets,
+                transitions,
+            })
+        }
+        _ => Err(format!("Expected 5 weights, got {}.", weights.len())),
+    }
+}
+
+/// Parses a comma separated list of hex byte values (e.g. "0xCC,90,0x00").
+pub fn parse_byte_list(list: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::new();
+
+    for value in list.split(',').map(|v| v.trim()).filter(|v| !v.is_empty()) {
+        let digits = value.trim_start_matches("0x").trim_start_matches("0X");
+
+        match u8::from_str_radix(digits, 16) {
+            Ok(byte) => bytes.push(byte),
+            Err(_e) => {
+                return Err(format!("Invalid byte value: {}", value));
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn byte_list_with_and_without_prefix() {
+        assert_eq!(
+            parse_byte_list("0xCC, 90,0X00,").unwrap(),
+            vec![0xCC, 0x90, 0x00]
+        );
+        assert!(parse_byte_list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn byte_list_rejects_values_beyond_a_byte() {
+        assert!(parse_byte_list("0xCC,0x100").is_err());
+        assert!(parse_byte_list("nop").is_err());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::config;
+use crate::dumper;
+use crate::groundtruth;
+
+/// Describes how the classification of a region changed against the baseline.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CHANGE {
+    /// Identified by the current run only.
+    GAINED,
+    /// Identified by the baseline only.
+    LOST,
+}
+
+/// Represents contiguous bytes whose classification changed the same way.
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub start: u64,
+    /// Address of the last byte (inclusive).
+    pub end: u64,
+    pub size: u64,
+    pub change: CHANGE,
+    /// Flags the identifying run (current for gained, baseline for lost bytes) set in the region.
+    pub flags: Vec<groundtruth::FLAG>,
+}
+
+/// Compares the identified bytes of two runs on the same binary.
+#[derive(Debug, Default)]
+pub struct CoverageDiff {
+    pub baseline_identified: u64,
+    pub current_identified: u64,
+    pub regions: Vec<Region>,
+}
+
+impl CoverageDiff {
+    /// Bytes identified by the current run minus the ones identified by the baseline.
+    pub fn net_gain(&self) -> i64 {
+        self.current_identified as i64 - self.baseline_identified as i64
+    }
+
+    pub fn bytes(&self, change: CHANGE) -> u64 {
+        self.regions
+            .iter()
+            .filter(|r| r.change == change)
+            .map(|r| r.size)
+            .sum()
+    }
+}
+
+fn is_identified(byte: Option<&groundtruth::Byte>) -> bool {
+    byte.is_some_and(|b| b.is_identified())
+}
+
+/// Finds the bytes which only one of the dumps identifies (has any flag for). Both dumps have to
+/// contain every byte and use the same address space.
+pub fn diff(baseline: &dumper::Dump, current: &dumper::Dump) -> Result<CoverageDiff, &'static str> {
+    if baseline.output_kind != config::OUTPUT_KIND::ALL
+        || current.output_kind != config::OUTPUT_KIND::ALL
+    {
+        return Err("Only dumps written with --output-kind all contain every byte!");
+    }
+
+    if baseline.address_space != current.address_space {
+        return Err("The dumps use different address spaces (see --address-mode)!");
+    }
+
+    // Bytes of either dump in address order, each with its counterpart in the other dump
+    let mut changes: Vec<(u64, CHANGE, &groundtruth::Byte)> = Vec::new();
+
+    for byte in &current.bytes {
+        let before = is_identified(baseline.byte_at(byte.offset));
+        let after = byte.is_identified();
+
+        if after && !before {
+            changes.push((byte.offset, CHANGE::GAINED, byte));
+        }
+    }
+
+    for byte in &baseline.bytes {
+        let before = byte.is_identified();
+        let after = is_identified(current.byte_at(byte.offset));
+
+        if before && !after {
+            changes.push((byte.offset, CHANGE::LOST, byte));
+        }
+    }
+
+    changes.sort_by_key(|(address, _, _)| *address);
+
+    let mut regions: Vec<Region> = Vec::new();
+
+    for (address, change, byte) in changes {
+        match regions.last_mut() {
+            Some(region) if region.change == change && region.end + 1 == address => {
+                region.end = address;
+                region.size += 1;
+
+                for flag in &byte.flags {
+                    if !region.flags.contains(flag) {
+                        region.flags.push(flag.clone());
+                    }
+                }
+            }
+            _ => regions.push(Region {
+                start: address,
+                end: address,
+                size: 1,
+                change,
+                flags: byte.flags.clone(),
+            }),
+        }
+    }
+
+    Ok(CoverageDiff {
+        baseline_identified: baseline.bytes_identified,
+        current_identified: current.bytes_identified,
+        regions,
+    })
+}
@@ -0,0 +1,132 @@
+/// Operators and special members of MSVC names (`??<code>`) which can be decoded.
+const MSVC_SPECIAL_NAMES: &[(&str, &str)] = &[
+    ("2", "operator new"),
+    ("3", "operator delete"),
+    ("4", "operator="),
+    ("8", "operator=="),
+    ("9", "operator!="),
+    ("A", "operator[]"),
+    ("R", "operator()"),
+    ("_U", "operator new[]"),
+    ("_V", "operator delete[]"),
+];
+
+/// Demangles a Rust (legacy and v0), Itanium (GCC, Clang) or MSVC symbol name. Names which are
+/// not mangled (or can not be decoded) are returned unchanged.
+pub fn demangle(name: &str) -> String {
+    demangle_rust(name)
+        .or_else(|| demangle_itanium(name))
+        .or_else(|| demangle_msvc(name))
+        .unwrap_or_else(|| name.to_string())
+}
+
+fn demangle_rust(name: &str) -> Option<String> {
+    // The alternate format omits the hash suffix of legacy names
+    rustc_demangle::try_demangle(name)
+        .ok()
+        .map(|demangled| format!("{:#}", demangled))
+}
+
+fn demangle_itanium(name: &str) -> Option<String> {
+    if !name.starts_with("_Z") {
+        return None;
+    }
+
+    cpp_demangle::Symbol::new(name)
+        .ok()?
+        .demangle(&cpp_demangle::DemangleOptions::default())
+        .ok()
+}
+
+/// Decodes the qualified name of an MSVC decorated name (e.g. `?bar@Foo@@QEAAHXZ` is
+/// `Foo::bar`). The signature is not decoded, names with templates or back references are left
+/// alone.
+fn demangle_msvc(name: &str) -> Option<String> {
+    let mut rest = name.strip_prefix('?')?;
+
+    // Constructors, destructors and operators are named after a code
+    let mut special = None;
+
+    if let Some(code) = rest.strip_prefix('?') {
+        special = match code.as_bytes().first()? {
+            b'0' | b'1' => Some(&code[..1]),
+            _ => MSVC_SPECIAL_NAMES
+                .iter()
+                .find(|(prefix, _)| code.starts_with(prefix))
+                .map(|(prefix, _)| &code[..prefix.len()]),
+        };
+        rest = &code[special?.len()..];
+    }
+
+    // Innermost name first, the scopes end with an empty fragment ("@@"). Global operators have
+    // no scope at all.
+    let mut fragments: Vec<&str> = match rest.strip_prefix('@') {
+        Some(_) if special.is_some() => Vec::new(),
+        _ => rest[..rest.find("@@")?].split('@').collect(),
+    };
+
+    let valid = |f: &&str| {
+        f.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == '$')
+            && f.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+    };
+
+    if !fragments.iter().all(valid) {
+        return None;
+    }
+
+    match special {
+        Some("0") => {
+            let class = *fragments.first()?;
+            fragments.insert(0, class);
+            Some(qualify(&fragments, None))
+        }
+        Some("1") => {
+            let destructor = format!("~{}", fragments.first()?);
+            Some(qualify(&fragments, Some(&destructor)))
+        }
+        Some(code) => {
+            let operator = MSVC_SPECIAL_NAMES.iter().find(|(c, _)| *c == code)?.1;
+            Some(qualify(&fragments, Some(operator)))
+        }
+        None if fragments.is_empty() => None,
+        None => Some(qualify(&fragments, None)),
+    }
+}
+
+/// Joins the fragments (innermost first) to a qualified name, optionally appending a member.
+fn qualify(fragments: &[&str], member: Option<&str>) -> String {
+    let mut parts: Vec<&str> = fragments.iter().rev().cloned().collect();
+
+    if let Some(member) = member {
+        parts.push(member);
+    }
+
+    parts.join("::")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn demangles_itanium_rust_and_msvc_names() {
+        let names = [
+            ("_ZN3foo3barEi", "foo::bar(int)"),
+            ("_ZN4core3fmt5write17h0123456789abcdefE", "core::fmt::write"),
+            ("_RNvCs15kBYyAo9fc_7mycrate7example", "mycrate::example"),
+            ("?bar@Foo@@QEAAHH@Z", "Foo::bar"),
+            ("??0Foo@@QEAA@XZ", "Foo::Foo"),
+        ];
+
+        for (name, demangled) in names {
+            assert_eq!(demangle(name), demangled, "{}", name);
+        }
+    }
+
+    #[test]
+    fn keeps_names_which_are_not_mangled() {
+        assert_eq!(demangle("main"), "main");
+        assert_eq!(demangle("_Znot_mangled"), "_Znot_mangled");
+    }
+}
@@ -1,14 +1,23 @@
 use std::mem;
+use std::time::Instant;
 
+use crate::config;
 use crate::groundtruth;
 use capstone::prelude::*;
 use fancy_regex::Regex;
 use lazy_static::lazy_static;
+use tracing::debug;
 
-#[allow(dead_code)]
+/// Error of a disassembly which did not finish before its deadline.
+pub const TIMED_OUT: &str = "Disassembly timed out!";
+
+/// Amount of instructions decoded at once, the deadline is checked before every chunk.
+const CHUNK_SIZE: usize = 256;
+
+/// Describes the disassembler backends.
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DISASSEMBLER {
     CAPSTONE,
-    ZYDIS,
 }
 
 #[allow(dead_code)]
@@ -23,42 +32,427 @@ mod cs_group_type {
     pub const CS_GRP_IRET: Type = 5;
 }
 
+/// Instructions whose first (memory) operand is only read. The one operand forms of the
+/// multiplications and divisions name their source, multi-operand `imul` is handled separately.
+const NON_WRITING_MNEMONICS: [&str; 11] = [
+    "cmp", "test", "push", "call", "bt", "div", "idiv", "mul", "imul", "ucomiss", "ucomisd",
+];
+
+/// Legacy prefixes which Capstone may decode as a standalone one-byte instruction: lock (0xF0),
+/// repne/rep (0xF2, 0xF3), segment overrides (0x2E, 0x36, 0x3E, 0x26, 0x64, 0x65), operand size
+/// (0x66) and address size (0x67).
+const LEGACY_PREFIXES: [u8; 11] = [
+    0xF0, 0xF2, 0xF3, 0x2E, 0x36, 0x3E, 0x26, 0x64, 0x65, 0x66, 0x67,
+];
+
+/// Merges orphaned prefixes into the instruction directly following them.
+///
+/// A one-byte "instruction" consisting of a legacy prefix is merged if the next instruction starts
+/// right after it, so it does not produce a spurious instruction start.
+fn merge_orphaned_prefixes(
+    instructions: Vec<groundtruth::Instruction>,
+) -> Vec<groundtruth::Instruction> {
+    let mut merged: Vec<groundtruth::Instruction> = Vec::with_capacity(instructions.len());
+    let mut pending: Option<groundtruth::Instruction> = None;
+
+    for mut instruction in instructions {
+        if let Some(prefix) = pending.take() {
+            if prefix.offset + prefix.length == instruction.offset {
+                debug!(
+                    offset = prefix.offset,
+                    prefix = %prefix.mnemonic,
+                    "Merged orphaned prefix into the following instruction."
+                );
+
+                let mut bytes = prefix.bytes;
+                bytes.append(&mut instruction.bytes);
+
+                instruction.mnemonic = format!("{} {}", prefix.mnemonic, instruction.mnemonic);
+                instruction.prefixes.insert(0, bytes[0]);
+                instruction.bytes = bytes;
+                instruction.offset = prefix.offset;
+                instruction.length += prefix.length;
+            } else {
+                merged.push(prefix);
+            }
+        }
+
+        if instruction.length == 1 && LEGACY_PREFIXES.contains(&instruction.bytes[0]) {
+            pending = Some(instruction);
+        } else {
+            merged.push(instruction);
+        }
+    }
+
+    // A trailing prefix has nothing to be merged into
+    merged.extend(pending);
+
+    merged
+}
+
+/// Derives the access flags for an explicit memory operand.
+///
+/// Capstone does not report operand access, therefore the access is derived from the operand
+/// position (Intel syntax places the destination first) and the amount of explicit operands.
+/// Implicit accesses (e.g. the stack of a push) are not covered.
+fn memory_access_flags(mnemonic: &str, index: usize, count: usize) -> Vec<groundtruth::FLAG> {
+    // Address computations and hint nops never access memory
+    if mnemonic == "lea" || mnemonic == "nop" || mnemonic.starts_with("prefetch") {
+        return Vec::new();
+    }
+
+    match operand_access(mnemonic, index, count) {
+        groundtruth::ACCESS::NONE => Vec::new(),
+        groundtruth::ACCESS::READ => vec![groundtruth::FLAG::MEMORY_READ],
+        groundtruth::ACCESS::WRITE => vec![groundtruth::FLAG::MEMORY_WRITE],
+        groundtruth::ACCESS::READ_WRITE => vec![
+            groundtruth::FLAG::MEMORY_READ,
+            groundtruth::FLAG::MEMORY_WRITE,
+        ],
+    }
+}
+
+/// Derives the access of an explicit operand from its position among `count` operands, like
+/// `memory_access_flags`.
+fn operand_access(mnemonic: &str, index: usize, count: usize) -> groundtruth::ACCESS {
+    // Exchanges write both operands (xchg, xadd)
+    if mnemonic == "xchg" || mnemonic == "xadd" {
+        return groundtruth::ACCESS::READ_WRITE;
+    }
+
+    // Multi-operand imul writes its destination, only the two operand form reads it as well
+    if mnemonic == "imul" && count > 1 && index == 0 {
+        return if count == 2 {
+            groundtruth::ACCESS::READ_WRITE
+        } else {
+            groundtruth::ACCESS::WRITE
+        };
+    }
+
+    // Source operands and destinations which are only compared, pushed or jumped to
+    if index > 0
+        || NON_WRITING_MNEMONICS.contains(&mnemonic)
+        || mnemonic.starts_with('j')
+        || (mnemonic.starts_with('f')
+            && !mnemonic.starts_with("fst")
+            && !mnemonic.starts_with("fist"))
+    {
+        return groundtruth::ACCESS::READ;
+    }
+
+    // Pure stores do not read their destination
+    if mnemonic.starts_with("mov")
+        || mnemonic.starts_with("set")
+        || mnemonic.starts_with("stos")
+        || mnemonic.starts_with("fst")
+        || mnemonic.starts_with("fist")
+        || mnemonic == "pop"
+        || mnemonic == "lea"
+    {
+        return groundtruth::ACCESS::WRITE;
+    }
+
+    // Everything else is a read-modify-write (e.g. add [rax], rbx)
+    groundtruth::ACCESS::READ_WRITE
+}
+
 pub fn disassemble(
     buffer: Vec<u8>,
-    architecture: &groundtruth::ARCHITECTURE,
+    architecture: &groundtruth::ArchInfo,
     disassembler: DISASSEMBLER,
 ) -> Result<Vec<groundtruth::Instruction>, &'static str> {
-    match disassembler {
-        DISASSEMBLER::CAPSTONE => {
-            return disassemble_capstone(buffer, architecture);
-        }
-        DISASSEMBLER::ZYDIS => {
-            return disassemble_zydis(buffer, architecture);
-        }
-    }
+    disassemble_until(buffer, architecture, disassembler, None)
 }
 
-pub fn disassemble_capstone(
+/// Disassembles like `disassemble`, but gives up with `TIMED_OUT` once the deadline has passed.
+pub fn disassemble_until(
     buffer: Vec<u8>,
-    architecture: &groundtruth::ARCHITECTURE,
+    architecture: &groundtruth::ArchInfo,
+    disassembler: DISASSEMBLER,
+    deadline: Option<Instant>,
 ) -> Result<Vec<groundtruth::Instruction>, &'static str> {
-    let mut instructions = Vec::new();
+    match disassembler {
+        DISASSEMBLER::CAPSTONE => disassemble_capstone(buffer, architecture, deadline),
+    }
+}
+
+/// Creates a Capstone instance for the architecture which prints the given syntax (x86 only).
+fn build_capstone(
+    architecture: &groundtruth::ArchInfo,
+    syntax: config::SYNTAX,
+) -> Capstone<'static> {
+    if let groundtruth::ARCHITECTURE::MIPS | groundtruth::ARCHITECTURE::MIPS64 = architecture.family
+    {
+        return build_capstone_mips(architecture);
+    }
 
-    let mode = match architecture {
-        groundtruth::ARCHITECTURE::X86 => arch::x86::ArchMode::Mode32,
-        groundtruth::ARCHITECTURE::X64 => arch::x86::ArchMode::Mode64,
+    if architecture.family.is_arm() {
+        return build_capstone_arm(architecture);
+    }
+
+    let mode = match (architecture.family, architecture.bits) {
+        (groundtruth::ARCHITECTURE::X86, _) => arch::x86::ArchMode::Mode32,
+        (groundtruth::ARCHITECTURE::X64, _) => arch::x86::ArchMode::Mode64,
+        (_, 32) => arch::x86::ArchMode::Mode32,
         _ => arch::x86::ArchMode::Mode64,
     };
 
-    let mut cs = Capstone::new()
+    let syntax = match syntax {
+        config::SYNTAX::INTEL => arch::x86::ArchSyntax::Intel,
+        config::SYNTAX::ATT => arch::x86::ArchSyntax::Att,
+    };
+
+    Capstone::new()
         .x86()
         .mode(mode)
-        .syntax(arch::x86::ArchSyntax::Intel)
+        .syntax(syntax)
         .detail(true)
         .build()
-        .unwrap();
+        .unwrap()
+}
+
+fn build_capstone_mips(architecture: &groundtruth::ArchInfo) -> Capstone<'static> {
+    let mode = match architecture.family {
+        groundtruth::ARCHITECTURE::MIPS64 => arch::mips::ArchMode::Mode64,
+        _ => arch::mips::ArchMode::Mode32,
+    };
+
+    let endian = match architecture.endianness {
+        groundtruth::ENDIANNESS::BIG => capstone::Endian::Big,
+        groundtruth::ENDIANNESS::LITTLE => capstone::Endian::Little,
+    };
+
+    Capstone::new()
+        .mips()
+        .mode(mode)
+        .endian(endian)
+        .detail(true)
+        .build()
+        .unwrap()
+}
+
+fn build_capstone_arm(architecture: &groundtruth::ArchInfo) -> Capstone<'static> {
+    let mode = match architecture.family {
+        groundtruth::ARCHITECTURE::THUMB => arch::arm::ArchMode::Thumb,
+        _ => arch::arm::ArchMode::Arm,
+    };
+
+    Capstone::new()
+        .arm()
+        .mode(mode)
+        .detail(true)
+        .build()
+        .unwrap()
+}
+
+/// Flags MIPS calls and returns, Capstone only reports them as jumps.
+fn mips_control_flow_flags(instruction: &mut groundtruth::Instruction) {
+    let flag = match instruction.mnemonic.as_str() {
+        "jal" | "jalr" | "jalx" | "bal" | "bgezal" | "bltzal" => {
+            groundtruth::FLAG::INSTRUCTION_CALL
+        }
+        "jr" if instruction.operand == "$ra" => groundtruth::FLAG::INSTRUCTION_RET,
+        _ => return,
+    };
+
+    instruction
+        .flags
+        .retain(|f| f != &groundtruth::FLAG::INSTRUCTION_JUMP);
+    instruction.set_flags(vec![flag]);
+}
+
+/// Flags ARM and Thumb calls (`bl`, `blx`) and returns (`bx lr` and loads of the pc, e.g.
+/// `pop {r4, pc}`), Capstone only reports them as jumps.
+fn arm_control_flow_flags(instruction: &mut groundtruth::Instruction) {
+    // Wide Thumb encodings carry a `.w` suffix
+    let mnemonic = instruction.mnemonic.split('.').next().unwrap_or_default();
+    let loads_pc = instruction.operand.contains("pc}");
+
+    let flag = match mnemonic {
+        "bl" | "blx" => groundtruth::FLAG::INSTRUCTION_CALL,
+        "bx" if instruction.operand == "lr" => groundtruth::FLAG::INSTRUCTION_RET,
+        "pop" | "ldm" if loads_pc => groundtruth::FLAG::INSTRUCTION_RET,
+        _ => return,
+    };
+
+    instruction
+        .flags
+        .retain(|f| f != &groundtruth::FLAG::INSTRUCTION_JUMP);
+    instruction.set_flags(vec![flag]);
+}
+
+/// Derives the access flags of an ARM load or store (including the `v` prefixed floating point
+/// ones), all other instructions with a memory operand (e.g. `pld`) do not access data.
+fn arm_memory_access_flags(mnemonic: &str) -> Vec<groundtruth::FLAG> {
+    let mnemonic = mnemonic.trim_start_matches('v');
+
+    if mnemonic.starts_with("ld") {
+        vec![groundtruth::FLAG::MEMORY_READ]
+    } else if mnemonic.starts_with("st") {
+        vec![groundtruth::FLAG::MEMORY_WRITE]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Size of the literal an ARM load reads (`ldr`: a word, `ldrd`: two words, `vldr` of a double
+/// register: eight bytes).
+fn literal_size(mnemonic: &str, operand: &str) -> u64 {
+    let mnemonic = mnemonic.split('.').next().unwrap_or_default();
+
+    if mnemonic.starts_with("ldrd") || (mnemonic == "vldr" && operand.starts_with('d')) {
+        8
+    } else if mnemonic.starts_with("ldrh") || mnemonic.starts_with("ldrsh") {
+        2
+    } else if mnemonic.starts_with("ldrb") || mnemonic.starts_with("ldrsb") {
+        1
+    } else {
+        4
+    }
+}
+
+/// Locates the literals ARM or Thumb code loads PC-relative (`ldr rN, [pc, #imm]`), the ARM
+/// counterpart of jump tables.
+///
+/// The buffer is decoded linearly from `address`, which the PC of Thumb loads is aligned
+/// relative to. The bytes of a literal found before are skipped, which is enough for pools
+/// placed behind the code referencing them. Returns the offsets within the buffer and the sizes
+/// of the literals, literals outside of the buffer are dropped.
+pub fn literal_pool_entries(
+    buffer: &[u8],
+    address: u64,
+    architecture: &groundtruth::ArchInfo,
+) -> Vec<(u64, u64)> {
+    let mut literals: Vec<(u64, u64)> = Vec::new();
+
+    // Guard: Only ARM and Thumb code has literal pools
+    if !architecture.family.is_arm() {
+        return literals;
+    }
+
+    let mut cs = build_capstone(architecture, config::SYNTAX::INTEL);
+    let pc = RegId(arch::arm::ArmReg::ARM_REG_PC as RegIdInt);
+
+    let decoded = match cs.disasm_all(buffer, address) {
+        Ok(decoded) => decoded,
+        Err(_e) => return literals,
+    };
+
+    for i in decoded.iter() {
+        let offset = i.address() - address;
+
+        // Guard: The bytes of a literal are no instruction
+        if literals
+            .iter()
+            .any(|(start, size)| offset >= *start && offset < start + size)
+        {
+            continue;
+        }
+
+        let detail: InsnDetail = match cs.insn_detail(&i) {
+            Ok(detail) => detail,
+            Err(_e) => continue,
+        };
+
+        let mnemonic = i.mnemonic().unwrap_or_default();
+
+        // Guard: Only loads read a literal
+        if !mnemonic.trim_start_matches('v').starts_with("ld") {
+            continue;
+        }
+
+        for operand in detail.arch_detail().operands() {
+            let memory = match operand {
+                arch::ArchOperand::ArmOperand(arch::arm::ArmOperand {
+                    op_type: arch::arm::ArmOperandType::Mem(memory),
+                    ..
+                }) if memory.base() == pc => memory,
+                _ => continue,
+            };
+
+            // The PC reads 8 bytes (ARM) or 4 bytes (Thumb, word aligned) ahead
+            let base = match architecture.family {
+                groundtruth::ARCHITECTURE::THUMB => (i.address() + 4) & !3,
+                _ => i.address() + 8,
+            };
+
+            let target = base.wrapping_add(memory.disp() as i64 as u64);
+            let size = literal_size(mnemonic, i.op_str().unwrap_or_default());
+
+            if target >= address && target + size <= address + buffer.len() as u64 {
+                literals.push((target - address, size));
+            }
+        }
+    }
+
+    literals.sort_unstable();
+    literals.dedup();
+    literals
+}
+
+/// Derives the access flags of a MIPS load or store, all other instructions with a memory
+/// operand (e.g. `cache`, `pref`) do not access data.
+fn mips_memory_access_flags(mnemonic: &str) -> Vec<groundtruth::FLAG> {
+    match mnemonic.chars().next() {
+        Some('l') => vec![groundtruth::FLAG::MEMORY_READ],
+        Some('s') => vec![groundtruth::FLAG::MEMORY_WRITE],
+        _ => Vec::new(),
+    }
+}
+
+pub fn disassemble_capstone(
+    buffer: Vec<u8>,
+    architecture: &groundtruth::ArchInfo,
+    deadline: Option<Instant>,
+) -> Result<Vec<groundtruth::Instruction>, &'static str> {
+    let mut instructions = Vec::new();
+
+    // The flags rely on the Intel operand order and mnemonics
+    let mut cs = build_capstone(architecture, config::SYNTAX::INTEL);
+
+    // Decoding stops at the first invalid instruction, a single call may take arbitrarily long on
+    // huge buffers. Chunks of instructions bound the time between two checks of the deadline.
+    let mut position = 0;
+
+    while position < buffer.len() {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Err(TIMED_OUT);
+        }
+
+        let known = instructions.len();
+        decode_chunk(
+            &mut cs,
+            &buffer[position..],
+            position,
+            architecture,
+            &mut instructions,
+        )?;
+        let complete = instructions.len() - known == CHUNK_SIZE;
+
+        position += instructions[known..]
+            .iter()
+            .map(|i| i.length as usize)
+            .sum::<usize>();
+
+        // Guard: Stopped at an invalid instruction
+        if !complete {
+            break;
+        }
+    }
+
+    Ok(merge_orphaned_prefixes(instructions))
+}
 
-    let disassembled_instructions = match cs.disasm_all(&buffer, 0x0) {
+/// Decodes up to `CHUNK_SIZE` instructions of the buffer, which starts at the given offset, and
+/// appends them to the instructions decoded so far.
+fn decode_chunk(
+    cs: &mut Capstone,
+    buffer: &[u8],
+    start: usize,
+    architecture: &groundtruth::ArchInfo,
+    instructions: &mut Vec<groundtruth::Instruction>,
+) -> Result<(), &'static str> {
+    let disassembled_instructions = match cs.disasm_count(buffer, start as u64, CHUNK_SIZE) {
         Ok(instructions) => instructions,
         Err(_e) => {
             return Err("Could not disassemble given bytes!");
@@ -72,10 +466,17 @@ pub fn disassemble_capstone(
         let mut instruction = groundtruth::Instruction {
             mnemonic: i.mnemonic().unwrap().to_string(),
             operand: i.op_str().unwrap().to_string(),
+            raw_operand: None,
             bytes: i.bytes().to_vec(),
+            prefixes: Vec::new(),
+            rex: None,
             offset: i.address(),
+            address: 0,
             length: i.bytes().len() as u64,
             flags: Vec::new(),
+            source: None,
+            target: None,
+            operands: Vec::new(),
         };
 
         // Get details for groups
@@ -104,8 +505,72 @@ pub fn disassemble_capstone(
             }
         }
 
+        let mips = matches!(
+            architecture.family,
+            groundtruth::ARCHITECTURE::MIPS | groundtruth::ARCHITECTURE::MIPS64
+        );
+
+        if mips {
+            mips_control_flow_flags(&mut instruction);
+        }
+
+        if architecture.family.is_arm() {
+            arm_control_flow_flags(&mut instruction);
+        }
+
+        // Record the encoding prefixes, capstone reports 0 for absent ones
+        if let arch::ArchDetail::X86Detail(x86) = detail.arch_detail() {
+            instruction.prefixes = x86.prefix().iter().copied().filter(|p| *p != 0).collect();
+            instruction.rex = Some(x86.rex()).filter(|rex| *rex != 0);
+        }
+
+        // Set memory access flags for explicit memory operands
+        for (index, operand) in detail.arch_detail().operands().iter().enumerate() {
+            match operand {
+                arch::ArchOperand::X86Operand(operand) => match operand.op_type {
+                    arch::x86::X86OperandType::Mem(_) => {
+                        instruction.set_flags(memory_access_flags(
+                            i.mnemonic().unwrap(),
+                            index,
+                            detail.arch_detail().operands().len(),
+                        ));
+                    }
+                    // Direct jumps and calls, the target is relative to the start of the buffer
+                    arch::x86::X86OperandType::Imm(value) if instruction.is_branch() => {
+                        instruction.target = Some(value as u64);
+                    }
+                    _ => {}
+                },
+                arch::ArchOperand::MipsOperand(operand) => match operand {
+                    arch::mips::MipsOperand::Mem(_) => {
+                        instruction.set_flags(mips_memory_access_flags(i.mnemonic().unwrap()));
+                    }
+                    arch::mips::MipsOperand::Imm(value) if instruction.is_branch() => {
+                        instruction.target = Some(*value as u64);
+                    }
+                    _ => {}
+                },
+                arch::ArchOperand::ArmOperand(operand) => match operand.op_type {
+                    arch::arm::ArmOperandType::Mem(_) => {
+                        instruction.set_flags(arm_memory_access_flags(i.mnemonic().unwrap()));
+                    }
+                    arch::arm::ArmOperandType::Imm(value) if instruction.is_branch() => {
+                        instruction.target = Some(value as u32 as u64);
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+
+        // A nop in the delay slot of a MIPS branch is executed, it is no alignment
+        let delay_slot = mips
+            && instructions
+                .last()
+                .is_some_and(|p: &groundtruth::Instruction| p.is_branch() || !p.falls_through());
+
         // Check if instruction is a nop (single/multi byte) and set align flag if true
-        if i.mnemonic().unwrap() == "nop" {
+        if i.mnemonic().unwrap() == "nop" && !delay_slot {
             instruction.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
         }
 
@@ -118,22 +583,428 @@ pub fn disassemble_capstone(
         // Note: these are not real NOPs since they introduce data dependency
         // TODO: Add mov
 
-        if i.mnemonic().unwrap() == "lea" {
-            if RE.is_match(i.op_str().unwrap()).unwrap() {
-                instruction.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
-            }
+        if i.mnemonic().unwrap() == "lea" && RE.is_match(i.op_str().unwrap()).unwrap() {
+            instruction.set_flags(vec![groundtruth::FLAG::INSTRUCTION_ALIGNMENT]);
         }
 
         instructions.push(instruction);
     }
 
-    Ok(instructions)
+    Ok(())
 }
 
-pub fn disassemble_zydis(
-    _buffer: Vec<u8>,
-    _architecture: &groundtruth::ARCHITECTURE,
-) -> Result<Vec<groundtruth::Instruction>, &'static str> {
-    let instructions = Vec::new();
-    Ok(instructions)
+/// Replaces mnemonic and operand of the instructions with their text in the given syntax.
+///
+/// Instructions are always decoded with Intel syntax, only their text is rendered again. An
+/// instruction which cannot be decoded on its own keeps its Intel text.
+pub fn render_syntax(
+    instructions: &mut [groundtruth::Instruction],
+    architecture: &groundtruth::ArchInfo,
+    syntax: config::SYNTAX,
+) {
+    // Guard: Already decoded with Intel syntax
+    if syntax == config::SYNTAX::INTEL {
+        return;
+    }
+
+    let mut cs = build_capstone(architecture, syntax);
+
+    for instruction in instructions.iter_mut() {
+        // Same address as the Intel decoding, so branch targets are printed the same way
+        let rendered = match cs.disasm_count(&instruction.bytes, instruction.offset, 1) {
+            Ok(rendered) => rendered,
+            Err(_e) => continue,
+        };
+
+        if let Some(i) = rendered.iter().next() {
+            instruction.mnemonic = i.mnemonic().unwrap_or_default().to_string();
+            instruction.operand = i.op_str().unwrap_or_default().to_string();
+        }
+    }
+}
+
+/// Adds the explicit operands to every instruction (see `--detailed-operands`), x86 only.
+///
+/// Every instruction is decoded again on its own with Intel syntax, so the operands follow the
+/// Intel order whatever syntax the text is rendered in. The access is derived from the position
+/// of the operand, memory operands of address computations and hint nops are not accessed.
+pub fn describe_operands(
+    instructions: &mut [groundtruth::Instruction],
+    architecture: &groundtruth::ArchInfo,
+) {
+    // Guard: Capstone reports no operand details for MIPS which are used here
+    if !architecture.family.is_x86() {
+        return;
+    }
+
+    let mut cs = build_capstone(architecture, config::SYNTAX::INTEL);
+
+    for instruction in instructions.iter_mut() {
+        let decoded = match cs.disasm_count(&instruction.bytes, instruction.offset, 1) {
+            Ok(decoded) => decoded,
+            Err(_e) => continue,
+        };
+
+        let i = match decoded.iter().next() {
+            Some(i) => i,
+            None => continue,
+        };
+
+        let detail: InsnDetail = match cs.insn_detail(&i) {
+            Ok(detail) => detail,
+            Err(_e) => continue,
+        };
+
+        let mnemonic = i.mnemonic().unwrap_or_default();
+        let register = |id: RegId| {
+            Some(id)
+                .filter(|id| id.0 != 0)
+                .and_then(|id| cs.reg_name(id))
+        };
+
+        let operands = detail.arch_detail().operands();
+        let count = operands.len();
+
+        instruction.operands = operands
+            .iter()
+            .enumerate()
+            .filter_map(|(index, operand)| {
+                let operand = match operand {
+                    arch::ArchOperand::X86Operand(operand) => operand,
+                    _ => return None,
+                };
+
+                let access = operand_access(mnemonic, index, count);
+                let (kind, access, register, immediate, memory) = match &operand.op_type {
+                    arch::x86::X86OperandType::Reg(id) => (
+                        groundtruth::OPERAND_KIND::REGISTER,
+                        access,
+                        register(*id),
+                        None,
+                        None,
+                    ),
+                    arch::x86::X86OperandType::Imm(value) => (
+                        groundtruth::OPERAND_KIND::IMMEDIATE,
+                        groundtruth::ACCESS::READ,
+                        None,
+                        Some(*value),
+                        None,
+                    ),
+                    arch::x86::X86OperandType::Mem(mem) => (
+                        groundtruth::OPERAND_KIND::MEMORY,
+                        if memory_access_flags(mnemonic, index, count).is_empty() {
+                            groundtruth::ACCESS::NONE
+                        } else {
+                            access
+                        },
+                        None,
+                        None,
+                        Some(groundtruth::MemoryOperand {
+                            segment: register(RegId(mem.segment() as RegIdInt)),
+                            base: register(mem.base()),
+                            index: register(mem.index()),
+                            scale: mem.scale(),
+                            displacement: mem.disp(),
+                        }),
+                    ),
+                    _ => return None,
+                };
+
+                Some(groundtruth::Operand {
+                    kind,
+                    size: operand.size,
+                    access,
+                    register,
+                    immediate,
+                    memory,
+                })
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// Instruction at `offset` holding `bytes`.
+    fn instruction(mnemonic: &str, offset: u64, bytes: &[u8]) -> groundtruth::Instruction {
+        groundtruth::Instruction {
+            offset,
+            bytes: bytes.to_vec(),
+            ..testing::instruction(mnemonic, offset, bytes.len() as u64)
+        }
+    }
+
+    fn disassemble_x64(buffer: &[u8]) -> Vec<groundtruth::Instruction> {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+
+        disassemble_capstone(buffer.to_vec(), &architecture, None).unwrap()
+    }
+
+    #[test]
+    fn prefixes_and_rex_byte_of_x64_instructions() {
+        let instructions = disassemble_x64(&[
+            0xF0, 0x48, 0x01, 0x18, // lock add qword ptr [rax], rbx
+            0x66, 0x90, // xchg ax, ax
+            0x48, 0x89, 0xE5, // mov rbp, rsp
+            0xC3, // ret
+        ]);
+
+        let encoding: Vec<(Vec<u8>, Option<u8>)> = instructions
+            .iter()
+            .map(|i| (i.prefixes.clone(), i.rex))
+            .collect();
+        assert_eq!(
+            encoding,
+            vec![
+                (vec![0xF0], Some(0x48)),
+                (vec![0x66], None),
+                (Vec::new(), Some(0x48)),
+                (Vec::new(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn render_syntax_rewrites_the_text_in_att_syntax() {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+        let mut instructions = disassemble_x64(&[
+            0x48, 0x8B, 0x45, 0xF8, // mov rax, qword ptr [rbp - 8]
+            0xC3, // ret
+        ]);
+        let flags: Vec<Vec<groundtruth::FLAG>> =
+            instructions.iter().map(|i| i.flags.clone()).collect();
+
+        render_syntax(&mut instructions, &architecture, config::SYNTAX::ATT);
+
+        assert_eq!(instructions[0].mnemonic, "movq");
+        assert_eq!(instructions[0].operand, "-8(%rbp), %rax");
+        assert_eq!(instructions[1].mnemonic, "retq");
+
+        // The flags are derived from the Intel decoding
+        let rendered: Vec<Vec<groundtruth::FLAG>> =
+            instructions.iter().map(|i| i.flags.clone()).collect();
+        assert_eq!(rendered, flags);
+    }
+
+    #[test]
+    fn render_syntax_keeps_the_intel_text() {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+        let mut instructions = disassemble_x64(&[0x48, 0x8B, 0x45, 0xF8]);
+
+        render_syntax(&mut instructions, &architecture, config::SYNTAX::INTEL);
+
+        assert_eq!(instructions[0].mnemonic, "mov");
+        assert_eq!(instructions[0].operand, "rax, qword ptr [rbp - 8]");
+    }
+
+    /// Memory access flags of every instruction of the buffer.
+    fn memory_flags(buffer: &[u8]) -> Vec<Vec<groundtruth::FLAG>> {
+        disassemble_x64(buffer)
+            .into_iter()
+            .map(|i| {
+                i.flags
+                    .into_iter()
+                    .filter(|f| {
+                        matches!(
+                            f,
+                            groundtruth::FLAG::MEMORY_READ | groundtruth::FLAG::MEMORY_WRITE
+                        )
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mov_writes_or_reads_memory_depending_on_the_operand_order() {
+        let flags = memory_flags(&[
+            0x48, 0x89, 0x18, // mov qword ptr [rax], rbx
+            0x48, 0x8B, 0x18, // mov rbx, qword ptr [rax]
+        ]);
+
+        assert_eq!(
+            flags,
+            vec![
+                vec![groundtruth::FLAG::MEMORY_WRITE],
+                vec![groundtruth::FLAG::MEMORY_READ],
+            ]
+        );
+    }
+
+    #[test]
+    fn memory_access_of_xchg_cmp_and_imul() {
+        let flags = memory_flags(&[
+            0x48, 0x87, 0x18, // xchg qword ptr [rax], rbx
+            0x48, 0x39, 0x18, // cmp qword ptr [rax], rbx
+            0x48, 0x6B, 0x03, 0x05, // imul rax, qword ptr [rbx], 5
+            0x6B, 0xC1, 0x05, // imul eax, ecx, 5
+        ]);
+
+        assert_eq!(
+            flags,
+            vec![
+                vec![
+                    groundtruth::FLAG::MEMORY_READ,
+                    groundtruth::FLAG::MEMORY_WRITE
+                ],
+                vec![groundtruth::FLAG::MEMORY_READ],
+                vec![groundtruth::FLAG::MEMORY_READ],
+                Vec::new(),
+            ]
+        );
+    }
+
+    #[test]
+    fn describe_operands_of_a_scaled_index_load_and_an_immediate_add() {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+        let mut instructions = disassemble_x64(&[
+            0x48, 0x8B, 0x44, 0xCB, 0x10, // mov rax, qword ptr [rbx + rcx*8 + 0x10]
+            0x83, 0xC0, 0x05, // add eax, 5
+        ]);
+
+        describe_operands(&mut instructions, &architecture);
+
+        let register = |name: &str, size: u8, access: groundtruth::ACCESS| groundtruth::Operand {
+            kind: groundtruth::OPERAND_KIND::REGISTER,
+            size,
+            access,
+            register: Some(name.to_string()),
+            immediate: None,
+            memory: None,
+        };
+
+        assert_eq!(
+            instructions[0].operands,
+            vec![
+                register("rax", 8, groundtruth::ACCESS::WRITE),
+                groundtruth::Operand {
+                    kind: groundtruth::OPERAND_KIND::MEMORY,
+                    size: 8,
+                    access: groundtruth::ACCESS::READ,
+                    register: None,
+                    immediate: None,
+                    memory: Some(groundtruth::MemoryOperand {
+                        segment: None,
+                        base: Some("rbx".to_string()),
+                        index: Some("rcx".to_string()),
+                        scale: 8,
+                        displacement: 0x10,
+                    }),
+                },
+            ]
+        );
+        assert_eq!(
+            instructions[1].operands,
+            vec![
+                register("eax", 4, groundtruth::ACCESS::READ_WRITE),
+                groundtruth::Operand {
+                    kind: groundtruth::OPERAND_KIND::IMMEDIATE,
+                    size: 4,
+                    access: groundtruth::ACCESS::READ,
+                    register: None,
+                    immediate: Some(5),
+                    memory: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn literal_pool_entries_of_thumb_loads_use_the_aligned_pc() {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::THUMB);
+        let buffer = [
+            0x00, 0xBF, // nop
+            0x01, 0x48, // ldr r0, [pc, #4]
+            0x70, 0x47, // bx lr
+            0x00, 0xBF, // nop
+            0x78, 0x56, 0x34, 0x12, // literal
+        ];
+
+        // The ldr at 0x1002 reads relative to 0x1004, not 0x1006
+        assert_eq!(
+            literal_pool_entries(&buffer, 0x1000, &architecture),
+            vec![(8, 4)]
+        );
+    }
+
+    #[test]
+    fn literal_pool_entries_of_arm_loads_in_both_directions() {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::ARM);
+        let buffer = [
+            0x78, 0x56, 0x34, 0x12, // literal
+            0x0C, 0x00, 0x1F, 0xE5, // ldr r0, [pc, #-0xc]
+            0x00, 0x10, 0x9F, 0xE5, // ldr r1, [pc]
+            0x1E, 0xFF, 0x2F, 0xE1, // bx lr
+            0xEF, 0xBE, 0xAD, 0xDE, // literal
+        ];
+
+        assert_eq!(
+            literal_pool_entries(&buffer, 0x1000, &architecture),
+            vec![(0, 4), (16, 4)]
+        );
+    }
+
+    #[test]
+    fn arm_calls_and_returns() {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::THUMB);
+        let instructions = disassemble_capstone(
+            vec![
+                0x10, 0xB5, // push {r4, lr}
+                0x00, 0xF0, 0x02, 0xF8, // bl #0xa
+                0x10, 0xBD, // pop {r4, pc}
+                0x70, 0x47, // bx lr
+            ],
+            &architecture,
+            None,
+        )
+        .unwrap();
+
+        let flags: Vec<Vec<groundtruth::FLAG>> =
+            instructions.iter().map(|i| i.flags.clone()).collect();
+        assert_eq!(
+            flags,
+            vec![
+                Vec::new(),
+                vec![groundtruth::FLAG::INSTRUCTION_CALL],
+                vec![groundtruth::FLAG::INSTRUCTION_RET],
+                vec![groundtruth::FLAG::INSTRUCTION_RET],
+            ]
+        );
+        assert_eq!(instructions[1].target, Some(0xA));
+    }
+
+    #[test]
+    fn orphaned_prefix_is_merged_into_the_following_instruction() {
+        let instructions = vec![
+            instruction("lock", 0, &[0xF0]),
+            instruction("add", 1, &[0x01, 0x18]),
+            instruction("ret", 3, &[0xC3]),
+        ];
+
+        let merged = merge_orphaned_prefixes(instructions);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].mnemonic, "lock add");
+        assert_eq!(merged[0].offset, 0);
+        assert_eq!(merged[0].length, 3);
+        assert_eq!(merged[0].bytes, vec![0xF0, 0x01, 0x18]);
+        assert_eq!(merged[0].prefixes, vec![0xF0]);
+    }
+
+    #[test]
+    fn prefix_without_adjacent_instruction_is_kept() {
+        let instructions = vec![
+            instruction("lock", 0, &[0xF0]),
+            instruction("ret", 4, &[0xC3]),
+            instruction("rep", 5, &[0xF3]),
+        ];
+
+        let merged = merge_orphaned_prefixes(instructions);
+
+        let mnemonics: Vec<&str> = merged.iter().map(|i| i.mnemonic.as_str()).collect();
+        assert_eq!(mnemonics, vec!["lock", "ret", "rep"]);
+    }
 }
@@ -1,33 +1,865 @@
+use std::env;
+use std::fs;
+use std::io::{Read, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::b2g;
+use crate::config;
+use crate::elf;
 use crate::groundtruth;
+use crate::pe;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
 use serde_derive::{Deserialize, Serialize};
+use tracing::info;
+
+/// Version of the dump format. It is incremented whenever a change breaks existing readers
+/// (removed or renamed fields, changed meaning of a field). Added fields which have a default do
+/// not change it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Schema version of dumps written before it was recorded.
+fn initial_schema_version() -> u32 {
+    1
+}
 
 /// Represents a dump containing all the information about a PDB obtained.
-#[derive(Serialize)]
-struct Dump {
-    version: String,
-    timestamp: u64,
-    architecture: groundtruth::ARCHITECTURE,
-    total_bytes: u64,
-    bytes_identified: u64,
-    accuracy: f64,
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Dump {
+    /// Version of the tool which wrote the dump.
+    pub version: String,
+    /// Version of the dump format (see [`SCHEMA_VERSION`]).
+    #[serde(default = "initial_schema_version")]
+    pub schema_version: u32,
+    pub timestamp: u64,
+    /// Architecture family (e.g. `X64`).
+    pub architecture: String,
+    #[serde(default)]
+    pub arch_info: groundtruth::ArchInfo,
+    /// ABI derived from the binary format and architecture (best effort).
+    #[serde(default)]
+    pub abi: groundtruth::ABI,
+    pub total_bytes: u64,
+    pub bytes_identified: u64,
+    pub accuracy: f64,
+    /// How hard the section is to disassemble correctly (see `--difficulty-weights`).
+    #[serde(default)]
+    pub difficulty: groundtruth::Difficulty,
+    /// Parts of the mapping contained in the dump (see `--output-kind`).
+    #[serde(default)]
+    pub output_kind: config::OUTPUT_KIND,
+    /// Order of the functions and instructions (see `--sort-output`).
+    #[serde(default)]
+    pub sort_order: config::SORT_ORDER,
+    /// Whether the processed functions were limited (see `--limit-functions`).
+    #[serde(default)]
+    pub partial: bool,
+    /// Address space of the addresses in the dump (see `--address-mode`).
+    #[serde(default)]
+    pub address_space: AddressSpace,
+    /// Address range covered by the dump if it is a shard (see `--shard-by`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shard: Option<Shard>,
+    pub bytes: Vec<groundtruth::Byte>,
+    pub functions: Vec<groundtruth::Function>,
+    pub instructions: Vec<groundtruth::Instruction>,
+    pub gaps: Vec<groundtruth::Gap>,
+    #[serde(default)]
+    pub imports: Vec<groundtruth::Import>,
+    #[serde(default)]
+    pub size_discrepancies: Vec<groundtruth::SizeDiscrepancy>,
+    /// Every label with its containing function (PE only).
+    #[serde(default)]
+    pub label_to_function: Vec<groundtruth::LabelMapping>,
+    /// PDB segments and the sections they refer to (PE only).
+    #[serde(default)]
+    pub segment_to_section: Vec<groundtruth::SegmentMapping>,
+    /// Symbols in none of the processed sections (PE only, `--list-symbols-not-in-section`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub symbols_not_in_section: Vec<groundtruth::UnattributedSymbol>,
+    /// In-line data recognized as jump tables and the kind of their entries (PE only).
+    #[serde(default)]
+    pub jump_tables: Vec<groundtruth::JumpTable>,
+    /// Branches whose target lies within another instruction.
+    #[serde(default)]
+    pub mid_instruction_targets: Vec<groundtruth::MidInstructionTarget>,
+    /// Function starts recovered from their prologues (see `--detect-prologues`).
+    #[serde(default)]
+    pub recovered_functions: Vec<groundtruth::RecoveredFunction>,
+    /// Functions without a return instruction, which possibly never return.
+    #[serde(default)]
+    pub noreturn_functions: Vec<groundtruth::NoReturnFunction>,
+    /// Data sections mapped next to the code section (see `--include-data-sections`).
+    #[serde(default)]
+    pub data_sections: Vec<groundtruth::DataSection>,
+    /// Compilers which built the binary (PE only).
+    #[serde(default)]
+    pub compilers: Vec<groundtruth::Compiler>,
+    /// Number of warnings per kind emitted while processing.
+    #[serde(default)]
+    pub warnings: groundtruth::Warnings,
+    /// Conditions detected while processing and the bytes or functions they affect.
+    #[serde(default)]
+    pub diagnostics: Vec<groundtruth::Diagnostic>,
+}
+
+impl Dump {
+    /// Looks up the byte at the given address (in the address space of the dump).
+    pub fn byte_at(&self, address: u64) -> Option<&groundtruth::Byte> {
+        // Bytes are sorted by their (rebased) offset
+        self.bytes
+            .binary_search_by_key(&address, |b| b.offset)
+            .ok()
+            .map(|index| &self.bytes[index])
+    }
+
+    /// Flags of the byte at the given address, empty for holes and addresses outside of the
+    /// section.
+    pub fn flags_at(&self, address: u64) -> &[groundtruth::FLAG] {
+        self.byte_at(address).map_or(&[], |b| &b.flags)
+    }
+}
+
+/// Creates the in-memory dump including its statistics.
+pub fn create_dump(
+    architecture: groundtruth::ArchInfo,
     bytes: Vec<groundtruth::Byte>,
     functions: Vec<groundtruth::Function>,
     instructions: Vec<groundtruth::Instruction>,
+) -> Dump {
+    // SOURCE_DATE_EPOCH pins the timestamp for reproducible dumps
+    let timestamp = match env::var("SOURCE_DATE_EPOCH").map(|s| s.parse()) {
+        Ok(Ok(timestamp)) => timestamp,
+        _ => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("System time went backwards")
+            .as_secs(),
+    };
+
+    let total_bytes = bytes.len();
+    let bytes_identified = bytes.iter().filter(|b| b.is_identified()).count();
+
+    Dump {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: SCHEMA_VERSION,
+        timestamp,
+        architecture: architecture.to_string(),
+        arch_info: architecture,
+        abi: groundtruth::ABI::UNKNOWN,
+        total_bytes: total_bytes as u64,
+        bytes_identified: bytes_identified as u64,
+        accuracy: 100.0 * (bytes_identified as f64 / total_bytes as f64),
+        difficulty: groundtruth::Difficulty::default(),
+        output_kind: config::OUTPUT_KIND::ALL,
+        sort_order: config::SORT_ORDER::ADDRESS,
+        partial: false,
+        address_space: AddressSpace::default(),
+        shard: None,
+        bytes,
+        functions,
+        instructions,
+        gaps: Vec::new(),
+        imports: Vec::new(),
+        size_discrepancies: Vec::new(),
+        label_to_function: Vec::new(),
+        segment_to_section: Vec::new(),
+        symbols_not_in_section: Vec::new(),
+        jump_tables: Vec::new(),
+        mid_instruction_targets: Vec::new(),
+        recovered_functions: Vec::new(),
+        noreturn_functions: Vec::new(),
+        data_sections: Vec::new(),
+        compilers: Vec::new(),
+        warnings: groundtruth::Warnings::default(),
+        diagnostics: Vec::new(),
+    }
 }
 
-pub mod plain {
-    use std::fs;
+pub fn create_dump_pe(pe: &b2g::pe::PE) -> Dump {
+    let mut dump = create_dump(
+        pe.architecture.clone(),
+        pe.bytes.clone(),
+        pe.pdb.functions.clone(),
+        pe.instructions.clone(),
+    );
+    dump.abi = groundtruth::ABI::for_pe(&pe.architecture);
+    dump.gaps = pe.gaps.clone();
+    dump.partial = pe.config.limit_functions.is_some();
+    dump.imports = pe.imports.clone();
+    dump.label_to_function = pe.label_to_function.clone();
+    dump.segment_to_section = pe.segment_to_section.clone();
+    dump.symbols_not_in_section = pe.symbols_not_in_section.clone();
+    dump.jump_tables = pe.jump_tables.clone();
+    dump.size_discrepancies = pe.size_discrepancies.clone();
+    dump.mid_instruction_targets = pe.mid_instruction_targets.clone();
+    dump.recovered_functions = pe.recovered_functions.clone();
+    dump.noreturn_functions = pe.noreturn_functions.clone();
+    dump.difficulty = pe.difficulty.clone();
+    dump.data_sections = pe.data_sections.clone();
+    dump.compilers = pe.pdb.compilers.clone();
+    if pe.config.include_data_bytes {
+        attach_data_bytes(&mut dump.functions, &pe.bytes);
+    }
+    dump.warnings = pe.warnings.clone();
+    dump.diagnostics = pe.warnings.diagnostics().to_vec();
+    if pe.config.deterministic {
+        dump.timestamp = 0;
+    }
+    dump
+}
+
+pub fn create_dump_elf(elf: &b2g::elf::ELF) -> Dump {
+    let mut dump = create_dump(
+        elf.architecture.clone(),
+        elf.bytes.clone(),
+        elf.dwarf.functions.clone(),
+        elf.instructions.clone(),
+    );
+    dump.abi = groundtruth::ABI::for_elf(&elf.architecture);
+    dump.gaps = elf.gaps.clone();
+    dump.partial = elf.config.limit_functions.is_some();
+    dump.size_discrepancies = elf.size_discrepancies.clone();
+    dump.mid_instruction_targets = elf.mid_instruction_targets.clone();
+    dump.recovered_functions = elf.recovered_functions.clone();
+    dump.noreturn_functions = elf.noreturn_functions.clone();
+    dump.difficulty = elf.difficulty.clone();
+    dump.data_sections = elf.data_sections.clone();
+    if elf.config.include_data_bytes {
+        attach_data_bytes(&mut dump.functions, &elf.bytes);
+    }
+    dump.warnings = elf.warnings.clone();
+    dump.diagnostics = elf.warnings.diagnostics().to_vec();
+    if elf.config.deterministic {
+        dump.timestamp = 0;
+    }
+    dump
+}
+
+/// Describes the part of a dump split with `--shard-by`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Shard {
+    pub index: usize,
+    /// Number of shards the dump was split into.
+    pub count: usize,
+    /// Address of the first byte of the shard.
+    #[serde(with = "address")]
+    pub start: u64,
+    /// Address after the last byte of the shard.
+    #[serde(with = "address")]
+    pub end: u64,
+}
+
+/// Chooses the address ranges of at most `count` shards of about the same number of bytes. A
+/// boundary within a function or instruction is moved behind it, so fewer shards remain if
+/// boundaries meet.
+pub fn shard_ranges(dump: &Dump, count: usize) -> Vec<(u64, u64)> {
+    let (start, end) = match (dump.bytes.first(), dump.bytes.last()) {
+        (Some(first), Some(last)) => (first.offset, last.offset + 1),
+        _ => return Vec::new(),
+    };
+
+    // Records which must not be split between two shards
+    let spans: Vec<(u64, u64)> = dump
+        .functions
+        .iter()
+        .map(|f| (start + f.offset, start + f.offset + f.size))
+        .chain(
+            dump.instructions
+                .iter()
+                .map(|i| (i.address, i.address + i.length)),
+        )
+        .collect();
+
+    let mut boundaries = vec![start];
+
+    for index in 1..count as u64 {
+        let mut boundary = start + (end - start) * index / count as u64;
+
+        while let Some((_, span_end)) = spans
+            .iter()
+            .find(|(span_start, span_end)| *span_start < boundary && boundary < *span_end)
+        {
+            boundary = *span_end;
+        }
+
+        if boundary > *boundaries.last().unwrap() && boundary < end {
+            boundaries.push(boundary);
+        }
+    }
+
+    boundaries.push(end);
+    boundaries.windows(2).map(|w| (w[0], w[1])).collect()
+}
+
+/// Splits the dump into shards by address range (see [`shard_ranges`]). Every record of the code
+/// section goes to the shard containing its address, function offsets stay relative to the first
+/// byte of the section. Records of other sections (imports, data sections, segments, compilers)
+/// go to the first shard, the statistics and warning counts of every shard describe the whole
+/// mapping.
+pub fn split(dump: &Dump, count: usize) -> Vec<Dump> {
+    let base = dump.bytes.first().map_or(0, |b| b.offset);
+    let ranges = shard_ranges(dump, count);
+
+    info!(
+        "Split the dump into {} shards: {}",
+        ranges.len(),
+        ranges
+            .iter()
+            .map(|(start, end)| format!("0x{:x}-0x{:x}", start, end))
+            .collect::<Vec<String>>()
+            .join(", ")
+    );
+
+    ranges
+        .iter()
+        .enumerate()
+        .map(|(index, &(start, end))| {
+            let first = index == 0;
+            let contains = |address: u64| address >= start && address < end;
+
+            let functions = pick(&dump.functions, |f| contains(base + f.offset));
+            let owns_label = |function: &Option<String>| match function {
+                Some(name) => functions.iter().any(|f| &f.name == name),
+                None => first,
+            };
+
+            Dump {
+                version: dump.version.clone(),
+                schema_version: dump.schema_version,
+                timestamp: dump.timestamp,
+                architecture: dump.architecture.clone(),
+                arch_info: dump.arch_info.clone(),
+                abi: dump.abi,
+                total_bytes: dump.total_bytes,
+                bytes_identified: dump.bytes_identified,
+                accuracy: dump.accuracy,
+                difficulty: dump.difficulty.clone(),
+                output_kind: dump.output_kind,
+                sort_order: dump.sort_order,
+                partial: dump.partial,
+                address_space: dump.address_space,
+                shard: Some(Shard {
+                    index,
+                    count: ranges.len(),
+                    start,
+                    end,
+                }),
+                bytes: pick(&dump.bytes, |b| contains(b.offset)),
+                instructions: pick(&dump.instructions, |i| contains(i.address)),
+                gaps: pick(&dump.gaps, |g| contains(g.start)),
+                imports: pick(&dump.imports, |_| first),
+                size_discrepancies: pick(&dump.size_discrepancies, |d| contains(base + d.offset)),
+                label_to_function: pick(&dump.label_to_function, |l| owns_label(&l.function)),
+                segment_to_section: pick(&dump.segment_to_section, |_| first),
+                symbols_not_in_section: pick(&dump.symbols_not_in_section, |_| first),
+                jump_tables: pick(&dump.jump_tables, |t| contains(t.address)),
+                mid_instruction_targets: pick(&dump.mid_instruction_targets, |t| {
+                    contains(t.source)
+                }),
+                recovered_functions: pick(&dump.recovered_functions, |f| contains(f.address)),
+                noreturn_functions: pick(&dump.noreturn_functions, |f| contains(f.address)),
+                data_sections: pick(&dump.data_sections, |_| first),
+                compilers: pick(&dump.compilers, |_| first),
+                warnings: dump.warnings.clone(),
+                diagnostics: pick(&dump.diagnostics, |d| match &d.range {
+                    Some(range) => contains(range.start),
+                    None => first,
+                }),
+                functions,
+            }
+        })
+        .collect()
+}
+
+fn pick<T: Clone>(items: &[T], keep: impl Fn(&T) -> bool) -> Vec<T> {
+    items.iter().filter(|item| keep(item)).cloned().collect()
+}
+
+/// Adds the contents of the in-line data of every function as a hex string. Regions reaching
+/// beyond the section are left without contents.
+fn attach_data_bytes(functions: &mut [groundtruth::Function], bytes: &[groundtruth::Byte]) {
+    for data in functions.iter_mut().flat_map(|f| f.data.iter_mut()) {
+        let region = bytes.get(data.offset as usize..(data.offset + data.size) as usize);
+
+        data.bytes =
+            region.map(|region| region.iter().map(|b| format!("{:02x}", b.value)).collect());
+    }
+}
+
+/// Restricts the dump to the given parts of the mapping. The statistics still describe the whole
+/// mapping.
+pub fn filter(dump: &mut Dump, kind: config::OUTPUT_KIND) {
+    dump.output_kind = kind;
+
+    match kind {
+        config::OUTPUT_KIND::ALL => {}
+        config::OUTPUT_KIND::CODE => {
+            dump.bytes.retain(|b| b.is_code() && !b.is_data());
+            dump.gaps.clear();
+            dump.imports.clear();
+            dump.jump_tables.clear();
+            dump.data_sections.clear();
+        }
+        config::OUTPUT_KIND::DATA => {
+            dump.bytes.retain(|b| b.is_data());
+            dump.functions.retain(|f| !f.data.is_empty());
+            dump.instructions.clear();
+            dump.gaps.clear();
+            dump.size_discrepancies.clear();
+            dump.label_to_function.clear();
+            dump.mid_instruction_targets.clear();
+            dump.recovered_functions.clear();
+            dump.noreturn_functions.clear();
+        }
+        config::OUTPUT_KIND::INSTRUCTIONS => {
+            dump.bytes.clear();
+            dump.gaps.clear();
+            dump.imports.clear();
+            dump.jump_tables.clear();
+            dump.data_sections.clear();
+        }
+    }
+}
+
+/// Orders the functions and instructions of the dump (see `--sort-output`). Instructions follow
+/// the order of the function containing them and keep their address order within it,
+/// instructions outside of every function come last. Ties are broken by address, but only the
+/// address order is guaranteed to be stable for diffing.
+pub fn sort(dump: &mut Dump, order: config::SORT_ORDER) {
+    dump.sort_order = order;
+
+    dump.functions.sort_by(|a, b| {
+        match order {
+            config::SORT_ORDER::ADDRESS => a.offset.cmp(&b.offset),
+            config::SORT_ORDER::NAME => a.display_name().cmp(b.display_name()),
+            config::SORT_ORDER::SIZE => b.size.cmp(&a.size),
+        }
+        .then(a.offset.cmp(&b.offset))
+    });
+
+    if order == config::SORT_ORDER::ADDRESS {
+        dump.instructions.sort_by_key(|i| i.address);
+        return;
+    }
+
+    // Extent of every function with its rank, by start address
+    let base = dump.bytes.first().map_or(0, |b| b.offset);
+    let mut extents: Vec<(u64, u64, usize)> = dump
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(rank, f)| (base + f.offset, base + f.offset + f.size, rank))
+        .collect();
+    extents.sort_unstable();
+
+    let rank = |address: u64| {
+        let index = extents.partition_point(|(start, _, _)| *start <= address);
+
+        match index.checked_sub(1).map(|i| extents[i]) {
+            Some((_, end, rank)) if address < end => rank,
+            _ => usize::MAX,
+        }
+    };
+
+    dump.instructions
+        .sort_by_cached_key(|i| (rank(i.address), i.address));
+}
+
+/// Names the addresses of the code section after the function containing them (see
+/// `--symbolic-addresses`).
+pub struct Symbols<'a> {
+    /// Start, end (exclusive) and name of every function, by start address.
+    extents: Vec<(u64, u64, &'a str)>,
+}
+
+impl<'a> Symbols<'a> {
+    /// Collects the extents of the functions, whose offsets are relative to `base` (the first byte
+    /// of the section).
+    pub fn new(functions: &'a [groundtruth::Function], base: u64) -> Self {
+        let mut extents: Vec<(u64, u64, &str)> = functions
+            .iter()
+            .map(|f| (base + f.offset, base + f.offset + f.size, f.display_name()))
+            .collect();
+        extents.sort_unstable();
+
+        Symbols { extents }
+    }
+
+    /// `function+0xNN` for an address within a function (the one starting last for overlapping
+    /// functions), `None` outside of every function.
+    pub fn resolve(&self, address: u64) -> Option<String> {
+        let index = self
+            .extents
+            .partition_point(|(start, _, _)| *start <= address);
+        let (start, end, name) = self.extents[..index].last()?;
+
+        if address < *end {
+            Some(format!("{}+0x{:x}", name, address - start))
+        } else {
+            None
+        }
+    }
+
+    /// ` <function+0xNN>` to append to an address, empty outside of every function or without
+    /// symbols.
+    pub fn suffix(symbols: Option<&Symbols>, address: u64) -> String {
+        match symbols.and_then(|s| s.resolve(address)) {
+            Some(symbol) => format!(" <{}>", symbol),
+            None => String::new(),
+        }
+    }
+}
+
+/// Translates the addresses of the byte vector (RVAs for PE, virtual addresses for ELF) into the
+/// address space selected with `--address-mode` and back.
+///
+/// The default translates nothing, which describes dumps written before the address mode existed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddressSpace {
+    pub mode: config::ADDRESS_MODE,
+    /// Preferred load address of the binary (lowest loadable segment for ELF).
+    #[serde(with = "address")]
+    pub image_base: u64,
+    /// Whether the byte vector uses virtual addresses (ELF) instead of RVAs (PE).
+    pub virtual_addresses: bool,
+    /// RVA of the code section.
+    #[serde(with = "address")]
+    pub section_rva: u64,
+    /// File offset of the code section.
+    #[serde(with = "address")]
+    pub section_file_offset: u64,
+}
+
+impl AddressSpace {
+    /// Translates an address of the byte vector into the address space of the outputs.
+    pub fn translate(&self, address: u64) -> u64 {
+        let rva = match self.virtual_addresses {
+            true => address.wrapping_sub(self.image_base),
+            false => address,
+        };
+
+        match self.mode {
+            config::ADDRESS_MODE::RVA => rva,
+            config::ADDRESS_MODE::VA => rva.wrapping_add(self.image_base),
+            config::ADDRESS_MODE::FILE => rva
+                .wrapping_sub(self.section_rva)
+                .wrapping_add(self.section_file_offset),
+        }
+    }
+
+    /// Translates an address of the outputs back into the address space of the byte vector
+    /// (RVAs for PE, virtual addresses for ELF).
+    pub fn resolve(&self, address: u64) -> u64 {
+        let rva = match self.mode {
+            config::ADDRESS_MODE::RVA => address,
+            config::ADDRESS_MODE::VA => address.wrapping_sub(self.image_base),
+            config::ADDRESS_MODE::FILE => address
+                .wrapping_sub(self.section_file_offset)
+                .wrapping_add(self.section_rva),
+        };
+
+        match self.virtual_addresses {
+            true => rva.wrapping_add(self.image_base),
+            false => rva,
+        }
+    }
+}
+
+/// Creates the address space of the outputs of a PE pipeline.
+pub fn address_space_pe(pe: &b2g::pe::PE, text_section: &groundtruth::Section) -> AddressSpace {
+    AddressSpace {
+        mode: pe.config.address_mode,
+        image_base: pe::get_image_base_from_buffer(&pe.binary).unwrap_or(pe.pdb.image_base),
+        virtual_addresses: false,
+        section_rva: text_section.va,
+        section_file_offset: text_section.raw_data_offset,
+    }
+}
+
+/// Creates the address space of the outputs of an ELF pipeline.
+pub fn address_space_elf(elf: &b2g::elf::ELF, text_section: &groundtruth::Section) -> AddressSpace {
+    let image_base = elf::get_image_base_from_buffer(&elf.binary).unwrap_or(0);
+
+    AddressSpace {
+        mode: elf.config.address_mode,
+        image_base,
+        virtual_addresses: true,
+        section_rva: text_section.va - image_base,
+        section_file_offset: text_section.raw_data_offset,
+    }
+}
+
+/// Translates every address of the dump (bytes, instructions, gaps, imports, ...) into the given
+/// address space. Function and in-line data offsets stay relative to the first byte.
+pub fn translate_addresses(dump: &mut Dump, space: AddressSpace) {
+    for byte in &mut dump.bytes {
+        byte.offset = space.translate(byte.offset);
+    }
+
+    for instruction in &mut dump.instructions {
+        instruction.address = space.translate(instruction.address);
+    }
+
+    for gap in &mut dump.gaps {
+        gap.start = space.translate(gap.start);
+        gap.end = space.translate(gap.end);
+    }
+
+    for import in &mut dump.imports {
+        import.iat = space.translate(import.iat);
+        import.thunk = import.thunk.map(|thunk| space.translate(thunk));
+        import.load_thunk = import.load_thunk.map(|thunk| space.translate(thunk));
+    }
+
+    for target in &mut dump.mid_instruction_targets {
+        target.source = space.translate(target.source);
+        target.target = space.translate(target.target);
+        target.instruction = space.translate(target.instruction);
+    }
+
+    for function in &mut dump.recovered_functions {
+        function.address = space.translate(function.address);
+    }
+
+    for function in &mut dump.noreturn_functions {
+        function.address = space.translate(function.address);
+    }
+
+    if let Some(shard) = &mut dump.shard {
+        shard.start = space.translate(shard.start);
+        shard.end = space.translate(shard.end);
+    }
+
+    for table in &mut dump.jump_tables {
+        table.address = space.translate(table.address);
+    }
+
+    for section in &mut dump.data_sections {
+        section.address = space.translate(section.address);
+
+        for byte in &mut section.bytes {
+            byte.offset = space.translate(byte.offset);
+        }
+
+        for symbol in &mut section.symbols {
+            symbol.address = space.translate(symbol.address);
+        }
+
+        for string in &mut section.strings {
+            string.address = space.translate(string.address);
+        }
+    }
+
+    for diagnostic in &mut dump.diagnostics {
+        if let Some(range) = &mut diagnostic.range {
+            range.start = space.translate(range.start);
+            range.end = space.translate(range.end);
+        }
+    }
+
+    dump.address_space = space;
+}
+
+/// Writes an output file, compressed if requested. The extension of the compression (`.gz`,
+/// `.zst`) is appended to the path.
+pub fn write_output(path: String, contents: &[u8], compression: config::COMPRESSION) {
+    let (path, compressed) = match compression {
+        config::COMPRESSION::NONE => (path, None),
+        config::COMPRESSION::GZIP => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder
+                .write_all(contents)
+                .expect("Unable to compress file");
+            let compressed = encoder.finish().expect("Unable to compress file");
+
+            (format!("{}.gz", path), Some(compressed))
+        }
+        config::COMPRESSION::ZSTD => {
+            let compressed = zstd::encode_all(contents, 0).expect("Unable to compress file");
+
+            (format!("{}.zst", path), Some(compressed))
+        }
+    };
+
+    match compressed {
+        Some(compressed) => {
+            info!(
+                "Compressed {} to {} bytes (ratio {:.1}).",
+                path,
+                compressed.len(),
+                contents.len() as f64 / compressed.len().max(1) as f64
+            );
+            fs::write(path, compressed).expect("Unable to write file");
+        }
+        None => fs::write(path, contents).expect("Unable to write file"),
+    }
+}
+
+/// Reads an input file, files ending with `.gz` or `.zst` are decompressed.
+pub fn read_input(path: &str) -> Result<Vec<u8>, &'static str> {
+    let contents = match fs::read(path) {
+        Ok(contents) => contents,
+        Err(_e) => return Err("Could not read file!"),
+    };
+
+    if path.ends_with(".gz") {
+        let mut decompressed = Vec::new();
+
+        return match GzDecoder::new(&contents[..]).read_to_end(&mut decompressed) {
+            Ok(_) => Ok(decompressed),
+            Err(_e) => Err("Could not decompress file!"),
+        };
+    }
+
+    if path.ends_with(".zst") {
+        return zstd::decode_all(&contents[..]).map_err(|_e| "Could not decompress file!");
+    }
+
+    Ok(contents)
+}
+
+/// (De-)serializes address-like fields either as integers or as `0x`-prefixed hex strings.
+///
+/// Deserialization accepts both representations so dumps stay readable regardless of the format
+/// they were written with.
+pub mod address {
+    use std::fmt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    static HEX: AtomicBool = AtomicBool::new(false);
+
+    /// Sets whether addresses are serialized as hex strings.
+    pub fn set_hex(hex: bool) {
+        HEX.store(hex, Ordering::Relaxed);
+    }
+
+    pub fn serialize<S: Serializer>(address: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        if HEX.load(Ordering::Relaxed) {
+            serializer.serialize_str(&format!("0x{:x}", address))
+        } else {
+            serializer.serialize_u64(*address)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        deserializer.deserialize_any(AddressVisitor)
+    }
+
+    struct AddressVisitor;
+
+    impl<'de> Visitor<'de> for AddressVisitor {
+        type Value = u64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an integer or a 0x-prefixed hex string")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<u64, E> {
+            Ok(value)
+        }
+
+        fn visit_i64<E: de::Error>(self, value: i64) -> Result<u64, E> {
+            if value < 0 {
+                return Err(E::custom("address must not be negative"));
+            }
+            Ok(value as u64)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<u64, E> {
+            let digits = value
+                .strip_prefix("0x")
+                .ok_or_else(|| E::custom("hex address must start with 0x"))?;
+
+            u64::from_str_radix(digits, 16).map_err(E::custom)
+        }
+    }
+}
+
+/// (De-)serializes instruction bytes either as a sequence of integers or as a compact hex string
+/// (e.g. `"4889e5"`).
+pub mod hex_bytes {
+    use std::fmt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use serde::de::{self, SeqAccess, Visitor};
+    use serde::{Deserializer, Serialize, Serializer};
+
+    static HEX: AtomicBool = AtomicBool::new(false);
+
+    /// Sets whether instruction bytes are serialized as hex strings.
+    pub fn set_hex(hex: bool) {
+        HEX.store(hex, Ordering::Relaxed);
+    }
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        if HEX.load(Ordering::Relaxed) {
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            serializer.serialize_str(&hex)
+        } else {
+            bytes.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        deserializer.deserialize_any(BytesVisitor)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = Vec<u8>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a sequence of bytes or a hex string")
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<u8>, A::Error> {
+            let mut bytes = Vec::new();
+
+            while let Some(byte) = seq.next_element()? {
+                bytes.push(byte);
+            }
+
+            Ok(bytes)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<Vec<u8>, E> {
+            if !value.len().is_multiple_of(2) {
+                return Err(E::custom("hex string must have an even length"));
+            }
+
+            (0..value.len())
+                .step_by(2)
+                .map(|i| {
+                    value
+                        .get(i..i + 2)
+                        .ok_or_else(|| E::custom("hex string must be ASCII"))
+                        .and_then(|digits| u8::from_str_radix(digits, 16).map_err(E::custom))
+                })
+                .collect()
+        }
+    }
+}
 
+pub mod plain {
     use crate::b2g;
+    use crate::config;
+    use crate::dumper;
     use crate::groundtruth;
 
     pub fn dump(
         file_name: String,
-        image_base: u64,
+        space: dumper::AddressSpace,
         sections: Vec<groundtruth::Section>,
         bytes: Vec<groundtruth::Byte>,
+        data_sections: &[groundtruth::DataSection],
+        symbols: Option<&dumper::Symbols>,
+        compression: config::COMPRESSION,
     ) {
-        let mut string = String::new();
+        let mut string = legend();
 
         for section in sections {
             string += &format!("******* section {} *******\n", section.name);
@@ -36,209 +868,1302 @@ pub mod plain {
                 section.name, section.va, section.raw_data_size
             );
 
+            // Only the code section and the data sections have a byte mapping
             if section.name == ".text" {
-                let mut i = 0;
-
-                while i < bytes.len() {
-                    let mut byte = &bytes[i];
-
-                    string += &format!("@0x{:012X}: ", byte.offset + image_base);
-
-                    let mut flags = "[".to_string();
-
-                    if byte.is_code() {
-                        // Check and set code related flags
-                        if byte.is_function_start() {
-                            flags += "F";
-                        }
-
-                        // This will be bytes used for alignment which are not reachable at all
-                        if byte.is_alignment() {
-                            flags += "N";
-                        }
-
-                        if byte.is_instruction_jump() {
-                            flags += "J";
-                        }
-
-                        if byte.is_instruction_interrupt() {
-                            flags += "3";
-                        }
-
-                        if byte.is_instruction_return() {
-                            flags += "R";
-                        }
-
-                        if byte.is_instruction_start() {
-                            flags += "I";
-                        }
-
-                        if byte.is_code() {
-                            flags += "C";
-                        }
-
-                        flags += "]";
-
-                        i += 1;
-                        for j in i..bytes.len() {
-                            byte = &bytes[j];
-
-                            if byte.is_code()
-                                && !byte.is_instruction_start()
-                                && !byte.is_data()
-                                && !byte.is_alignment()
-                            {
-                                flags += "C";
-                                i += 1;
-                            } else {
-                                break;
-                            }
-                        }
-                    } else if byte.is_data() {
-                        flags += "D]";
-
-                        i += 1;
-                        for j in i..bytes.len() {
-                            byte = &bytes[j];
-
-                            if byte.is_data()
-                                && !byte.is_instruction_start()
-                                && !byte.is_code()
-                                && !byte.is_alignment()
-                            {
-                                flags += "D";
-                                i += 1;
-                            } else {
-                                break;
-                            }
-                        }
-                    } else if byte.is_alignment() {
-                        flags += "N]";
-
-                        i += 1;
-                        for j in i..bytes.len() {
-                            byte = &bytes[j];
-
-                            if byte.is_alignment()
-                                && !byte.is_instruction_start()
-                                && !byte.is_code()
-                                && !byte.is_data()
-                            {
-                                flags += "N";
-                                i += 1;
-                            } else {
-                                break;
-                            }
-                        }
-                    } else {
-                        flags += "U]";
-
-                        i += 1;
-                        for j in i..bytes.len() {
-                            byte = &bytes[j];
-
-                            if !byte.is_alignment()
-                                && !byte.is_instruction_start()
-                                && !byte.is_code()
-                                && !byte.is_data()
-                            {
-                                flags += "U";
-                                i += 1;
-                            } else {
-                                break;
-                            }
-                        }
-                    }
-                    string += &flags;
-                    string += "\n";
-                }
+                string += &dump_bytes(space, &bytes, symbols);
+            } else if let Some(data_section) = data_sections.iter().find(|d| d.name == section.name)
+            {
+                string += &dump_bytes(space, &data_section.bytes, None);
             }
         }
 
         // Save dump
-        fs::write(format!("{}.txt", file_name), string).expect("Unable to write file");
+        dumper::write_output(format!("{}.txt", file_name), string.as_bytes(), compression);
+    }
+
+    /// Letter of every flag in the mapping, in the order they are written. Every flag has to be
+    /// listed here to show up in the mapping and its legend.
+    const LEGEND: &[(groundtruth::FLAG, char)] = &[
+        (groundtruth::FLAG::FUNCTION_START, 'F'),
+        (groundtruth::FLAG::FUNCTION_END, 'f'),
+        (groundtruth::FLAG::FUNCTION_BOUNDARY, '|'),
+        (groundtruth::FLAG::BLOCK_START, 'B'),
+        (groundtruth::FLAG::BLOCK_JOIN, 'b'),
+        (groundtruth::FLAG::INSTRUCTION_ALIGNMENT, 'N'),
+        (groundtruth::FLAG::INSTRUCTION_JUMP, 'J'),
+        (groundtruth::FLAG::INSTRUCTION_CALL, 'L'),
+        (groundtruth::FLAG::INSTRUCTION_INT, '3'),
+        (groundtruth::FLAG::INSTRUCTION_RET, 'R'),
+        (groundtruth::FLAG::INSTRUCTION_IRET, 'T'),
+        (groundtruth::FLAG::INSTRUCTION_START, 'I'),
+        (groundtruth::FLAG::INSTRUCTION_END, 'i'),
+        (groundtruth::FLAG::MEMORY_READ, '<'),
+        (groundtruth::FLAG::MEMORY_WRITE, '>'),
+        (groundtruth::FLAG::SUSPICIOUS, '?'),
+        (groundtruth::FLAG::RECOVERED, '~'),
+        (groundtruth::FLAG::READABLE, 'r'),
+        (groundtruth::FLAG::WRITEABLE, 'w'),
+        (groundtruth::FLAG::EXECUTABLE, 'x'),
+        (groundtruth::FLAG::CODE, 'C'),
+        (groundtruth::FLAG::DATA, 'D'),
+    ];
+
+    /// Letter of bytes which are neither code, data nor alignment.
+    const UNKNOWN: char = 'U';
+
+    /// Describes the line format and every letter of `LEGEND`.
+    fn legend() -> String {
+        let mut string =
+            "# @<address>: [<flags of the first byte>]<kind of every further byte>\n".to_string();
+        string += "# Runs of code, data, alignment or unknown bytes share a line, every \
+                   instruction and function boundary starts a new one.\n";
+
+        for (flag, letter) in LEGEND {
+            string += &format!("# {}: {:?}\n", letter, flag);
+        }
+        string += &format!("# {}: Unknown\n", UNKNOWN);
+
+        string
+    }
+
+    /// Letters of all flags of the byte, led by `U` if it is not identified (e.g. only marks a
+    /// function boundary).
+    fn letters(byte: &groundtruth::Byte) -> String {
+        let letters: String = LEGEND
+            .iter()
+            .filter(|(flag, _)| byte.flags.contains(flag))
+            .map(|(_, letter)| *letter)
+            .collect();
+
+        if byte.is_identified() {
+            letters
+        } else {
+            format!("{}{}", UNKNOWN, letters)
+        }
+    }
+
+    /// Kinds of the byte (code, data, alignment), by priority. Bytes without any are unknown.
+    fn kinds(byte: &groundtruth::Byte) -> Vec<char> {
+        let kinds: Vec<char> = [
+            (byte.is_code(), 'C'),
+            (byte.is_data(), 'D'),
+            (byte.is_alignment(), 'N'),
+        ]
+        .iter()
+        .filter(|(is, _)| *is)
+        .map(|(_, letter)| *letter)
+        .collect();
+
+        if kinds.is_empty() {
+            vec![UNKNOWN]
+        } else {
+            kinds
+        }
+    }
+
+    /// Writes one line per run of bytes with the same kind (code, data, alignment, unknown). The
+    /// first byte of a run lists all its flags, every further byte its kind.
+    fn dump_bytes(
+        space: dumper::AddressSpace,
+        bytes: &[groundtruth::Byte],
+        symbols: Option<&dumper::Symbols>,
+    ) -> String {
+        let mut string = String::new();
+        let mut i = 0;
+
+        while i < bytes.len() {
+            let byte = &bytes[i];
+            let run_kind = kinds(byte)[0];
+
+            string += &format!(
+                "@0x{:012X}{}: [{}]",
+                space.translate(byte.offset),
+                dumper::Symbols::suffix(symbols, byte.offset),
+                letters(byte)
+            );
+
+            i += 1;
+
+            // A run is continued by bytes of its kind only, bytes with several kinds start a new one
+            // and so do the boundaries after a function (which would not show otherwise)
+            while let Some(next) = bytes.get(i) {
+                if next.is_instruction_start()
+                    || next.is_function_boundary()
+                    || kinds(next) != [run_kind]
+                {
+                    break;
+                }
+
+                string.push(run_kind);
+                i += 1;
+            }
+
+            string += "\n";
+        }
+
+        string
     }
 
     pub fn dump_pe(pe: &b2g::pe::PE) {
+        let base = pe.bytes.first().map_or(0, |b| b.offset);
+        let symbols = dumper::Symbols::new(&pe.pdb.functions, base);
+
         dump(
             pe.file_name.clone(),
-            pe.pdb.image_base,
+            pe.address_space,
             pe.sections.clone(),
             pe.bytes.clone(),
+            &pe.data_sections,
+            Some(&symbols).filter(|_| pe.config.symbolic_addresses),
+            pe.config.compression,
         );
     }
 
     pub fn dump_elf(elf: &b2g::elf::ELF) {
+        let base = elf.bytes.first().map_or(0, |b| b.offset);
+        let symbols = dumper::Symbols::new(&elf.dwarf.functions, base);
+
         dump(
             elf.file_name.clone(),
-            elf.dwarf.image_base,
+            elf.address_space,
             elf.sections.clone(),
             elf.bytes.clone(),
+            &elf.data_sections,
+            Some(&symbols).filter(|_| elf.config.symbolic_addresses),
+            elf.config.compression,
         );
     }
 }
 
-pub mod yaml {
-    use std::fs;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    use serde_yaml;
-
+/// Writes the disassembly in the text format of `objdump -d -M intel`.
+pub mod objdump {
     use crate::b2g;
+    use crate::config;
+    use crate::disassembler;
     use crate::dumper;
     use crate::groundtruth;
 
+    /// Amount of instruction bytes objdump prints per line.
+    const BYTES_PER_LINE: usize = 7;
+
     pub fn dump(
         file_name: String,
-        architecture: groundtruth::ARCHITECTURE,
-        bytes: Vec<groundtruth::Byte>,
-        functions: Vec<groundtruth::Function>,
-        instructions: Vec<groundtruth::Instruction>,
+        space: dumper::AddressSpace,
+        architecture: &groundtruth::ArchInfo,
+        functions: &[groundtruth::Function],
+        bytes: &[groundtruth::Byte],
+        config: &config::Config,
     ) {
-        let start = SystemTime::now();
-        let since_the_epoch = start
-            .duration_since(UNIX_EPOCH)
-            .expect("System time went backwards");
-
-        let total_bytes = bytes.len();
-        let bytes_identified = bytes.iter().filter(|b| b.get_flags().len() > 0).count();
-
-        let dump = dumper::Dump {
-            version: "v0.1".to_string(),
-            timestamp: since_the_epoch.as_secs(),
-            architecture,
-            total_bytes: total_bytes as u64,
-            bytes_identified: bytes_identified as u64,
-            accuracy: 100.0 * (bytes_identified as f64 / total_bytes as f64),
-            bytes: bytes.clone(),
-            functions: functions.clone(),
-            instructions: instructions.clone(),
-        };
+        let mut string = String::new();
 
-        // Serialize
-        let s = serde_yaml::to_string(&dump).unwrap();
+        // Function offsets are relative to the first byte
+        let base = bytes.first().map_or(0, |b| b.offset);
+        let symbols = dumper::Symbols::new(functions, base);
+        let symbols = Some(&symbols).filter(|_| config.symbolic_addresses);
 
-        // Save dump
-        fs::write(format!("{}.yaml", file_name), s).expect("Unable to write file");
-    }
+        for function in functions {
+            let start = base + function.offset;
+            let end = start + function.size;
 
-    pub fn dump_pe(pe: &b2g::pe::PE) {
-        dump(
-            pe.file_name.clone(),
-            pe.architecture,
-            pe.bytes.clone(),
-            pe.pdb.functions.clone(),
-            pe.instructions.clone(),
-        );
-    }
+            let name = function.display_name();
 
-    pub fn dump_elf(elf: &b2g::elf::ELF) {
-        dump(
+            match architecture.bits {
+                32 => string += &format!("\n{:08x} <{}>:\n", space.translate(start), name),
+                _ => string += &format!("\n{:016x} <{}>:\n", space.translate(start), name),
+            }
+
+            // Disassemble every contiguous run of code bytes, in-line data is skipped
+            let mut run: Vec<&groundtruth::Byte> = Vec::new();
+            let mut in_data = false;
+
+            for byte in bytes.iter().filter(|b| b.offset >= start && b.offset < end) {
+                if byte.is_code() && !byte.is_data() {
+                    run.push(byte);
+                    in_data = false;
+                    continue;
+                }
+
+                string += &dump_run(space, architecture, config, symbols, &run);
+                run.clear();
+
+                // Mark the start of in-line data once
+                if byte.is_data() && !in_data {
+                    string += &format!(
+                        "{:>8x}{}:\t(data)\n",
+                        space.translate(byte.offset),
+                        dumper::Symbols::suffix(symbols, byte.offset)
+                    );
+                }
+                in_data = byte.is_data();
+            }
+
+            string += &dump_run(space, architecture, config, symbols, &run);
+        }
+
+        // Save dump
+        dumper::write_output(
+            format!("{}.objdump", file_name),
+            string.as_bytes(),
+            config.compression,
+        );
+    }
+
+    fn dump_run(
+        space: dumper::AddressSpace,
+        architecture: &groundtruth::ArchInfo,
+        config: &config::Config,
+        symbols: Option<&dumper::Symbols>,
+        run: &[&groundtruth::Byte],
+    ) -> String {
+        let mut string = String::new();
+
+        // Guard: Nothing to disassemble
+        if run.is_empty() {
+            return string;
+        }
+
+        let buffer = run.iter().map(|b| b.value).collect();
+
+        let mut instructions =
+            match disassembler::disassemble(buffer, architecture, config.disassembler) {
+                Ok(instructions) => instructions,
+                Err(_e) => return string,
+            };
+
+        disassembler::render_syntax(&mut instructions, architecture, config.syntax);
+
+        for instruction in instructions {
+            let offset = run[0].offset + instruction.offset;
+            let address = space.translate(offset);
+            let symbol = dumper::Symbols::suffix(symbols, offset);
+
+            for (line, chunk) in instruction.bytes.chunks(BYTES_PER_LINE).enumerate() {
+                let hex: String = chunk.iter().map(|b| format!("{:02x} ", b)).collect();
+
+                if line == 0 {
+                    let text = format!("{:<6} {}", instruction.mnemonic, instruction.operand);
+                    string += &format!(
+                        "{:>8x}{}:\t{:<21}\t{}\n",
+                        address,
+                        symbol,
+                        hex,
+                        text.trim_end()
+                    );
+                } else {
+                    string += &format!(
+                        "{:>8x}:\t{}\n",
+                        address + (line * BYTES_PER_LINE) as u64,
+                        hex.trim_end()
+                    );
+                }
+            }
+        }
+
+        string
+    }
+
+    pub fn dump_pe(pe: &b2g::pe::PE) {
+        dump(
+            pe.file_name.clone(),
+            pe.address_space,
+            &pe.architecture,
+            &pe.pdb.functions,
+            &pe.bytes,
+            &pe.config,
+        );
+    }
+
+    pub fn dump_elf(elf: &b2g::elf::ELF) {
+        dump(
             elf.file_name.clone(),
-            elf.architecture,
-            elf.bytes.clone(),
-            elf.dwarf.functions.clone(),
-            elf.instructions.clone(),
+            elf.address_space,
+            &elf.architecture,
+            &elf.dwarf.functions,
+            &elf.bytes,
+            &elf.config,
+        );
+    }
+}
+
+/// Writes the control-flow graph of every function in the DOT format of Graphviz. Basic blocks
+/// (see [`b2g::common::detect_basic_blocks`]) are nodes, branches and fallthroughs are edges.
+pub mod dot {
+    use std::collections::HashSet;
+
+    use crate::b2g;
+    use crate::config;
+    use crate::dumper;
+    use crate::groundtruth;
+
+    pub fn dump(
+        file_name: String,
+        space: dumper::AddressSpace,
+        functions: &[groundtruth::Function],
+        bytes: &[groundtruth::Byte],
+        instructions: &[groundtruth::Instruction],
+        compression: config::COMPRESSION,
+    ) {
+        let mut string = String::from("digraph cfg {\n");
+        string += "\tnode [shape=box, fontname=\"monospace\"];\n";
+
+        // Function offsets are relative to the first byte
+        let base = bytes.first().map_or(0, |b| b.offset);
+
+        let mut sorted: Vec<&groundtruth::Instruction> = instructions.iter().collect();
+        sorted.sort_by_key(|i| i.address);
+
+        for (index, function) in functions.iter().enumerate() {
+            let start = base + function.offset;
+            let end = start + function.size;
+
+            let function_instructions: Vec<&groundtruth::Instruction> = sorted
+                .iter()
+                .filter(|i| i.address >= start && i.address < end)
+                .copied()
+                .collect();
+
+            string += &format!("\tsubgraph cluster_{} {{\n", index);
+            string += &format!("\t\tlabel=\"{}\";\n", escape(function.display_name()));
+            string += &dump_function(space, &split_blocks(&function_instructions));
+            string += "\t}\n";
+        }
+
+        string += "}\n";
+
+        // Save dump
+        dumper::write_output(format!("{}.dot", file_name), string.as_bytes(), compression);
+    }
+
+    /// Splits the (sorted) instructions of a function into basic blocks. A block ends before a
+    /// block start and before a gap (e.g. in-line data).
+    fn split_blocks<'a>(
+        instructions: &[&'a groundtruth::Instruction],
+    ) -> Vec<Vec<&'a groundtruth::Instruction>> {
+        let mut blocks: Vec<Vec<&groundtruth::Instruction>> = Vec::new();
+
+        for instruction in instructions {
+            let contiguous = blocks
+                .last()
+                .and_then(|block| block.last())
+                .is_some_and(|last| last.address + last.length == instruction.address);
+
+            match blocks.last_mut() {
+                Some(block)
+                    if contiguous
+                        && !instruction.flags.contains(&groundtruth::FLAG::BLOCK_START) =>
+                {
+                    block.push(instruction)
+                }
+                _ => blocks.push(vec![instruction]),
+            }
+        }
+
+        blocks
+    }
+
+    fn dump_function(
+        space: dumper::AddressSpace,
+        blocks: &[Vec<&groundtruth::Instruction>],
+    ) -> String {
+        let mut string = String::new();
+
+        let starts: HashSet<u64> = blocks.iter().map(|block| block[0].address).collect();
+
+        for (index, block) in blocks.iter().enumerate() {
+            let address = block[0].address;
+
+            let label: String = block
+                .iter()
+                .map(|i| {
+                    let text = format!("{} {}", i.mnemonic, i.operand);
+                    format!(
+                        "{:x}: {}\\l",
+                        space.translate(i.address),
+                        escape(text.trim_end())
+                    )
+                })
+                .collect();
+
+            string += &format!("\t\t{} [label=\"{}\"];\n", node(space, address), label);
+
+            let last = block[block.len() - 1];
+            let jump = last.flags.contains(&groundtruth::FLAG::INSTRUCTION_JUMP);
+            let falls_through = last.falls_through();
+
+            // Branch edge (targets are restricted to the function, calls do not end a block)
+            if let Some(target) = last.target.filter(|_| jump) {
+                // Conditional jumps also fall through
+                let color = if falls_through { "green" } else { "blue" };
+
+                // Targets which do not start a block (e.g. mid-instruction) stay visible
+                if !starts.contains(&target) {
+                    string += &format!(
+                        "\t\t{} [label=\"{:x}\", shape=ellipse];\n",
+                        node(space, target),
+                        space.translate(target)
+                    );
+                }
+
+                string += &format!(
+                    "\t\t{} -> {} [color={}];\n",
+                    node(space, address),
+                    node(space, target),
+                    color
+                );
+            }
+
+            // Fallthrough edge into the directly following block
+            let next = blocks
+                .get(index + 1)
+                .filter(|next| last.address + last.length == next[0].address);
+
+            if let Some(next) = next.filter(|_| falls_through) {
+                let color = if jump { "red" } else { "blue" };
+
+                string += &format!(
+                    "\t\t{} -> {} [color={}, style=dashed];\n",
+                    node(space, address),
+                    node(space, next[0].address),
+                    color
+                );
+            }
+        }
+
+        string
+    }
+
+    /// Name of the node of the block starting at `address`.
+    fn node(space: dumper::AddressSpace, address: u64) -> String {
+        format!("b_{:x}", space.translate(address))
+    }
+
+    /// Escapes a string for a quoted DOT label.
+    fn escape(text: &str) -> String {
+        text.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+
+    pub fn dump_pe(pe: &b2g::pe::PE) {
+        dump(
+            pe.file_name.clone(),
+            pe.address_space,
+            &pe.pdb.functions,
+            &pe.bytes,
+            &pe.instructions,
+            pe.config.compression,
+        );
+    }
+
+    pub fn dump_elf(elf: &b2g::elf::ELF) {
+        dump(
+            elf.file_name.clone(),
+            elf.address_space,
+            &elf.dwarf.functions,
+            &elf.bytes,
+            &elf.instructions,
+            elf.config.compression,
+        );
+    }
+}
+
+/// Compact per-byte labels for byte classifiers (`--format labels-bin`, `.labels.bin`).
+///
+/// The file starts with a header of 24 bytes, all integers are little-endian:
+///
+/// | Offset | Size | Field                                              |
+/// |--------|------|----------------------------------------------------|
+/// | 0      | 4    | Magic `B2GL`                                       |
+/// | 4      | 2    | Format version (1)                                 |
+/// | 6      | 2    | Size of a label in bytes (4)                       |
+/// | 8      | 8    | Address of the first byte (see `--address-mode`)   |
+/// | 16     | 8    | Number of labels, one per byte of the code section |
+///
+/// It is followed by the labels, one `u32` per byte in address order. Bit `i` of a label is set
+/// if the byte has the flag `FLAGS[i]`. The labels are 4-byte aligned, so a mapped file can be
+/// used as an array directly.
+pub mod labels {
+    use crate::b2g;
+    use crate::config;
+    use crate::dumper;
+    use crate::groundtruth;
+    use tracing::info;
+
+    pub const MAGIC: &[u8; 4] = b"B2GL";
+    pub const VERSION: u16 = 1;
+    pub const HEADER_SIZE: usize = 24;
+    /// Size of a label in bytes.
+    const LABEL_SIZE: usize = 4;
+
+    /// Flag of every bit of a label. New flags are appended only, which keeps existing files
+    /// valid.
+    pub const FLAGS: [groundtruth::FLAG; 22] = [
+        groundtruth::FLAG::CODE,
+        groundtruth::FLAG::DATA,
+        groundtruth::FLAG::EXECUTABLE,
+        groundtruth::FLAG::WRITEABLE,
+        groundtruth::FLAG::READABLE,
+        groundtruth::FLAG::INSTRUCTION_START,
+        groundtruth::FLAG::INSTRUCTION_END,
+        groundtruth::FLAG::FUNCTION_START,
+        groundtruth::FLAG::FUNCTION_END,
+        groundtruth::FLAG::FUNCTION_BOUNDARY,
+        groundtruth::FLAG::BLOCK_START,
+        groundtruth::FLAG::BLOCK_JOIN,
+        groundtruth::FLAG::INSTRUCTION_ALIGNMENT,
+        groundtruth::FLAG::INSTRUCTION_JUMP,
+        groundtruth::FLAG::INSTRUCTION_CALL,
+        groundtruth::FLAG::INSTRUCTION_RET,
+        groundtruth::FLAG::INSTRUCTION_INT,
+        groundtruth::FLAG::INSTRUCTION_IRET,
+        groundtruth::FLAG::MEMORY_READ,
+        groundtruth::FLAG::MEMORY_WRITE,
+        groundtruth::FLAG::SUSPICIOUS,
+        groundtruth::FLAG::RECOVERED,
+    ];
+
+    /// Represents a loaded labels file.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Labels {
+        /// Address of the first byte.
+        pub base: u64,
+        pub labels: Vec<u32>,
+    }
+
+    impl Labels {
+        /// Flags of the byte at `address`, none outside of the labelled bytes.
+        pub fn flags_at(&self, address: u64) -> Option<Vec<groundtruth::FLAG>> {
+            let index = address.checked_sub(self.base)? as usize;
+
+            self.labels.get(index).map(|bits| flags(*bits))
+        }
+    }
+
+    /// Label of a byte with the given flags.
+    pub fn bits(flags: &[groundtruth::FLAG]) -> u32 {
+        FLAGS
+            .iter()
+            .enumerate()
+            .filter(|(_, flag)| flags.contains(flag))
+            .fold(0, |bits, (i, _)| bits | 1 << i)
+    }
+
+    /// Flags set in a label, in the order of `FLAGS`.
+    pub fn flags(bits: u32) -> Vec<groundtruth::FLAG> {
+        FLAGS
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| bits & 1 << i != 0)
+            .map(|(_, flag)| flag.clone())
+            .collect()
+    }
+
+    /// Header and labels of the bytes, which have to be contiguous.
+    pub fn encode(space: dumper::AddressSpace, bytes: &[groundtruth::Byte]) -> Vec<u8> {
+        let base = bytes.first().map_or(0, |b| space.translate(b.offset));
+
+        let mut contents = Vec::with_capacity(HEADER_SIZE + LABEL_SIZE * bytes.len());
+        contents.extend_from_slice(MAGIC);
+        contents.extend_from_slice(&VERSION.to_le_bytes());
+        contents.extend_from_slice(&(LABEL_SIZE as u16).to_le_bytes());
+        contents.extend_from_slice(&base.to_le_bytes());
+        contents.extend_from_slice(&(bytes.len() as u64).to_le_bytes());
+
+        for byte in bytes {
+            contents.extend_from_slice(&bits(&byte.flags).to_le_bytes());
+        }
+
+        contents
+    }
+
+    /// Parses the contents of a labels file, see the module documentation for the format.
+    pub fn decode(contents: &[u8]) -> Result<Labels, &'static str> {
+        // Guard: Header
+        if contents.len() < HEADER_SIZE || &contents[0..4] != MAGIC {
+            return Err("Not a labels file!");
+        }
+
+        let u16_at = |i: usize| u16::from_le_bytes([contents[i], contents[i + 1]]);
+        let u64_at = |i: usize| {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(&contents[i..i + 8]);
+            u64::from_le_bytes(bytes)
+        };
+
+        if u16_at(4) != VERSION {
+            return Err("Labels file has an unsupported version!");
+        }
+
+        if u16_at(6) as usize != LABEL_SIZE {
+            return Err("Labels file has an unsupported label size!");
+        }
+
+        let base = u64_at(8);
+        let count = u64_at(16);
+
+        let body = &contents[HEADER_SIZE..];
+
+        if body.len() as u64 != count.saturating_mul(LABEL_SIZE as u64) {
+            return Err("Labels file is truncated!");
+        }
+
+        let labels = body
+            .chunks_exact(LABEL_SIZE)
+            .map(|label| u32::from_le_bytes([label[0], label[1], label[2], label[3]]))
+            .collect();
+
+        Ok(Labels { base, labels })
+    }
+
+    /// Loads a labels file, compressed files (`.gz`, `.zst`) are decompressed.
+    pub fn load(path: &str) -> Result<Labels, &'static str> {
+        decode(&dumper::read_input(path)?)
+    }
+
+    pub fn dump(
+        file_name: String,
+        space: dumper::AddressSpace,
+        bytes: &[groundtruth::Byte],
+        compression: config::COMPRESSION,
+    ) {
+        let contents = encode(space, bytes);
+
+        info!(
+            "Labels of {} bytes take {} bytes.",
+            bytes.len(),
+            contents.len()
+        );
+
+        dumper::write_output(format!("{}.labels.bin", file_name), &contents, compression);
+    }
+
+    pub fn dump_pe(pe: &b2g::pe::PE) {
+        dump(
+            pe.file_name.clone(),
+            pe.address_space,
+            &pe.bytes,
+            pe.config.compression,
+        );
+    }
+
+    pub fn dump_elf(elf: &b2g::elf::ELF) {
+        dump(
+            elf.file_name.clone(),
+            elf.address_space,
+            &elf.bytes,
+            elf.config.compression,
+        );
+    }
+}
+
+pub mod yaml {
+    use serde_derive::Deserialize;
+
+    use serde_yaml;
+
+    use crate::b2g;
+    use crate::config;
+    use crate::dumper;
+
+    pub fn dump(
+        file_name: String,
+        dump: &dumper::Dump,
+        hex_addresses: bool,
+        hex_bytes: bool,
+        compression: config::COMPRESSION,
+    ) {
+        // Serialize
+        dumper::address::set_hex(hex_addresses);
+        dumper::hex_bytes::set_hex(hex_bytes);
+        let s = serde_yaml::to_string(dump).unwrap();
+
+        // Save dump
+        dumper::write_output(format!("{}.yaml", file_name), s.as_bytes(), compression);
+    }
+
+    /// Only the schema version of a dump, read before the dump itself.
+    #[derive(Deserialize)]
+    struct Header {
+        #[serde(default = "dumper::initial_schema_version")]
+        schema_version: u32,
+    }
+
+    /// Loads a previously written YAML dump, compressed dumps (`.gz`, `.zst`) are decompressed.
+    /// Dumps of another schema version are rejected.
+    pub fn load(path: &str) -> Result<dumper::Dump, &'static str> {
+        let s = match dumper::read_input(path).map(String::from_utf8) {
+            Ok(Ok(s)) => s,
+            _ => {
+                return Err("Could not read dump!");
+            }
+        };
+
+        let header: Header = match serde_yaml::from_str(&s) {
+            Ok(header) => header,
+            Err(_e) => return Err("Could not parse dump!"),
+        };
+
+        if header.schema_version > dumper::SCHEMA_VERSION {
+            return Err("Dump was written with a newer schema version, please update!");
+        }
+
+        if header.schema_version < dumper::SCHEMA_VERSION {
+            return Err("Dump was written with an older, incompatible schema version!");
+        }
+
+        match serde_yaml::from_str(&s) {
+            Ok(dump) => Ok(dump),
+            Err(_e) => Err("Could not parse dump!"),
+        }
+    }
+
+    /// Sorts, filters, translates and writes the dump, split into `<file>.shard<i>.yaml` files
+    /// with `--shard-by`.
+    fn write(
+        file_name: &str,
+        mut full_dump: dumper::Dump,
+        config: &config::Config,
+        space: dumper::AddressSpace,
+    ) {
+        // Before splitting, the shards pick their records in order
+        dumper::sort(&mut full_dump, config.sort_output);
+
+        let shards = match config.shard_by {
+            Some(count) => dumper::split(&full_dump, count),
+            None => vec![full_dump],
+        };
+
+        for mut shard in shards {
+            let name = match &shard.shard {
+                Some(s) => format!("{}.shard{}", file_name, s.index),
+                None => file_name.to_string(),
+            };
+
+            dumper::filter(&mut shard, config.output_kind);
+            dumper::translate_addresses(&mut shard, space);
+
+            dump(
+                name,
+                &shard,
+                config.hex_addresses,
+                config.hex_bytes,
+                config.compression,
+            );
+        }
+    }
+
+    pub fn dump_pe(pe: &b2g::pe::PE) {
+        write(
+            &pe.file_name,
+            dumper::create_dump_pe(pe),
+            &pe.config,
+            pe.address_space,
+        );
+    }
+
+    pub fn dump_elf(elf: &b2g::elf::ELF) {
+        write(
+            &elf.file_name,
+            dumper::create_dump_elf(elf),
+            &elf.config,
+            elf.address_space,
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    /// Number of shards every dump is split into.
+    const SHARDS: usize = 3;
+
+    /// Dumps of PE fixtures with and without jump tables and of ELF fixtures with data symbols and
+    /// delay slots, the outputs are named after the test.
+    fn dumps(test: &str) -> Vec<Dump> {
+        let pe_dump = include_str!("../fixtures/selftest/pe.pdb.yaml");
+        let pe = [
+            (
+                "pe",
+                pe_dump,
+                &include_bytes!("../fixtures/selftest/pe.exe")[..],
+            ),
+            (
+                "pe-delay",
+                pe_dump,
+                &include_bytes!("../fixtures/selftest/pe-delay.exe")[..],
+            ),
+            (
+                "pe-jump-relative",
+                include_str!("../fixtures/selftest/pe-jump-relative.pdb.yaml"),
+                &include_bytes!("../fixtures/selftest/pe-jump-relative.exe")[..],
+            ),
+            (
+                "pe-jump-overrun",
+                include_str!("../fixtures/selftest/pe-jump-overrun.pdb.yaml"),
+                &include_bytes!("../fixtures/selftest/pe.exe")[..],
+            ),
+        ];
+        let elf = [
+            (
+                "elf",
+                include_str!("../fixtures/selftest/elf.obj.yaml"),
+                &include_bytes!("../fixtures/selftest/elf")[..],
+            ),
+            (
+                "elf-data",
+                include_str!("../fixtures/selftest/data.obj.yaml"),
+                &include_bytes!("../fixtures/selftest/data")[..],
+            ),
+            (
+                "elf-mips",
+                include_str!("../fixtures/selftest/mips.obj.yaml"),
+                &include_bytes!("../fixtures/selftest/mips")[..],
+            ),
+            (
+                "elf-thumb",
+                include_str!("../fixtures/selftest/thumb.obj.yaml"),
+                &include_bytes!("../fixtures/selftest/thumb")[..],
+            ),
+        ];
+
+        let pe = pe.iter().map(|(name, dump, binary)| {
+            let test = format!("{}-{}", test, name);
+            create_dump_pe(&testing::process_pe(&test, dump, binary, testing::config()))
+        });
+        let elf = elf.iter().map(|(name, dump, binary)| {
+            let test = format!("{}-{}", test, name);
+            create_dump_elf(&testing::process_elf(
+                &test,
+                dump,
+                binary,
+                testing::config(),
+            ))
+        });
+
+        pe.chain(elf).collect()
+    }
+
+    /// Address of the first byte of the section of the dump.
+    fn base(dump: &Dump) -> u64 {
+        dump.bytes.first().map_or(0, |b| b.offset)
+    }
+
+    #[test]
+    fn dumps_of_the_same_input_are_identical() {
+        let mut first = dumps("identical-first");
+        let mut second = dumps("identical-second");
+
+        for (first, second) in first.iter_mut().zip(second.iter_mut()) {
+            // Only the timestamp may differ (see SOURCE_DATE_EPOCH)
+            first.timestamp = 0;
+            second.timestamp = 0;
+
+            assert_eq!(
+                serde_yaml::to_string(first).unwrap(),
+                serde_yaml::to_string(second).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn flags_at_finds_every_byte_and_nothing_outside() {
+        for dump in dumps("flags-at") {
+            for byte in &dump.bytes {
+                assert_eq!(dump.flags_at(byte.offset), &byte.flags[..]);
+            }
+
+            let outside = [
+                dump.bytes.first().and_then(|b| b.offset.checked_sub(1)),
+                dump.bytes.last().map(|b| b.offset + 1),
+            ];
+            for address in outside.iter().flatten() {
+                assert!(dump.flags_at(*address).is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn in_line_data_holds_the_bytes_of_its_function() {
+        for dump in dumps("in-line-data") {
+            for function in &dump.functions {
+                // Named in-line data (jump tables) ends within its function
+                for data in function.data.iter().filter(|d| !d.name.is_empty()) {
+                    assert!(data.offset + data.size <= function.offset + function.size);
+                }
+
+                for data in &function.data {
+                    let expected: Option<String> = dump
+                        .bytes
+                        .get(data.offset as usize..(data.offset + data.size) as usize)
+                        .map(|region| region.iter().map(|b| format!("{:02x}", b.value)).collect());
+                    assert_eq!(data.bytes, expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn functions_start_at_distinct_offsets() {
+        for dump in dumps("function-starts") {
+            for pair in dump.functions.windows(2) {
+                assert_ne!(
+                    (pair[0].segment, pair[0].offset),
+                    (pair[1].segment, pair[1].offset)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn function_boundaries_follow_every_function() {
+        for dump in dumps("function-boundaries") {
+            let base = base(&dump);
+
+            let mut expected: Vec<u64> = dump
+                .functions
+                .iter()
+                .filter(|f| f.size > 0)
+                .map(|f| base + f.offset + f.size)
+                .filter(|address| dump.byte_at(*address).is_some())
+                .collect();
+            expected.sort_unstable();
+            expected.dedup();
+
+            let found: Vec<u64> = dump
+                .bytes
+                .iter()
+                .filter(|b| b.is_function_boundary())
+                .map(|b| b.offset)
+                .collect();
+
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn symbols_name_instructions_relative_to_their_function() {
+        for dump in dumps("symbols") {
+            let base = base(&dump);
+            let symbols = Symbols::new(&dump.functions, base);
+
+            for function in &dump.functions {
+                let start = base + function.offset;
+                let end = start + function.size;
+
+                for instruction in dump
+                    .instructions
+                    .iter()
+                    .filter(|i| i.address > start && i.address < end)
+                {
+                    // Overlapping functions name the address after the later one
+                    let overlapped = dump.functions.iter().any(|f| {
+                        base + f.offset > start
+                            && base + f.offset <= instruction.address
+                            && instruction.address < base + f.offset + f.size
+                    });
+
+                    if !overlapped {
+                        let expected = format!(
+                            "{}+0x{:x}",
+                            function.display_name(),
+                            instruction.address - start
+                        );
+                        assert_eq!(symbols.resolve(instruction.address), Some(expected));
+                    }
+                }
+            }
+
+            let beyond = dump
+                .functions
+                .iter()
+                .map(|f| base + f.offset + f.size)
+                .max()
+                .filter(|end| dump.byte_at(*end).is_some());
+            assert_eq!(beyond.and_then(|end| symbols.resolve(end)), None);
+        }
+    }
+
+    #[test]
+    fn split_keeps_everything_once_and_within_its_shard() {
+        for dump in dumps("split") {
+            let base = base(&dump);
+            let shards = split(&dump, SHARDS);
+
+            let bytes: usize = shards.iter().map(|s| s.bytes.len()).sum();
+            let instructions: usize = shards.iter().map(|s| s.instructions.len()).sum();
+            let functions: usize = shards.iter().map(|s| s.functions.len()).sum();
+            assert_eq!(bytes, dump.bytes.len());
+            assert_eq!(instructions, dump.instructions.len());
+            assert_eq!(functions, dump.functions.len());
+
+            for shard in &shards {
+                let range = shard.shard.unwrap();
+
+                if range.index + 1 < range.count {
+                    let ends = shard
+                        .functions
+                        .iter()
+                        .map(|f| base + f.offset + f.size)
+                        .chain(shard.instructions.iter().map(|i| i.address + i.length));
+
+                    for end in ends {
+                        assert!(end <= range.end);
+                    }
+                }
+
+                let yaml = serde_yaml::to_string(shard).unwrap();
+                assert!(serde_yaml::from_str::<Dump>(&yaml).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn sort_keeps_the_instructions_with_their_functions() {
+        for dump in dumps("sort") {
+            let base = base(&dump);
+
+            for order in [
+                config::SORT_ORDER::ADDRESS,
+                config::SORT_ORDER::NAME,
+                config::SORT_ORDER::SIZE,
+            ] {
+                let mut sorted = dump.clone();
+                sort(&mut sorted, order);
+
+                assert_eq!(sorted.functions.len(), dump.functions.len());
+                assert_eq!(sorted.instructions.len(), dump.instructions.len());
+
+                assert!(sorted.functions.windows(2).all(|pair| match order {
+                    config::SORT_ORDER::ADDRESS => pair[0].offset <= pair[1].offset,
+                    config::SORT_ORDER::NAME => pair[0].display_name() <= pair[1].display_name(),
+                    config::SORT_ORDER::SIZE => pair[0].size >= pair[1].size,
+                }));
+
+                // Position of the function containing every instruction, in the sorted order
+                let positions: Vec<(usize, u64)> = sorted
+                    .instructions
+                    .iter()
+                    .map(|i| {
+                        let position = sorted.functions.iter().position(|f| {
+                            base + f.offset <= i.address && i.address < base + f.offset + f.size
+                        });
+                        (position.unwrap_or(usize::MAX), i.address)
+                    })
+                    .collect();
+                assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn labels_read_back_to_the_flags_of_every_byte() {
+        for dump in dumps("labels") {
+            let space = dump.address_space;
+            let labels = labels::decode(&labels::encode(space, &dump.bytes)).unwrap();
+
+            assert_eq!(labels.labels.len(), dump.bytes.len());
+
+            for byte in &dump.bytes {
+                let flags = labels.flags_at(space.translate(byte.offset)).unwrap();
+                assert_eq!(flags.len(), byte.flags.len());
+                assert!(flags.iter().all(|f| byte.flags.contains(f)));
+            }
+        }
+    }
+
+    #[test]
+    fn translate_and_resolve_are_inverse_in_every_mode() {
+        // PE image at 0x140000000 with the code section at RVA 0x1000 and file offset 0x400, ELF
+        // image at 0x400000 with the code section at 0x401000 and file offset 0x1000
+        let pe = AddressSpace {
+            image_base: 0x1_4000_0000,
+            virtual_addresses: false,
+            section_rva: 0x1000,
+            section_file_offset: 0x400,
+            ..AddressSpace::default()
+        };
+        let elf = AddressSpace {
+            image_base: 0x40_0000,
+            virtual_addresses: true,
+            section_rva: 0x1000,
+            section_file_offset: 0x1000,
+            ..AddressSpace::default()
+        };
+
+        let expected = [
+            (config::ADDRESS_MODE::RVA, 0x1010, 0x1010),
+            (config::ADDRESS_MODE::VA, 0x1_4000_1010, 0x40_1010),
+            (config::ADDRESS_MODE::FILE, 0x410, 0x1010),
+        ];
+
+        for (mode, pe_address, elf_address) in expected {
+            let pe = AddressSpace { mode, ..pe };
+            let elf = AddressSpace { mode, ..elf };
+
+            assert_eq!(pe.translate(0x1010), pe_address);
+            assert_eq!(pe.resolve(pe_address), 0x1010);
+            assert_eq!(elf.translate(0x40_1010), elf_address);
+            assert_eq!(elf.resolve(elf_address), 0x40_1010);
+        }
+    }
+
+    /// Addresses written by every dumper, parsed back from the outputs of `file_name`.
+    struct Written {
+        yaml: Dump,
+        plain: Vec<u64>,
+        objdump: Vec<u64>,
+        dot: Vec<u64>,
+        labels: u64,
+    }
+
+    fn read_written(file_name: &str) -> Written {
+        let read = |extension: &str| {
+            String::from_utf8(read_input(&format!("{}.{}", file_name, extension)).unwrap()).unwrap()
+        };
+        let hex = |text: &str| u64::from_str_radix(text.trim(), 16).unwrap();
+
+        // `@0x00000040100A: [...]`, `  40100a:\t48 89 e5 ...` and `b_40100a [label="..."]`
+        let plain = read("txt")
+            .lines()
+            .filter_map(|l| l.strip_prefix("@0x"))
+            .map(|l| hex(&l[..l.find([':', ' ']).unwrap()]))
+            .collect();
+        let objdump = read("objdump")
+            .lines()
+            .filter(|l| l.split('\t').count() == 3)
+            .map(|l| hex(&l[..l.find(':').unwrap()]))
+            .collect();
+        let dot = read("dot")
+            .lines()
+            .filter_map(|l| l.trim_start().strip_prefix("b_"))
+            .filter(|l| !l.contains("->") && !l.contains("shape=ellipse"))
+            .map(|l| hex(&l[..l.find(' ').unwrap()]))
+            .collect();
+
+        Written {
+            yaml: yaml::load(&format!("{}.yaml", file_name)).unwrap(),
+            plain,
+            objdump,
+            dot,
+            labels: labels::load(&format!("{}.labels.bin", file_name))
+                .unwrap()
+                .base,
+        }
+    }
+
+    /// Checks that every dumper wrote the addresses of the byte vector translated into `space`,
+    /// starting at `first` (the address of the first byte of the code section in its mode).
+    fn assert_addresses(
+        file_name: &str,
+        space: AddressSpace,
+        bytes: &[groundtruth::Byte],
+        instructions: &[groundtruth::Instruction],
+        first: u64,
+    ) {
+        let written = read_written(file_name);
+        let addresses: Vec<u64> = instructions
+            .iter()
+            .map(|i| space.translate(i.address))
+            .collect();
+
+        assert_eq!(space.translate(bytes[0].offset), first);
+        assert!(bytes
+            .iter()
+            .all(|b| space.resolve(space.translate(b.offset)) == b.offset));
+
+        assert_eq!(written.yaml.address_space, space);
+        assert_eq!(written.yaml.bytes[0].offset, first);
+        let mut yaml: Vec<u64> = written
+            .yaml
+            .instructions
+            .iter()
+            .map(|i| i.address)
+            .collect();
+        yaml.sort_unstable();
+        let mut sorted = addresses.clone();
+        sorted.sort_unstable();
+        assert_eq!(yaml, sorted);
+
+        assert!(written.plain.contains(&first));
+        assert!(addresses.iter().all(|a| written.plain.contains(a)));
+
+        assert!(!written.objdump.is_empty());
+        assert!(written.objdump.iter().all(|a| addresses.contains(a)));
+
+        assert!(!written.dot.is_empty());
+        assert!(written.dot.iter().all(|a| addresses.contains(a)));
+
+        assert_eq!(written.labels, first);
+    }
+
+    #[test]
+    fn every_dumper_writes_the_addresses_of_the_address_mode() {
+        let modes = [
+            config::ADDRESS_MODE::RVA,
+            config::ADDRESS_MODE::VA,
+            config::ADDRESS_MODE::FILE,
+        ];
+
+        for mode in modes {
+            let config = config::Config {
+                address_mode: mode,
+                ..testing::config()
+            };
+            let test = format!("address-mode-{:?}", mode).to_lowercase();
+
+            let pe = testing::process_pe(
+                &format!("{}-pe", test),
+                include_str!("../fixtures/selftest/pe.pdb.yaml"),
+                include_bytes!("../fixtures/selftest/pe.exe"),
+                config.clone(),
+            );
+            yaml::dump_pe(&pe);
+            plain::dump_pe(&pe);
+            objdump::dump_pe(&pe);
+            dot::dump_pe(&pe);
+            labels::dump_pe(&pe);
+
+            let text = b2g::common::select_code_section(&pe.sections, &[]).unwrap();
+            let image_base = pe::get_image_base_from_buffer(&pe.binary).unwrap();
+            let first = match mode {
+                config::ADDRESS_MODE::RVA => text.va,
+                config::ADDRESS_MODE::VA => image_base + text.va,
+                config::ADDRESS_MODE::FILE => text.raw_data_offset,
+            };
+            assert_addresses(
+                &pe.file_name,
+                pe.address_space,
+                &pe.bytes,
+                &pe.instructions,
+                first,
+            );
+
+            let elf = testing::process_elf(
+                &format!("{}-elf", test),
+                include_str!("../fixtures/selftest/elf.obj.yaml"),
+                include_bytes!("../fixtures/selftest/elf"),
+                config,
+            );
+            yaml::dump_elf(&elf);
+            plain::dump_elf(&elf);
+            objdump::dump_elf(&elf);
+            dot::dump_elf(&elf);
+            labels::dump_elf(&elf);
+
+            let text = b2g::common::select_code_section(&elf.sections, &[]).unwrap();
+            let image_base = elf::get_image_base_from_buffer(&elf.binary).unwrap();
+            let first = match mode {
+                config::ADDRESS_MODE::RVA => text.va - image_base,
+                config::ADDRESS_MODE::VA => text.va,
+                config::ADDRESS_MODE::FILE => text.raw_data_offset,
+            };
+            assert_addresses(
+                &elf.file_name,
+                elf.address_space,
+                &elf.bytes,
+                &elf.instructions,
+                first,
+            );
+        }
+    }
+}
@@ -3,112 +3,327 @@ use std::io::Read;
 
 use goblin::elf;
 use goblin::elf::header;
+use goblin::elf::program_header;
+use goblin::elf::section_header;
 
 use crate::groundtruth;
 
-pub fn get_architecture(path: &str) -> Result<groundtruth::ARCHITECTURE, &'static str> {
-    let mut buffer = Vec::new();
+pub fn get_architecture(path: &str) -> Result<groundtruth::ArchInfo, &'static str> {
+    get_architecture_from_buffer(&read_file(path)?)
+}
 
-    let mut f = match File::open(path) {
-        Ok(f) => f,
+/// Buffer-based variant of [`get_architecture`].
+pub fn get_architecture_from_buffer(buffer: &[u8]) -> Result<groundtruth::ArchInfo, &'static str> {
+    let elf = match elf::Elf::parse(buffer) {
+        Ok(pe) => pe,
         Err(_e) => {
-            return Err("[-] Could not find file!");
+            return Err("Could not parse ELF!");
         }
     };
 
-    match f.read_to_end(&mut buffer) {
-        Ok(_f) => {}
-        Err(_e) => {
-            return Err("[-] Could not read file!");
-        }
+    let family = match elf.header.e_machine {
+        header::EM_386 => groundtruth::ARCHITECTURE::X86,
+        header::EM_X86_64 => groundtruth::ARCHITECTURE::X64,
+        header::EM_MIPS if elf.is_64 => groundtruth::ARCHITECTURE::MIPS64,
+        header::EM_MIPS => groundtruth::ARCHITECTURE::MIPS,
+        header::EM_ARM => groundtruth::ARCHITECTURE::ARM,
+        _ => groundtruth::ARCHITECTURE::UNKNOWN,
+    };
+
+    let endianness = match elf.little_endian {
+        true => groundtruth::ENDIANNESS::LITTLE,
+        false => groundtruth::ENDIANNESS::BIG,
     };
 
-    let elf = match elf::Elf::parse(&buffer) {
+    Ok(groundtruth::ArchInfo {
+        family,
+        bits: if elf.is_64 { 64 } else { 32 },
+        endianness,
+        machine_name: header::machine_to_str(elf.header.e_machine).to_string(),
+    })
+}
+
+/// Add.
+pub fn read_elf(path: &str) -> Result<Vec<groundtruth::Byte>, &'static str> {
+    read_elf_from_buffer(&read_file(path)?)
+}
+
+/// Buffer-based variant of [`read_elf`].
+pub fn read_elf_from_buffer(buffer: &[u8]) -> Result<Vec<groundtruth::Byte>, &'static str> {
+    let mut bytes = Vec::new();
+
+    for (offset, byte) in buffer.iter().enumerate() {
+        bytes.push(groundtruth::Byte {
+            offset: offset as u64,
+            value: *byte,
+            flags: Vec::new(),
+            provenance: Vec::new(),
+        })
+    }
+
+    Ok(bytes)
+}
+
+/// Add.
+pub fn parse_sections(path: &str) -> Result<Vec<groundtruth::Section>, &'static str> {
+    parse_sections_from_buffer(&read_file(path)?)
+}
+
+/// Buffer-based variant of [`parse_sections`].
+pub fn parse_sections_from_buffer(
+    buffer: &[u8],
+) -> Result<Vec<groundtruth::Section>, &'static str> {
+    let elf = match elf::Elf::parse(buffer) {
         Ok(pe) => pe,
         Err(_e) => {
-            return Err("[-] Could not parse ELF!");
+            return Err("Could not parse pe");
         }
     };
 
-    let architecture = match elf.is_64 {
-        false => groundtruth::ARCHITECTURE::X86,
-        true => groundtruth::ARCHITECTURE::X64,
-        _ => groundtruth::ARCHITECTURE::UNKNOWN,
+    let mut sections: Vec<groundtruth::Section> = Vec::new();
+
+    // Sections without contents in the file only claim their offset
+    let in_file: Vec<bool> = elf
+        .section_headers
+        .iter()
+        .map(|s| {
+            !matches!(
+                s.sh_type,
+                section_header::SHT_NOBITS | section_header::SHT_NULL
+            )
+        })
+        .collect();
+
+    for section in elf.section_headers {
+        let name = match elf.shdr_strtab.get(section.sh_name) {
+            Some(Ok(name)) => name.to_string(),
+            _ => "Placeholder".to_string(),
+        };
+
+        let mut flags = Vec::new();
+        let alloc = section.sh_flags & section_header::SHF_ALLOC as u64 != 0;
+        let executable = section.sh_flags & section_header::SHF_EXECINSTR as u64 != 0;
+
+        if alloc {
+            flags.push(groundtruth::FLAG::READABLE);
+        }
+        if section.sh_flags & section_header::SHF_WRITE as u64 != 0 {
+            flags.push(groundtruth::FLAG::WRITEABLE);
+        }
+        if executable {
+            flags.push(groundtruth::FLAG::EXECUTABLE);
+            flags.push(groundtruth::FLAG::CODE);
+        }
+
+        // Loaded contents which are not code (e.g. .rodata, .data, .init_array)
+        let contents = matches!(
+            section.sh_type,
+            section_header::SHT_PROGBITS
+                | section_header::SHT_INIT_ARRAY
+                | section_header::SHT_FINI_ARRAY
+                | section_header::SHT_PREINIT_ARRAY
+        );
+        if alloc && !executable && contents {
+            flags.push(groundtruth::FLAG::DATA);
+        }
+
+        sections.push(groundtruth::Section {
+            name,
+            va: section.sh_addr,
+            raw_data_offset: section.sh_offset,
+            raw_data_size: section.sh_size,
+            flags,
+            overlaps: Vec::new(),
+        });
+    }
+
+    // Every byte of the file belongs to the first section containing it
+    groundtruth::resolve_section_overlaps(&mut sections, |index| in_file[index]);
+
+    Ok(sections)
+}
+
+/// Retrieves the image base: the lowest virtual address of a loadable segment. Position
+/// independent binaries have an image base of 0.
+pub fn get_image_base_from_buffer(buffer: &[u8]) -> Result<u64, &'static str> {
+    let elf = match elf::Elf::parse(buffer) {
+        Ok(elf) => elf,
+        Err(_e) => {
+            return Err("Could not parse ELF!");
+        }
     };
 
-    Ok(architecture)
+    Ok(elf
+        .program_headers
+        .iter()
+        .filter(|p| p.p_type == program_header::PT_LOAD)
+        .map(|p| p.p_vaddr)
+        .min()
+        .unwrap_or(0))
 }
 
-/// Add.
-pub fn read_elf(path: &str) -> Result<Vec<groundtruth::Byte>, &'static str> {
-    let mut buffer = Vec::new();
-    let mut bytes = Vec::new();
+pub fn parse_line_ranges(path: &str) -> Result<Vec<groundtruth::LineRange>, &'static str> {
+    parse_line_ranges_from_buffer(&read_file(path)?)
+}
 
-    let mut f = match File::open(path) {
-        Ok(f) => f,
+/// Buffer-based variant of [`parse_line_ranges`]. Reads the DWARF line programs of all units and
+/// returns the address ranges of every row sorted by address. Binaries without line information
+/// yield no ranges.
+pub fn parse_line_ranges_from_buffer(
+    buffer: &[u8],
+) -> Result<Vec<groundtruth::LineRange>, &'static str> {
+    let elf = match elf::Elf::parse(buffer) {
+        Ok(elf) => elf,
         Err(_e) => {
-            return Err("[-] Could not find file!");
+            return Err("Could not parse ELF!");
         }
     };
 
-    match f.read_to_end(&mut buffer) {
-        Ok(_f) => {}
+    let endian = match elf.little_endian {
+        true => gimli::RunTimeEndian::Little,
+        false => gimli::RunTimeEndian::Big,
+    };
+
+    let load_section = |id: gimli::SectionId| -> Result<_, gimli::Error> {
+        let data = section_data(&elf, buffer, id.name()).unwrap_or(&[]);
+        Ok(gimli::EndianSlice::new(data, endian))
+    };
+
+    let dwarf = match gimli::Dwarf::load(load_section) {
+        Ok(dwarf) => dwarf,
         Err(_e) => {
-            return Err("[-] Could not read file!");
+            return Err("Could not load DWARF sections!");
         }
     };
 
-    for (offset, byte) in buffer.iter().enumerate() {
-        bytes.push(groundtruth::Byte {
-            offset: offset as u64,
-            value: *byte,
-            flags: Vec::new(),
-        })
+    match collect_line_ranges(&dwarf) {
+        Ok(mut ranges) => {
+            ranges.sort_by_key(|r| r.start);
+            Ok(ranges)
+        }
+        Err(_e) => Err("Could not parse DWARF line program!"),
     }
+}
 
-    Ok(bytes)
+fn collect_line_ranges<R: gimli::Reader>(
+    dwarf: &gimli::Dwarf<R>,
+) -> Result<Vec<groundtruth::LineRange>, gimli::Error> {
+    let mut ranges = Vec::new();
+    let mut units = dwarf.units();
+
+    while let Some(header) = units.next()? {
+        let unit = dwarf.unit(header)?;
+
+        // Guard: Units without a line program (e.g. partial line info)
+        let program = match unit.line_program.clone() {
+            Some(program) => program,
+            None => continue,
+        };
+
+        let mut rows = program.rows();
+        let mut previous: Option<(u64, groundtruth::SourceLocation)> = None;
+
+        while let Some((header, row)) = rows.next_row()? {
+            // Every row ends the range of the previous one within a sequence
+            if let Some((start, location)) = previous.take() {
+                if row.address() > start {
+                    ranges.push(groundtruth::LineRange {
+                        start,
+                        end: row.address(),
+                        location,
+                    });
+                }
+            }
+
+            if row.end_sequence() {
+                continue;
+            }
+
+            let file = match row.file(header) {
+                Some(file) => {
+                    let name = dwarf.attr_string(&unit, file.path_name())?;
+                    let name = name.to_string_lossy()?.into_owned();
+
+                    match file.directory(header) {
+                        Some(directory) if !name.starts_with('/') => {
+                            let directory = dwarf.attr_string(&unit, directory)?;
+                            format!("{}/{}", directory.to_string_lossy()?, name)
+                        }
+                        _ => name,
+                    }
+                }
+                None => "<unknown>".to_string(),
+            };
+
+            let line = row.line().map_or(0, |line| line.get());
+
+            previous = Some((row.address(), groundtruth::SourceLocation { file, line }));
+        }
+    }
+
+    Ok(ranges)
 }
 
-/// Add.
-pub fn parse_sections(path: &str) -> Result<Vec<groundtruth::Section>, &'static str> {
+/// Returns the contents of the section with the given name.
+fn section_data<'a>(elf: &elf::Elf, buffer: &'a [u8], name: &str) -> Option<&'a [u8]> {
+    let section = elf
+        .section_headers
+        .iter()
+        .find(|s| elf.shdr_strtab.get(s.sh_name).and_then(|n| n.ok()) == Some(name))?;
+
+    // Guard: Sections without contents in the file (e.g. SHT_NOBITS)
+    if section.sh_type == section_header::SHT_NOBITS {
+        return None;
+    }
+
+    buffer.get(section.sh_offset as usize..(section.sh_offset + section.sh_size) as usize)
+}
+
+/// Reads the whole binary into memory.
+fn read_file(path: &str) -> Result<Vec<u8>, &'static str> {
     let mut buffer = Vec::new();
 
     let mut f = match File::open(path) {
         Ok(f) => f,
         Err(_e) => {
-            return Err("[-] Could not find file!");
+            return Err("Could not find file!");
         }
     };
 
     match f.read_to_end(&mut buffer) {
         Ok(_f) => {}
         Err(_e) => {
-            return Err("[-] Could not read file!");
+            return Err("Could not read file!");
         }
     };
 
-    let elf = match elf::Elf::parse(&buffer) {
-        Ok(pe) => pe,
-        Err(_e) => {
-            return Err("[-] Could not parse pe");
-        }
-    };
+    Ok(buffer)
+}
 
-    let mut sections: Vec<groundtruth::Section> = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for section in elf.section_headers {
-        let name = match elf.shdr_strtab.get(section.sh_name) {
-            Some(name) => name.unwrap().to_string(),
-            None => "Placeholder".to_string(),
-        };
+    /// Only `with_lines` (0x401000) is compiled with line information, `without_lines`
+    /// (0x401012) is not (see `fixtures/tests/lines.c`).
+    const PARTIAL_LINES: &[u8] = include_bytes!("../fixtures/tests/lines");
 
-        sections.push(groundtruth::Section {
-            name,
-            va: section.sh_addr as u64,
-            raw_data_offset: section.sh_offset as u64,
-            raw_data_size: section.sh_size as u64,
-        });
+    #[test]
+    fn line_ranges_of_partial_line_information() {
+        let ranges = parse_line_ranges_from_buffer(PARTIAL_LINES).unwrap();
+
+        assert!(!ranges.is_empty());
+        assert!(ranges.windows(2).all(|w| w[0].start <= w[1].start));
+        assert!(ranges
+            .iter()
+            .all(|r| r.start >= 0x401000 && r.end <= 0x401012));
+        assert!(ranges.iter().all(|r| r.location.file.ends_with("lines.c")));
     }
 
-    Ok(sections)
+    #[test]
+    fn no_line_ranges_without_line_information() {
+        let binary = include_bytes!("../fixtures/selftest/data");
+
+        assert!(parse_line_ranges_from_buffer(binary).unwrap().is_empty());
+    }
 }
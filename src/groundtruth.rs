@@ -1,9 +1,13 @@
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::dumper;
 
 /// Flags for Instructions, Functions and Bytes.
 #[allow(dead_code)]
 #[allow(non_camel_case_types)]
-#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
 pub enum FLAG {
     CODE,
     DATA,
@@ -14,30 +18,179 @@ pub enum FLAG {
     INSTRUCTION_END,
     FUNCTION_START,
     FUNCTION_END,
+    /// First byte after a function (the one following `FUNCTION_END`), whatever it is. Marks the
+    /// boundary only and does not identify the byte.
+    FUNCTION_BOUNDARY,
     BLOCK_START,
+    /// Start of a block which is reached by a branch as well as by falling through.
+    BLOCK_JOIN,
     INSTRUCTION_ALIGNMENT,
     INSTRUCTION_JUMP,
     INSTRUCTION_CALL,
     INSTRUCTION_RET,
     INSTRUCTION_INT,
     INSTRUCTION_IRET,
+    MEMORY_READ,
+    MEMORY_WRITE,
+    SUSPICIOUS,
+    /// Set by a heuristic (e.g. a function prologue) instead of the symbol source.
+    RECOVERED,
 }
 
 /// Describes different architectures.
 #[allow(dead_code)]
-#[derive(Debug, Copy, Clone, Serialize)]
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
 pub enum ARCHITECTURE {
     X64,
     X86,
+    MIPS,
+    MIPS64,
+    /// 32-bit ARM, functions may be Thumb code (see [`Function::thumb`]).
+    ARM,
+    /// Thumb code of an ARM binary, only used to decode a Thumb function.
+    THUMB,
+    UNKNOWN,
+}
+
+impl ARCHITECTURE {
+    /// Whether the x86 specific heuristics (e.g. int3 padding, prologues) apply.
+    pub fn is_x86(self) -> bool {
+        matches!(self, ARCHITECTURE::X64 | ARCHITECTURE::X86)
+    }
+
+    /// Whether the code is ARM or Thumb code, which loads constants from literal pools.
+    pub fn is_arm(self) -> bool {
+        matches!(self, ARCHITECTURE::ARM | ARCHITECTURE::THUMB)
+    }
+}
+
+/// Describes the byte order of an architecture.
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ENDIANNESS {
+    LITTLE,
+    BIG,
+}
+
+/// Describes an architecture including its bitness, byte order and exact machine type.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchInfo {
+    pub family: ARCHITECTURE,
+    pub bits: u8,
+    pub endianness: ENDIANNESS,
+    pub machine_name: String,
+}
+
+impl From<ARCHITECTURE> for ArchInfo {
+    /// Derives the details from the family alone (e.g. for symbol dumps).
+    fn from(family: ARCHITECTURE) -> Self {
+        let (bits, machine_name) = match family {
+            ARCHITECTURE::X64 => (64, "x86-64"),
+            ARCHITECTURE::X86 => (32, "x86"),
+            ARCHITECTURE::MIPS => (32, "mips"),
+            ARCHITECTURE::MIPS64 => (64, "mips64"),
+            ARCHITECTURE::ARM => (32, "arm"),
+            ARCHITECTURE::THUMB => (32, "thumb"),
+            ARCHITECTURE::UNKNOWN => (64, "unknown"),
+        };
+
+        // MIPS is big-endian unless stated otherwise
+        let endianness = match family {
+            ARCHITECTURE::MIPS | ARCHITECTURE::MIPS64 => ENDIANNESS::BIG,
+            _ => ENDIANNESS::LITTLE,
+        };
+
+        ArchInfo {
+            family,
+            bits,
+            endianness,
+            machine_name: machine_name.to_string(),
+        }
+    }
+}
+
+impl ArchInfo {
+    /// Architecture the code of the function is decoded with, Thumb functions of an ARM binary
+    /// are decoded as Thumb.
+    pub fn of_function(&self, function: &Function) -> ArchInfo {
+        match self.family {
+            ARCHITECTURE::ARM if function.thumb => ArchInfo {
+                family: ARCHITECTURE::THUMB,
+                machine_name: "thumb".to_string(),
+                ..self.clone()
+            },
+            _ => self.clone(),
+        }
+    }
+}
+
+impl Default for ArchInfo {
+    fn default() -> Self {
+        ArchInfo::from(ARCHITECTURE::UNKNOWN)
+    }
+}
+
+impl fmt::Display for ArchInfo {
+    /// Prints the family like the former bare enum (e.g. `X64`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.family)
+    }
+}
+
+/// Describes the calling convention and ABI the binary most likely follows.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum ABI {
+    /// System V AMD64 (ELF x64).
+    SYSV_AMD64,
+    /// System V i386 (ELF x86).
+    SYSV_I386,
+    /// Microsoft x64 (PE x64).
+    WIN64,
+    /// Microsoft x86 (PE x86), the calling convention may differ per function.
+    WIN32,
+    /// MIPS o32 (ELF MIPS).
+    MIPS_O32,
+    /// MIPS n64 (ELF MIPS64).
+    MIPS_N64,
+    /// ARM AAPCS (ELF ARM).
+    AAPCS,
+    #[default]
     UNKNOWN,
 }
 
+impl ABI {
+    /// Derives the ABI of a PE binary from its architecture.
+    pub fn for_pe(architecture: &ArchInfo) -> Self {
+        match architecture.family {
+            ARCHITECTURE::X64 => ABI::WIN64,
+            ARCHITECTURE::X86 => ABI::WIN32,
+            _ => ABI::UNKNOWN,
+        }
+    }
+
+    /// Derives the ABI of an ELF binary from its architecture.
+    pub fn for_elf(architecture: &ArchInfo) -> Self {
+        match architecture.family {
+            ARCHITECTURE::X64 => ABI::SYSV_AMD64,
+            ARCHITECTURE::X86 => ABI::SYSV_I386,
+            ARCHITECTURE::MIPS => ABI::MIPS_O32,
+            ARCHITECTURE::MIPS64 => ABI::MIPS_N64,
+            ARCHITECTURE::ARM | ARCHITECTURE::THUMB => ABI::AAPCS,
+            ARCHITECTURE::UNKNOWN => ABI::UNKNOWN,
+        }
+    }
+}
+
 /// Describes different architectures.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Byte {
+    #[serde(with = "dumper::address")]
     pub offset: u64,
     pub value: u8,
     pub flags: Vec<FLAG>,
+    /// Reasons for the flags of this byte (only recorded with `--explain`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub provenance: Vec<String>,
 }
 
 impl Byte {
@@ -73,28 +226,138 @@ impl Byte {
         self.flags.iter().any(|x| x == &FLAG::FUNCTION_START)
     }
 
+    pub fn is_function_end(&self) -> bool {
+        self.flags.iter().any(|x| x == &FLAG::FUNCTION_END)
+    }
+
+    pub fn is_function_boundary(&self) -> bool {
+        self.flags.iter().any(|x| x == &FLAG::FUNCTION_BOUNDARY)
+    }
+
+    /// Whether the byte has any flag besides the boundary marker of the function before it.
+    pub fn is_identified(&self) -> bool {
+        self.flags.iter().any(|x| x != &FLAG::FUNCTION_BOUNDARY)
+    }
+
     pub fn get_flags(&self) -> Vec<FLAG> {
         self.flags.clone()
     }
 
+    /// Records why this byte got its flags.
+    pub fn explain(&mut self, reason: String) {
+        if !self.provenance.contains(&reason) {
+            self.provenance.push(reason);
+        }
+    }
+
     pub fn set_flags(&mut self, flags: Vec<FLAG>) {
-        //self.flags.append(flags);s
+        // Skip flags which are already set (e.g. by a duplicate symbol at the same offset)
         for flag in flags {
-            self.flags.push(flag);
+            if !self.flags.contains(&flag) {
+                self.flags.push(flag);
+            }
         }
-        self.flags.dedup();
     }
 }
 
 /// Describes different architectures.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Instruction {
     pub mnemonic: String,
     pub operand: String,
+    /// Operand before normalization (only recorded with `--normalize-operands`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_operand: Option<String>,
+    #[serde(with = "dumper::hex_bytes")]
     pub bytes: Vec<u8>,
+    /// Legacy prefix bytes (lock/rep, segment, operand size, address size), at most one per group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prefixes: Vec<u8>,
+    /// REX prefix byte (x64 only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rex: Option<u8>,
+    #[serde(with = "dumper::address")]
     pub offset: u64,
+    /// Address of the first byte (same base as the byte offsets).
+    #[serde(default, with = "dumper::address")]
+    pub address: u64,
     pub length: u64,
     pub flags: Vec<FLAG>,
+    /// Source line the instruction was generated from (if line information is available).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceLocation>,
+    /// Address of the branch target (direct jumps and calls within the same function only).
+    #[serde(skip)]
+    pub target: Option<u64>,
+    /// Explicit operands in Intel order (x86 only, recorded with `--detailed-operands`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub operands: Vec<Operand>,
+}
+
+/// Describes the kinds of instruction operands.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OPERAND_KIND {
+    REGISTER,
+    IMMEDIATE,
+    MEMORY,
+}
+
+/// Describes how an instruction accesses an operand.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ACCESS {
+    /// Neither read nor written (e.g. the address computed by `lea`).
+    NONE,
+    READ,
+    WRITE,
+    READ_WRITE,
+}
+
+/// Represents an explicit operand of an instruction.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operand {
+    pub kind: OPERAND_KIND,
+    /// Size in bytes.
+    pub size: u8,
+    pub access: ACCESS,
+    /// Name of a register operand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub register: Option<String>,
+    /// Value of an immediate operand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub immediate: Option<i64>,
+    /// Address expression of a memory operand.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory: Option<MemoryOperand>,
+}
+
+/// Represents the address `segment:[base + index * scale + displacement]` of a memory operand.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryOperand {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub segment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    pub scale: i32,
+    pub displacement: i64,
+}
+
+/// Represents a position within a source file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u64,
+}
+
+/// Represents a range of addresses generated from the same source line.
+#[derive(Debug, Clone)]
+pub struct LineRange {
+    pub start: u64,
+    pub end: u64,
+    pub location: SourceLocation,
 }
 
 impl Instruction {
@@ -105,6 +368,23 @@ impl Instruction {
     pub fn is_alignment(&self) -> bool {
         self.flags.iter().any(|x| x == &FLAG::INSTRUCTION_ALIGNMENT)
     }
+
+    pub fn is_branch(&self) -> bool {
+        self.flags
+            .iter()
+            .any(|x| x == &FLAG::INSTRUCTION_JUMP || x == &FLAG::INSTRUCTION_CALL)
+    }
+
+    /// Whether the execution may continue with the following instruction.
+    pub fn falls_through(&self) -> bool {
+        // MIPS: j, b and jr are unconditional, ARM: b and bx
+        !(matches!(self.mnemonic.as_str(), "jmp" | "j" | "b" | "jr" | "bx")
+            || self
+                .flags
+                .iter()
+                .any(|x| x == &FLAG::INSTRUCTION_RET || x == &FLAG::INSTRUCTION_IRET))
+    }
+
     pub fn set_flags(&mut self, flags: Vec<FLAG>) {
         //self.flags.append(flags);
         for flag in flags {
@@ -118,12 +398,134 @@ impl Instruction {
 pub struct Type {}
 
 /// Represents a PE section and its meta data.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Section {
     pub name: String,
     pub va: u64,
     pub raw_data_offset: u64,
     pub raw_data_size: u64,
+    /// Contents (`CODE`, `DATA`) and permissions (`READABLE`, `WRITEABLE`, `EXECUTABLE`).
+    #[serde(default)]
+    pub flags: Vec<FLAG>,
+    /// Raw data of earlier sections which this section overlapped and which has been cut off
+    /// from it (see [`resolve_section_overlaps`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overlaps: Vec<SectionOverlap>,
+}
+
+/// Represents the raw data shared by a section with an earlier one of the section table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SectionOverlap {
+    /// Name of the earlier section, which keeps the bytes.
+    pub section: String,
+    /// File offset of the first shared byte.
+    pub start: u64,
+    /// File offset behind the last shared byte.
+    pub end: u64,
+}
+
+/// Cuts the raw data of overlapping sections so every byte of the file belongs to a single
+/// section: the first one in the section table wins. A later section starting within an earlier
+/// one starts behind it, one reaching into an earlier one ends in front of it (also if it covers
+/// the earlier one completely). The virtual address of a loaded section moves along with its
+/// start. Sections without data in the file (e.g. ELF `.bss`) are left out by `in_file`.
+pub fn resolve_section_overlaps(sections: &mut [Section], in_file: impl Fn(usize) -> bool) {
+    for index in 0..sections.len() {
+        // Guard: Nothing to cut from sections without raw data
+        if !in_file(index) {
+            continue;
+        }
+
+        let (earlier, rest) = sections.split_at_mut(index);
+        let section = &mut rest[0];
+
+        for (_, other) in earlier.iter().enumerate().filter(|(i, _)| in_file(*i)) {
+            let (start, end) = (
+                section.raw_data_offset,
+                section.raw_data_offset + section.raw_data_size,
+            );
+            let (other_start, other_end) = (
+                other.raw_data_offset,
+                other.raw_data_offset + other.raw_data_size,
+            );
+
+            // Guard: Disjoint or empty ranges
+            if start >= end || other_start >= other_end || start >= other_end || other_start >= end
+            {
+                continue;
+            }
+
+            section.overlaps.push(SectionOverlap {
+                section: other.name.clone(),
+                start: start.max(other_start),
+                end: end.min(other_end),
+            });
+
+            let (new_start, new_end) = if other_start <= start {
+                (other_end.min(end), end)
+            } else {
+                (start, other_start)
+            };
+
+            if section.va != 0 {
+                section.va += new_start - start;
+            }
+            section.raw_data_offset = new_start;
+            section.raw_data_size = new_end - new_start;
+        }
+    }
+}
+
+impl Section {
+    /// Whether the section holds initialized data which is not executable.
+    pub fn is_data(&self) -> bool {
+        self.raw_data_size > 0
+            && self.flags.contains(&FLAG::DATA)
+            && !self.flags.contains(&FLAG::EXECUTABLE)
+    }
+
+    /// Permission flags of the section.
+    pub fn permissions(&self) -> Vec<FLAG> {
+        self.flags
+            .iter()
+            .filter(|f| matches!(f, FLAG::READABLE | FLAG::WRITEABLE | FLAG::EXECUTABLE))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Represents a data section classified by `--include-data-sections`. Its bytes are mapped like
+/// the bytes of the code section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DataSection {
+    pub name: String,
+    #[serde(with = "dumper::address")]
+    pub address: u64,
+    pub size: u64,
+    /// Permissions of the section.
+    pub flags: Vec<FLAG>,
+    pub symbols: Vec<DataSymbol>,
+    pub strings: Vec<StringLiteral>,
+    pub bytes: Vec<Byte>,
+}
+
+/// Represents a data symbol (global or static variable) located in a data section.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataSymbol {
+    pub name: String,
+    #[serde(with = "dumper::address")]
+    pub address: u64,
+    pub size: u64,
+}
+
+/// Represents a NUL terminated string of printable characters within a data section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StringLiteral {
+    #[serde(with = "dumper::address")]
+    pub address: u64,
+    /// Length without the terminating NUL.
+    pub length: u64,
+    pub value: String,
 }
 
 /// Represents a hole (meaning contiguous unidentified bytes) within a byte vector.
@@ -134,57 +536,593 @@ pub struct Hole {
     pub size: u64,
 }
 
+/// Describes what lies between two consecutive functions.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GAP_KIND {
+    EMPTY,
+    PADDING,
+    CODE,
+    DATA,
+}
+
+/// Represents the bytes between the end of a function and the start of the next one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Gap {
+    #[serde(with = "dumper::address")]
+    pub start: u64,
+    #[serde(with = "dumper::address")]
+    pub end: u64,
+    pub size: u64,
+    pub kind: GAP_KIND,
+}
+
+/// Represents an entry of the PE import address table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Import {
+    pub name: String,
+    pub dll: String,
+    /// Address of the IAT entry.
+    #[serde(with = "dumper::address")]
+    pub iat: u64,
+    pub size: u64,
+    /// Address of the jump thunk calling through the IAT entry (if found).
+    pub thunk: Option<u64>,
+    /// Whether the DLL is loaded on the first call (delay import directory).
+    #[serde(default)]
+    pub delayed: bool,
+    /// Address of the load thunk the delay IAT entry points to until the import is resolved.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub load_thunk: Option<u64>,
+}
+
+/// Represents a RUNTIME_FUNCTION entry of the x64 exception directory (`.pdata`). All addresses
+/// are RVAs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeFunction {
+    pub begin: u64,
+    pub end: u64,
+    pub unwind_info: u64,
+    /// Size of the UNWIND_INFO structure (without language specific handler data).
+    pub unwind_info_size: u64,
+}
+
+/// Represents a function whose declared size disagrees with its disassembly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SizeDiscrepancy {
+    pub name: String,
+    #[serde(with = "dumper::address")]
+    pub offset: u64,
+    pub declared_size: u64,
+    pub disassembled_size: u64,
+    /// Whether the function was shrunk to its disassembly (see `--trust-disasm`).
+    #[serde(default)]
+    pub applied: bool,
+}
+
+/// Represents a branch whose target lies within another instruction (overlapping instructions or
+/// anti-disassembly tricks).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MidInstructionTarget {
+    /// Address of the branch.
+    #[serde(with = "dumper::address")]
+    pub source: u64,
+    /// Address the branch jumps to.
+    #[serde(with = "dumper::address")]
+    pub target: u64,
+    /// Address of the instruction containing the target.
+    #[serde(with = "dumper::address")]
+    pub instruction: u64,
+}
+
+/// Describes how a function without a reachable `ret` ends.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum NORETURN_REASON {
+    /// Ends in a trap (`int3`, `ud2`).
+    TRAP,
+    /// Ends in a call (e.g. to `abort` or `exit`).
+    CALL,
+    /// Ends in a jump (e.g. a tail call, which may return through its target).
+    JUMP,
+    /// Ends in any other instruction (e.g. `hlt`).
+    OTHER,
+}
+
+/// Represents a function without any return instruction, which possibly never returns. Only the
+/// instructions within the function are considered, not the functions it calls.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoReturnFunction {
+    pub name: String,
+    /// Address of the first byte of the function.
+    #[serde(with = "dumper::address")]
+    pub address: u64,
+    pub reason: NORETURN_REASON,
+}
+
+/// Describes how the entries of a jump table encode their targets.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum JUMP_TABLE_ENTRY {
+    /// Pointer sized virtual address of the target (e.g. MSVC x86).
+    ABSOLUTE,
+    /// 4-byte RVA of the target (e.g. MSVC x64).
+    IMAGE_RELATIVE,
+    /// Signed 4-byte offset of the target from the start of the table (e.g. position independent
+    /// code of GCC and Clang).
+    RELATIVE,
+}
+
+/// Represents in-line data of a function which was recognized as a jump table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JumpTable {
+    pub function: String,
+    #[serde(with = "dumper::address")]
+    pub address: u64,
+    pub entry: JUMP_TABLE_ENTRY,
+    pub entry_size: u64,
+    /// Number of consecutive entries whose target lies within the function.
+    pub entries: u64,
+}
+
+/// Describes the function prologues recognized by `--detect-prologues`.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+pub enum PROLOGUE {
+    /// `push rbp; mov rbp, rsp` (`push ebp; mov ebp, esp` on x86).
+    FRAME_POINTER,
+    /// `sub rsp, imm` (`sub esp, imm` on x86).
+    STACK_ALLOCATION,
+    /// `mov edi, edi; push ebp; mov ebp, esp` of hotpatchable x86 functions.
+    HOTPATCH,
+}
+
+/// Represents a function start recovered from its prologue (not known to the symbol source).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveredFunction {
+    #[serde(with = "dumper::address")]
+    pub address: u64,
+    pub prologue: PROLOGUE,
+}
+
+/// Rates how hard the binary is to disassemble correctly. Every component lies within 0 and 1,
+/// the score is their weighted mean scaled to 0-100.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Difficulty {
+    pub score: f64,
+    /// Fraction of the classified bytes which are in-line data.
+    pub inline_data: f64,
+    /// Decode failures per function (at most 1).
+    pub decode_failures: f64,
+    /// Mean entropy of the hole bytes in bits per byte, divided by 8.
+    pub hole_entropy: f64,
+    /// Branches into the middle of an instruction per branch with a known target (at most 1).
+    pub mid_instruction_targets: f64,
+    /// Code/data transitions per 64 classified bytes (at most 1).
+    pub transitions: f64,
+}
+
+/// Represents an S_COMPILE3 record describing the compiler which built a module.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Compiler {
+    pub name: String,
+    pub frontend_version: String,
+    pub backend_version: String,
+    pub machine: String,
+    pub flags: Vec<String>,
+}
+
+impl Compiler {
+    /// Whether the module was compiled with `/hotpatch`.
+    pub fn is_hotpatchable(&self) -> bool {
+        self.flags.iter().any(|f| f == "HotPatch")
+    }
+}
+
+/// Describes conditions which are reported but do not abort processing.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum WARNING {
+    SKIPPED_SYMBOL,
+    FUNCTION_OUT_OF_BOUNDS,
+    INSTRUCTION_OUT_OF_BOUNDS,
+    FUNCTION_WITHOUT_CODE,
+    DECODE_FAILURE,
+    DATA_OVERLAP,
+    ARCHITECTURE_MISMATCH,
+    MID_INSTRUCTION_TARGET,
+    DATA_ONLY_FUNCTION,
+    /// The last instruction of a function continues behind its declared end.
+    STRADDLING_INSTRUCTION,
+    /// A symbol refers to a PDB segment without a section in the PE header.
+    SEGMENT_WITHOUT_SECTION,
+    /// Not a single function of the symbol source lies in the code section.
+    NO_FUNCTIONS,
+    /// The disassembly of a function exceeded the time budget (see `--function-timeout`).
+    FUNCTION_TIMEOUT,
+    /// A symbol does not fit the section it refers to (see `--validate-offsets`).
+    SYMBOL_OUT_OF_BOUNDS,
+    /// The raw data of a section overlaps an earlier section, which keeps the shared bytes.
+    SECTION_OVERLAP,
+    /// A symbol lies in none of the processed sections (see `--list-symbols-not-in-section`).
+    SYMBOL_NOT_IN_SECTION,
+    /// The labels of an in-line jump table imply more entries than its function has bytes left.
+    JUMP_TABLE_OVERRUN,
+}
+
+impl WARNING {
+    /// Describes a number of occurrences for the end of run summary.
+    fn describe(self, count: u64) -> String {
+        match self {
+            WARNING::SKIPPED_SYMBOL => format!("{} symbols skipped (no size or location)", count),
+            WARNING::FUNCTION_OUT_OF_BOUNDS => {
+                format!("{} functions skipped (out of bounds)", count)
+            }
+            WARNING::INSTRUCTION_OUT_OF_BOUNDS => {
+                format!("{} instructions skipped (out of bounds)", count)
+            }
+            WARNING::FUNCTION_WITHOUT_CODE => {
+                format!("{} functions not starting with code", count)
+            }
+            WARNING::DECODE_FAILURE => format!("{} decode failures", count),
+            WARNING::DATA_OVERLAP => format!("{} data symbols overlapping code", count),
+            WARNING::ARCHITECTURE_MISMATCH => "architecture may be wrong".to_string(),
+            WARNING::MID_INSTRUCTION_TARGET => {
+                format!("{} branches into the middle of an instruction", count)
+            }
+            WARNING::DATA_ONLY_FUNCTION => {
+                format!("{} functions reclassified as data (no code bytes)", count)
+            }
+            WARNING::STRADDLING_INSTRUCTION => {
+                format!("{} instructions continuing behind their function", count)
+            }
+            WARNING::SEGMENT_WITHOUT_SECTION => {
+                format!("{} symbols in segments without a section", count)
+            }
+            WARNING::NO_FUNCTIONS => "0 functions resolved (dump may not match)".to_string(),
+            WARNING::FUNCTION_TIMEOUT => {
+                format!("{} functions skipped (disassembly timed out)", count)
+            }
+            WARNING::SYMBOL_OUT_OF_BOUNDS => {
+                format!("{} symbols dropped (outside of their section)", count)
+            }
+            WARNING::SECTION_OVERLAP => {
+                format!("{} overlaps of section raw data cut off", count)
+            }
+            WARNING::SYMBOL_NOT_IN_SECTION => {
+                format!("{} symbols outside of the processed sections", count)
+            }
+            WARNING::JUMP_TABLE_OVERRUN => {
+                format!("{} jump tables clamped to their function", count)
+            }
+        }
+    }
+
+    /// Conditions which invalidate the whole mapping are errors.
+    pub fn severity(self) -> SEVERITY {
+        match self {
+            WARNING::ARCHITECTURE_MISMATCH | WARNING::NO_FUNCTIONS => SEVERITY::ERROR,
+            _ => SEVERITY::WARNING,
+        }
+    }
+}
+
+/// Describes how much a diagnostic affects the trustworthiness of the mapping.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SEVERITY {
+    /// A heuristic classified the bytes instead of the symbol source.
+    INFO,
+    WARNING,
+    ERROR,
+}
+
+/// Represents the bytes from `start` to `end` (inclusive).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ByteRange {
+    #[serde(with = "dumper::address")]
+    pub start: u64,
+    #[serde(with = "dumper::address")]
+    pub end: u64,
+}
+
+/// Represents a condition detected while processing together with the region it affects.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Diagnostic {
+    pub severity: SEVERITY,
+    /// Warning counted in the end of run summary (none for informational diagnostics).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<WARNING>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub range: Option<ByteRange>,
+}
+
+impl Diagnostic {
+    pub fn warning(kind: WARNING, message: String) -> Diagnostic {
+        Diagnostic {
+            severity: kind.severity(),
+            kind: Some(kind),
+            message,
+            function: None,
+            range: None,
+        }
+    }
+
+    pub fn info(message: String) -> Diagnostic {
+        Diagnostic {
+            severity: SEVERITY::INFO,
+            kind: None,
+            message,
+            function: None,
+            range: None,
+        }
+    }
+
+    pub fn in_function(mut self, name: &str) -> Diagnostic {
+        self.function = Some(name.to_string());
+        self
+    }
+
+    pub fn at(mut self, start: u64, end: u64) -> Diagnostic {
+        self.range = Some(ByteRange { start, end });
+        self
+    }
+}
+
+/// Counts the warnings emitted during a run and keeps them as diagnostics.
+///
+/// Only the counts are serialized here, the dump holds the diagnostics in a field of their own.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Warnings {
+    counts: BTreeMap<WARNING, u64>,
+    #[serde(skip)]
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Warnings {
+    /// Records a diagnostic, its kind (if any) is counted for the summary.
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        if let Some(kind) = diagnostic.kind {
+            *self.counts.entry(kind).or_insert(0) += 1;
+        }
+
+        self.diagnostics.push(diagnostic);
+    }
+
+    /// Records a number of occurrences which cannot be attributed to a region as one diagnostic.
+    pub fn add_many(&mut self, kind: WARNING, count: u64) {
+        if count > 0 {
+            *self.counts.entry(kind).or_insert(0) += count;
+            self.diagnostics
+                .push(Diagnostic::warning(kind, kind.describe(count)));
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    pub fn count(&self, kind: WARNING) -> u64 {
+        self.counts.get(&kind).copied().unwrap_or(0)
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+}
+
+impl fmt::Display for Warnings {
+    /// Prints a one-line summary (e.g. `12 functions skipped (out of bounds), 3 decode failures`).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let summary: Vec<String> = self
+            .counts
+            .iter()
+            .map(|(kind, count)| kind.describe(*count))
+            .collect();
+
+        write!(f, "{}", summary.join(", "))
+    }
+}
+
 /// Represents a symbol with the S_THUNK32 tag.
-#[derive(Debug, PartialEq, Serialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Thunk {
     pub offset: u64,
     pub segment: u8,
     pub size: u64,
+    /// Named function located at the same offset, which represents the thunk in the functions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
 }
 
 /// Represents a symbol with an S_LDATA32 or S_GDATA32 tag.
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Data {
     pub name: String,
+    #[serde(with = "dumper::address")]
     pub offset: u64,
     pub segment: u8,
     pub size: u64,
+    /// Contents as a hex string (see `--include-data-bytes`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes: Option<String>,
 }
 
 /// Represents a symbol with the S_LABEL32 tag.
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Label {
     pub name: String,
+    #[serde(with = "dumper::address")]
     pub offset: u64,
     pub segment: u8,
 }
 
+/// Maps a label to the function containing it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LabelMapping {
+    pub name: String,
+    #[serde(with = "dumper::address")]
+    pub offset: u64,
+    pub segment: u8,
+    /// Name of the containing function (none for orphaned labels).
+    pub function: Option<String>,
+    /// Offset of the label relative to the start of the containing function.
+    pub offset_in_function: Option<u64>,
+}
+
+/// Maps a PDB segment to the section of the PE header it refers to. Segments are numbered from 1
+/// in the order of the section headers, which are indexed from 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentMapping {
+    pub segment: u8,
+    /// Name of the section (none if the PE header has no such section).
+    pub section: Option<String>,
+    /// Index of the section in the PE header.
+    pub index: Option<usize>,
+    /// Number of symbols located in the segment.
+    pub symbols: u64,
+    /// Number of symbols located behind the raw data of the section (e.g. in `.bss`).
+    pub beyond_raw_data: u64,
+}
+
+/// A symbol of the PDB located in none of the processed sections (see
+/// `--list-symbols-not-in-section`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnattributedSymbol {
+    /// `Function`, `Label` or `Data`.
+    pub kind: String,
+    pub name: String,
+    pub segment: u8,
+    #[serde(with = "dumper::address")]
+    pub offset: u64,
+    /// Name of the section the segment refers to (none if the PE header has no such section).
+    pub section: Option<String>,
+}
+
 /// Represents a symbol with an S_GPROC32, S_LPROC32 or S_PUB32 tag.
-#[derive(Debug, Clone, PartialEq, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Function {
     pub name: String,
+    /// Demangled name (see `--demangle`), `name` itself if it is not mangled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub demangled_name: Option<String>,
+    #[serde(with = "dumper::address")]
     pub offset: u64,
     pub segment: u8,
     pub size: u64,
     pub labels: Vec<Label>,
     pub data: Vec<Data>,
+    /// Stack frame of the procedure (S_FRAMEPROC), PDB only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame: Option<Frame>,
+    /// Whether the function is Thumb code (ARM only), the symbol value has its lowest bit set.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub thumb: bool,
+}
+
+impl Function {
+    /// Demangled name if available, the raw name otherwise.
+    pub fn display_name(&self) -> &str {
+        self.demangled_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Represents an S_FRAMEPROC record describing the stack frame of the preceding procedure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Frame {
+    /// Total size of the frame in bytes, excluding the callee saved registers.
+    pub size: u64,
+    pub padding: u64,
+    pub callee_saved: u64,
+    pub flags: Vec<String>,
+}
+
+impl Frame {
+    /// Whether the procedure uses structured exception handling (`__try`).
+    pub fn has_seh(&self) -> bool {
+        self.flags
+            .iter()
+            .any(|f| f == "HasStructuredExceptionHandling")
+    }
+
+    /// Whether the procedure uses C++ exception handling.
+    pub fn has_eh(&self) -> bool {
+        self.flags.iter().any(|f| f == "HasExceptionHandling")
+    }
+
+    /// Whether the procedure was compiled with asynchronous exception handling (`/EHa`).
+    pub fn has_async_eh(&self) -> bool {
+        self.flags
+            .iter()
+            .any(|f| f == "AsynchronousExceptionHandling")
+    }
 }
 
 /// Represents all accumulated information about a PDB file.
 #[derive(Debug)]
 pub struct PDB {
     pub image_base: u64,
-    pub architecture: ARCHITECTURE,
+    pub architecture: ArchInfo,
     pub functions: Vec<Function>,
     pub data: Vec<Data>,
     pub thunks: Vec<Thunk>,
     pub labels: Vec<Label>,
+    /// Distinct compilers of all modules (S_COMPILE3).
+    pub compilers: Vec<Compiler>,
+    /// Symbols dropped because their size or location is missing.
+    pub skipped_symbols: u64,
 }
 
 /// Represents all accumulated information about a ELF file.
 #[derive(Debug)]
 pub struct DWARF {
     pub image_base: u64,
-    pub architecture: ARCHITECTURE,
+    pub architecture: ArchInfo,
     pub functions: Vec<Function>,
+    /// Data symbols (`STT_OBJECT`), their offsets are virtual addresses.
+    pub data: Vec<Data>,
+    /// Symbols dropped because their size or location is missing.
+    pub skipped_symbols: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn instruction(mnemonic: &str, flags: Vec<FLAG>) -> Instruction {
+        Instruction {
+            flags,
+            ..crate::testing::instruction(mnemonic, 0x1000, 1)
+        }
+    }
+
+    #[test]
+    fn only_unconditional_jumps_and_returns_do_not_fall_through() {
+        assert!(!instruction("jmp", vec![FLAG::INSTRUCTION_JUMP]).falls_through());
+        assert!(!instruction("ret", vec![FLAG::INSTRUCTION_RET]).falls_through());
+        assert!(!instruction("iretq", vec![FLAG::INSTRUCTION_IRET]).falls_through());
+        assert!(instruction("je", vec![FLAG::INSTRUCTION_JUMP]).falls_through());
+        assert!(instruction("call", vec![FLAG::INSTRUCTION_CALL]).falls_through());
+    }
+
+    #[test]
+    fn set_flags_skips_flags_already_set() {
+        let mut byte = Byte {
+            offset: 0x1000,
+            value: 0x55,
+            flags: Vec::new(),
+            provenance: Vec::new(),
+        };
+
+        byte.set_flags(vec![FLAG::CODE, FLAG::FUNCTION_START]);
+        byte.set_flags(vec![FLAG::CODE, FLAG::READABLE, FLAG::FUNCTION_START]);
+
+        assert_eq!(
+            byte.flags,
+            vec![FLAG::CODE, FLAG::FUNCTION_START, FLAG::READABLE]
+        );
+    }
 }
@@ -0,0 +1,221 @@
+use std::collections::BTreeMap;
+
+use crate::config;
+use crate::dumper;
+
+/// Amount of example addresses kept per pattern and dump.
+const EXAMPLES_PER_DUMP: usize = 3;
+
+/// Holes with at least this entropy (bits per byte) look compressed or encrypted.
+const HIGH_ENTROPY: f64 = 6.0;
+
+/// Minimum length of a run of printable characters for a hole to be a string.
+const MIN_STRING_LENGTH: usize = 4;
+
+/// Describes what the bytes of a hole look like.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PATTERN {
+    /// Less than 4 bytes.
+    SHORT,
+    /// Only 0x00 bytes.
+    ZERO_FILL,
+    /// A single repeated byte value other than 0x00.
+    FILL,
+    /// Printable ASCII, optionally NUL terminated.
+    STRING,
+    /// Pointer sized little endian values sharing their upper half (e.g. a jump table).
+    POINTER_TABLE,
+    /// High entropy bytes (e.g. compressed or encrypted data).
+    HIGH_ENTROPY,
+    /// Anything else.
+    UNKNOWN,
+}
+
+/// Represents a single hole (contiguous unflagged bytes) of a dump.
+#[derive(Debug, Clone)]
+pub struct Hole {
+    pub address: u64,
+    pub size: u64,
+    pub entropy: f64,
+    pub pattern: PATTERN,
+}
+
+/// Summarizes the holes of one pattern across all dumps.
+#[derive(Debug, Clone)]
+pub struct PatternSummary {
+    pub pattern: PATTERN,
+    pub holes: u64,
+    pub bytes: u64,
+    /// Mean entropy of the holes in bits per byte.
+    pub entropy: f64,
+    /// Addresses of the first holes per dump.
+    pub examples: BTreeMap<String, Vec<u64>>,
+}
+
+/// Shannon entropy of the given bytes in bits per byte.
+pub fn entropy(bytes: &[u8]) -> f64 {
+    let mut counts = [0u64; 256];
+
+    for byte in bytes {
+        counts[*byte as usize] += 1;
+    }
+
+    counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| {
+            let p = *count as f64 / bytes.len() as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Classifies the bytes of a hole.
+pub fn classify(bytes: &[u8], pointer_size: usize) -> PATTERN {
+    if bytes.len() < 4 {
+        return PATTERN::SHORT;
+    }
+
+    if bytes.iter().all(|b| *b == 0x00) {
+        return PATTERN::ZERO_FILL;
+    }
+
+    if bytes.iter().all(|b| *b == bytes[0]) {
+        return PATTERN::FILL;
+    }
+
+    if is_string(bytes) {
+        return PATTERN::STRING;
+    }
+
+    if is_pointer_table(bytes, pointer_size) {
+        return PATTERN::POINTER_TABLE;
+    }
+
+    if entropy(bytes) >= HIGH_ENTROPY {
+        return PATTERN::HIGH_ENTROPY;
+    }
+
+    PATTERN::UNKNOWN
+}
+
+fn is_string(bytes: &[u8]) -> bool {
+    // Trailing NUL bytes terminate (and pad) the string
+    let end = bytes.iter().rposition(|b| *b != 0x00).map_or(0, |i| i + 1);
+
+    end >= MIN_STRING_LENGTH && bytes[..end].iter().all(|b| is_printable(*b))
+}
+
+fn is_printable(byte: u8) -> bool {
+    byte.is_ascii_graphic() || byte == b' ' || byte == b'\t' || byte == b'\n'
+}
+
+/// Finds the NUL terminated runs of at least 4 printable characters. Returns their start and
+/// length (without the NUL).
+pub fn find_strings(bytes: &[u8]) -> Vec<(usize, usize)> {
+    let mut strings = Vec::new();
+    let mut start = 0;
+
+    for (index, byte) in bytes.iter().enumerate() {
+        if is_printable(*byte) {
+            continue;
+        }
+
+        if *byte == 0x00 && index - start >= MIN_STRING_LENGTH {
+            strings.push((start, index - start));
+        }
+
+        start = index + 1;
+    }
+
+    strings
+}
+
+fn is_pointer_table(bytes: &[u8], pointer_size: usize) -> bool {
+    // Guard: At least two complete entries
+    if !bytes.len().is_multiple_of(pointer_size) || bytes.len() < 2 * pointer_size {
+        return false;
+    }
+
+    let mut entries = bytes.chunks(pointer_size).map(|entry| {
+        entry
+            .iter()
+            .rev()
+            .fold(0u64, |value, byte| (value << 8) | u64::from(*byte))
+    });
+
+    let first = entries.next().unwrap();
+    let upper = |value: u64| value >> (pointer_size * 4);
+
+    first != 0 && entries.all(|entry| upper(entry) == upper(first))
+}
+
+/// Finds and classifies the holes of a complete dump.
+pub fn collect(dump: &dumper::Dump) -> Result<Vec<Hole>, &'static str> {
+    if dump.output_kind != config::OUTPUT_KIND::ALL {
+        return Err("Only dumps written with --output-kind all contain every byte!");
+    }
+
+    let pointer_size = (dump.arch_info.bits / 8).max(1) as usize;
+    let mut holes = Vec::new();
+
+    for run in dump.bytes.split(|b| b.is_identified()) {
+        // Guard: Adjacent flagged bytes
+        if run.is_empty() {
+            continue;
+        }
+
+        let values: Vec<u8> = run.iter().map(|b| b.value).collect();
+
+        holes.push(Hole {
+            address: run[0].offset,
+            size: values.len() as u64,
+            entropy: entropy(&values),
+            pattern: classify(&values, pointer_size),
+        });
+    }
+
+    Ok(holes)
+}
+
+/// Aggregates the holes of several dumps (named by their path) per pattern. The most common
+/// patterns come first.
+pub fn aggregate(holes: &[(String, Vec<Hole>)]) -> Vec<PatternSummary> {
+    let mut summaries: BTreeMap<PATTERN, PatternSummary> = BTreeMap::new();
+
+    for (name, holes) in holes {
+        for hole in holes {
+            let summary = summaries
+                .entry(hole.pattern)
+                .or_insert_with(|| PatternSummary {
+                    pattern: hole.pattern,
+                    holes: 0,
+                    bytes: 0,
+                    entropy: 0.0,
+                    examples: BTreeMap::new(),
+                });
+
+            summary.holes += 1;
+            summary.bytes += hole.size;
+            summary.entropy += hole.entropy;
+
+            let examples = summary.examples.entry(name.clone()).or_default();
+            if examples.len() < EXAMPLES_PER_DUMP {
+                examples.push(hole.address);
+            }
+        }
+    }
+
+    let mut summaries: Vec<PatternSummary> = summaries
+        .into_values()
+        .map(|mut summary| {
+            summary.entropy /= summary.holes as f64;
+            summary
+        })
+        .collect();
+
+    summaries.sort_by(|a, b| b.holes.cmp(&a.holes).then(b.bytes.cmp(&a.bytes)));
+
+    summaries
+}
@@ -0,0 +1,19 @@
+pub mod b2g;
+pub mod bundle;
+pub mod checkpoint;
+pub mod config;
+pub mod coverage;
+pub mod demangle;
+pub mod disassembler;
+pub mod dumper;
+pub mod elf;
+pub mod groundtruth;
+pub mod holes;
+pub mod parser;
+pub mod pe;
+pub mod query;
+pub mod repl;
+pub mod selftest;
+#[cfg(test)]
+mod testing;
+pub mod verify;
@@ -1,66 +1,1085 @@
-pub mod b2g;
-pub mod disassembler;
-pub mod dumper;
-pub mod elf;
-pub mod groundtruth;
-pub mod parser;
-pub mod pe;
-
-use clap::{App, Arg};
-use goblin::{error, Object};
-use log::{error, info, warn};
-use std::env;
-use std::fs::File;
+use binary2groundtruth::{
+    b2g, bundle, checkpoint, config, coverage, disassembler, dumper, holes, query, selftest, verify,
+};
+use clap::{App, AppSettings, Arg, SubCommand};
+use goblin::mach::{constants::cputype, Mach};
+use goblin::Object;
+use std::fs::{self, File};
 use std::io::Read;
-use std::path::Path;
+use std::process;
+use tracing::{error, info, info_span};
+use tracing_subscriber::EnvFilter;
+
+/// Magic bytes at the start of every WebAssembly module.
+const WASM_MAGIC: &[u8] = b"\0asm";
+
+/// Describes the binary formats with a pipeline.
+#[allow(non_camel_case_types, clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BINARY_FORMAT {
+    PE,
+    ELF,
+}
 
 fn main() {
     let matches = App::new("Binary2Groundtruth")
-        .version("0.1")
+        .version(env!("CARGO_PKG_VERSION"))
         .author("xitan <git@xitan.me>")
         .about("Creates groundtruth mappings from PDBs/ELFs.")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .setting(AppSettings::StrictUtf8)
         .arg(
             Arg::with_name("DUMP")
-                .help("Sets the input PDB/ELF YAML dump to use.")
-                .required(true)
+                .help("Sets the input PDB/ELF YAML dump to use (comma-separated dumps are merged).")
+                .required_unless_one(&[
+                    "verify-dump",
+                    "hole-report",
+                    "coverage-diff",
+                    "query",
+                    "bundle",
+                ])
                 .index(1),
         )
         .arg(
             Arg::with_name("BINARY")
                 .help("Sets the input PE/ELF to use.")
-                .required(true)
+                .required_unless_one(&[
+                    "verify-dump",
+                    "hole-report",
+                    "coverage-diff",
+                    "query",
+                    "bundle",
+                ])
                 .index(2),
         )
+        .arg(
+            Arg::with_name("bundle")
+                .long("bundle")
+                .value_name("ARCHIVE")
+                .conflicts_with_all(&["DUMP", "BINARY", "checkpoint"])
+                .help("Reads the binary and its YAML dump from a single .tar/.zip archive."),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .long("verbose")
+                .multiple(true)
+                .help("Logs more details: -v adds debug output, -vv traces every stage (RUST_LOG takes precedence)."),
+        )
+        .arg(
+            Arg::with_name("verify")
+                .long("verify")
+                .help("Checks that all emitted bytes still match the binary after processing."),
+        )
+        .arg(
+            Arg::with_name("padding-bytes")
+                .long("padding-bytes")
+                .value_name("HEX,HEX,...")
+                .default_value("0xCC")
+                .help("Sets the byte values treated as padding fill within holes."),
+        )
+        .arg(
+            Arg::with_name("repl")
+                .long("repl")
+                .help("Starts an interactive prompt for querying the result after processing."),
+        )
+        .arg(
+            Arg::with_name("no-truncate")
+                .long("no-truncate")
+                .help("Keeps the trailing 0x00 bytes at the end of the section."),
+        )
+        .arg(
+            Arg::with_name("exclude-section")
+                .long("exclude-section")
+                .value_name("NAME")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Skips the given code section (e.g. .text$di). Can be repeated."),
+        )
+        .arg(
+            Arg::with_name("hex-addresses")
+                .long("hex-addresses")
+                .help("Writes offsets in the YAML dump as 0x-prefixed hex strings."),
+        )
+        .arg(
+            Arg::with_name("hex-bytes")
+                .long("hex-bytes")
+                .help("Writes instruction bytes in the YAML dump as hex strings (e.g. 4889e5)."),
+        )
+        .arg(
+            Arg::with_name("symbolic-addresses")
+                .long("symbolic-addresses")
+                .help("Appends the containing function and offset (e.g. <main+0x1f>) to the addresses of the .txt and .objdump outputs."),
+        )
+        .arg(
+            Arg::with_name("limit-functions")
+                .long("limit-functions")
+                .value_name("N")
+                .help("Processes only the first N functions. The dump is marked as partial."),
+        )
+        .arg(
+            Arg::with_name("shard-by")
+                .long("shard-by")
+                .value_name("N")
+                .help("Splits the YAML dump into N shards by address range (<file>.shard0.yaml, ...)."),
+        )
+        .arg(
+            Arg::with_name("include-imports")
+                .long("include-imports")
+                .help("Flags IAT entries and import thunks within the code section (PE only)."),
+        )
+        .arg(
+            Arg::with_name("function")
+                .long("function")
+                .value_name("NAME")
+                .help("Restricts the output to a single function (and its labels/data)."),
+        )
+        .arg(
+            Arg::with_name("format")
+                .long("format")
+                .value_name("FORMAT")
+                .possible_values(&["yaml", "objdump", "dot", "labels-bin"])
+                .default_value("yaml")
+                .help("Sets the output format (yaml: .txt and .yaml, objdump: .objdump listing, dot: .dot control-flow graph, labels-bin: .labels.bin per-byte flag bitfields)."),
+        )
+        .arg(
+            Arg::with_name("address-mode")
+                .long("address-mode")
+                .value_name("MODE")
+                .possible_values(&["rva", "va", "file"])
+                .default_value("va")
+                .help("Sets the address space of all emitted addresses."),
+        )
+        .arg(
+            Arg::with_name("syntax")
+                .long("syntax")
+                .value_name("SYNTAX")
+                .possible_values(&["intel", "att"])
+                .default_value("intel")
+                .help("Sets the assembly syntax of the instruction text."),
+        )
+        .arg(
+            Arg::with_name("disassembler")
+                .long("disassembler")
+                .value_name("BACKEND")
+                .possible_values(&["capstone"])
+                .default_value("capstone")
+                .help("Sets the disassembler backend."),
+        )
+        .arg(
+            Arg::with_name("output-kind")
+                .long("output-kind")
+                .value_name("KIND")
+                .possible_values(&["all", "code", "data", "instructions"])
+                .default_value("all")
+                .help("Restricts the YAML dump to code, data or instructions."),
+        )
+        .arg(
+            Arg::with_name("sort-output")
+                .long("sort-output")
+                .value_name("ORDER")
+                .possible_values(&["address", "name", "size"])
+                .default_value("address")
+                .help("Orders the functions and instructions of the YAML dump (only address order is stable for diffing)."),
+        )
+        .arg(
+            Arg::with_name("compress")
+                .long("compress")
+                .value_name("COMPRESSION")
+                .possible_values(&["gzip", "zstd", "none"])
+                .default_value("none")
+                .help("Compresses the written outputs (.gz/.zst)."),
+        )
+        .arg(
+            Arg::with_name("explain")
+                .long("explain")
+                .help("Records why each byte got its flags in the YAML dump (large output)."),
+        )
+        .arg(
+            Arg::with_name("flag-mnemonics")
+                .long("flag-mnemonics")
+                .value_name("MNEMONIC,...")
+                .help("Flags instructions with one of the given mnemonics as suspicious."),
+        )
+        .arg(
+            Arg::with_name("trust-disasm")
+                .long("trust-disasm")
+                .help("Shrinks functions whose declared size exceeds their disassembly."),
+        )
+        .arg(
+            Arg::with_name("fill-min-run")
+                .long("fill-min-run")
+                .value_name("BYTES")
+                .default_value("16")
+                .help("Minimum length of a repeated multi-byte fill pattern (0 disables it)."),
+        )
+        .arg(
+            Arg::with_name("include-data-sections")
+                .long("include-data-sections")
+                .help("Maps the data sections (bytes, data symbols and strings) as well."),
+        )
+        .arg(
+            Arg::with_name("include-data-bytes")
+                .long("include-data-bytes")
+                .help("Adds the contents of every in-line data region to the YAML dump (hex)."),
+        )
+        .arg(
+            Arg::with_name("source-lines")
+                .long("source-lines")
+                .help("Maps every instruction to its source line using the DWARF line program (ELF only)."),
+        )
+        .arg(
+            Arg::with_name("detect-prologues")
+                .long("detect-prologues")
+                .help("Flags common function prologues within holes as recovered function starts."),
+        )
+        .arg(
+            Arg::with_name("demangle")
+                .long("demangle")
+                .help("Adds the demangled name (C++, Rust) of every function to the outputs."),
+        )
+        .arg(
+            Arg::with_name("hotpatch-padding")
+                .long("hotpatch-padding")
+                .help("Flags the hotpatch area in front of every function as alignment (PE only)."),
+        )
+        .arg(
+            Arg::with_name("normalize-operands")
+                .long("normalize-operands")
+                .help("Replaces addresses in instruction operands with 0xADDR in the dumps."),
+        )
+        .arg(
+            Arg::with_name("detailed-operands")
+                .long("detailed-operands")
+                .help("Adds the operands of every instruction (kind, value and access) to the YAML dump (x86 only, enlarges the dump)."),
+        )
+        .arg(
+            Arg::with_name("functions-from")
+                .long("functions-from")
+                .value_name("FILE")
+                .help("Adds the functions of a function list exported from Ghidra or IDA."),
+        )
+        .arg(
+            Arg::with_name("functions-format")
+                .long("functions-format")
+                .value_name("FORMAT")
+                .possible_values(&["ghidra", "ida"])
+                .default_value("ghidra")
+                .help("Sets the format of the --functions-from list (ghidra: CSV, ida: TSV)."),
+        )
+        .arg(
+            Arg::with_name("anomaly-threshold")
+                .long("anomaly-threshold")
+                .value_name("FRACTION")
+                .default_value("0.1")
+                .help(
+                    "Sets the fraction of anomalous instructions which hints a wrong architecture.",
+                ),
+        )
+        .arg(
+            Arg::with_name("difficulty-weights")
+                .long("difficulty-weights")
+                .value_name("WEIGHTS")
+                .default_value("1,1,1,1,1")
+                .help(
+                    "Sets the weights of the difficulty score (inline data, decode failures, hole entropy, mid-instruction targets, transitions).",
+                ),
+        )
+        .arg(
+            Arg::with_name("use-pdata")
+                .long("use-pdata")
+                .help("Adds and validates function boundaries with the .pdata section (PE x64)."),
+        )
+        .arg(
+            Arg::with_name("deterministic")
+                .long("deterministic")
+                .help("Sets the timestamp of the YAML dump to 0 for reproducible outputs."),
+        )
+        .arg(
+            Arg::with_name("max-memory")
+                .long("max-memory")
+                .value_name("MB")
+                .help("Aborts if the estimated memory usage exceeds MB (best effort)."),
+        )
+        .arg(
+            Arg::with_name("function-timeout")
+                .long("function-timeout")
+                .value_name("MS")
+                .help("Skips functions whose disassembly takes longer than MS milliseconds."),
+        )
+        .arg(
+            Arg::with_name("validate-offsets")
+                .long("validate-offsets")
+                .help("Checks all symbols against their sections first and drops the ones outside."),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .long("strict")
+                .requires("validate-offsets")
+                .help("Aborts if a symbol does not fit its section (with --validate-offsets)."),
+        )
+        .arg(
+            Arg::with_name("count-only")
+                .long("count-only")
+                .help("Only prints how many symbols lie in the code section and exits."),
+        )
+        .arg(
+            Arg::with_name("list-symbols-not-in-section")
+                .long("list-symbols-not-in-section")
+                .help("Lists the functions, labels and data symbols which lie in none of the processed sections (PE only)."),
+        )
+        .arg(
+            Arg::with_name("checkpoint")
+                .long("checkpoint")
+                .value_name("PATH")
+                .help("Records the completed DUMP and BINARY pair in a checkpoint file."),
+        )
+        .arg(
+            Arg::with_name("resume")
+                .long("resume")
+                .requires("checkpoint")
+                .help("Skips the DUMP and BINARY pair if the checkpoint lists it as completed."),
+        )
+        .arg(
+            Arg::with_name("verify-dump")
+                .long("verify-dump")
+                .value_names(&["DUMP", "BINARY"])
+                .number_of_values(2)
+                .help("Checks a previously generated YAML dump against its binary and exits."),
+        )
+        .arg(
+            Arg::with_name("hole-report")
+                .long("hole-report")
+                .value_name("DUMP")
+                .multiple(true)
+                .help("Ranks the unclassified byte patterns of several YAML dumps and exits."),
+        )
+        .arg(
+            Arg::with_name("coverage-diff")
+                .long("coverage-diff")
+                .value_names(&["BASELINE", "CURRENT"])
+                .number_of_values(2)
+                .help("Lists the bytes only one of two YAML dumps of the same binary identifies and exits."),
+        )
+        .arg(
+            Arg::with_name("query")
+                .long("query")
+                .value_names(&["EXPRESSION", "DUMP"])
+                .number_of_values(2)
+                .help("Lists the instructions and bytes of a YAML dump whose flags match EXPRESSION and exits."),
+        )
+        .subcommand(
+            SubCommand::with_name("selftest")
+                .about("Processes the built-in PE and ELF fixtures and checks the results."),
+        )
         .get_matches();
 
-    //pdb2groundtruth::run(matches.value_of("PDB").unwrap(), matches.value_of("PE").unwrap());
+    // Log the progress by default, RUST_LOG or -v/-vv show the details. The selftest only logs
+    // errors, the warnings of the fixtures are expected and its results are the PASS/FAIL lines.
+    let selftest = matches.subcommand_matches("selftest").is_some();
+    let level = match (matches.occurrences_of("verbose"), selftest) {
+        (0, true) => "error",
+        (0, false) => "info",
+        (1, _) => "debug",
+        _ => "trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+
+    info!("Binary2Groundtruth Parser started.");
+
+    if selftest {
+        process::exit(run_selftest());
+    }
+
+    if let Some(mut values) = matches.values_of("verify-dump") {
+        let (dump, binary) = (values.next().unwrap(), values.next().unwrap());
+        process::exit(run_verify_dump(dump, binary));
+    }
+
+    if let Some(mut values) = matches.values_of("coverage-diff") {
+        let (baseline, current) = (values.next().unwrap(), values.next().unwrap());
+        process::exit(run_coverage_diff(baseline, current));
+    }
+
+    if let Some(mut values) = matches.values_of("query") {
+        let (expression, dump) = (values.next().unwrap(), values.next().unwrap());
+        process::exit(run_query(expression, dump));
+    }
+
+    if let Some(paths) = matches.values_of("hole-report") {
+        process::exit(run_hole_report(&paths.collect::<Vec<&str>>()));
+    }
+
+    // Fail early with the offending path instead of deep within the pipeline
+    let inputs = matches
+        .value_of("DUMP")
+        .into_iter()
+        .flat_map(|paths| paths.split(','))
+        .map(|path| ("DUMP", path))
+        .chain(matches.value_of("BINARY").map(|path| ("BINARY", path)))
+        .chain(matches.value_of("bundle").map(|path| ("Bundle", path)));
+
+    for (kind, path) in inputs {
+        if let Err(e) = check_input(kind, path) {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+
+    // Load the checkpoint of a batch run and skip the pair if it is done already
+    let checkpoint = match matches.value_of("checkpoint") {
+        Some(path) => match load_checkpoint(
+            path,
+            matches.value_of("DUMP").unwrap(),
+            matches.value_of("BINARY").unwrap(),
+        ) {
+            Ok(checkpoint) => Some(checkpoint),
+            Err(e) => {
+                error!("{}", e);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
 
-    simple_logger::init().unwrap();
+    if let Some((checkpoint, entry)) = &checkpoint {
+        if matches.is_present("resume") && checkpoint.contains(&entry.hash) {
+            info!("Inputs were already processed according to the checkpoint, skipping.");
+            process::exit(0);
+        }
+    }
+
+    let padding_bytes = match config::parse_byte_list(matches.value_of("padding-bytes").unwrap()) {
+        Ok(padding_bytes) => padding_bytes,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
 
-    info!("[+] Binary2Groundtruth Parser started.");
+    let limit_functions = match matches.value_of("limit-functions").map(str::parse) {
+        None => None,
+        Some(Ok(limit)) => Some(limit),
+        Some(Err(_e)) => {
+            error!("--limit-functions expects a number.");
+            process::exit(1);
+        }
+    };
+
+    let shard_by = match matches.value_of("shard-by").map(str::parse) {
+        None => None,
+        Some(Ok(count)) if count > 0 => Some(count),
+        Some(_) => {
+            error!("--shard-by expects a positive number.");
+            process::exit(1);
+        }
+    };
 
-    let mut fd =
-        File::open(matches.value_of("BINARY").unwrap()).expect("[-] Could not find binary.");
+    let max_memory = match matches.value_of("max-memory").map(str::parse) {
+        None => None,
+        Some(Ok(limit)) => Some(limit),
+        Some(Err(_e)) => {
+            error!("--max-memory expects a number.");
+            process::exit(1);
+        }
+    };
+
+    let function_timeout = match matches.value_of("function-timeout").map(str::parse) {
+        None => None,
+        Some(Ok(timeout)) => Some(timeout),
+        Some(Err(_e)) => {
+            error!("--function-timeout expects a number of milliseconds.");
+            process::exit(1);
+        }
+    };
+
+    let fill_min_run = match matches.value_of("fill-min-run").unwrap().parse() {
+        Ok(length) => length,
+        Err(_e) => {
+            error!("--fill-min-run expects a number.");
+            process::exit(1);
+        }
+    };
+
+    let anomaly_threshold = match matches.value_of("anomaly-threshold").unwrap().parse() {
+        Ok(threshold) => threshold,
+        Err(_e) => {
+            error!("--anomaly-threshold expects a number.");
+            process::exit(1);
+        }
+    };
+
+    let difficulty_weights =
+        match config::parse_difficulty_weights(matches.value_of("difficulty-weights").unwrap()) {
+            Ok(weights) => weights,
+            Err(e) => {
+                error!("--difficulty-weights: {}", e);
+                process::exit(1);
+            }
+        };
+
+    let config = config::Config {
+        verify: matches.is_present("verify"),
+        padding_bytes,
+        repl: matches.is_present("repl"),
+        no_truncate: matches.is_present("no-truncate"),
+        exclude_sections: matches
+            .values_of("exclude-section")
+            .map(|v| v.map(String::from).collect())
+            .unwrap_or_default(),
+        hex_addresses: matches.is_present("hex-addresses"),
+        hex_bytes: matches.is_present("hex-bytes"),
+        symbolic_addresses: matches.is_present("symbolic-addresses"),
+        limit_functions,
+        include_imports: matches.is_present("include-imports"),
+        function: matches.value_of("function").map(String::from),
+        explain: matches.is_present("explain"),
+        trust_disassembly: matches.is_present("trust-disasm"),
+        hotpatch_padding: matches.is_present("hotpatch-padding"),
+        detect_prologues: matches.is_present("detect-prologues"),
+        demangle: matches.is_present("demangle"),
+        include_data_sections: matches.is_present("include-data-sections"),
+        include_data_bytes: matches.is_present("include-data-bytes"),
+        source_lines: matches.is_present("source-lines"),
+        normalize_operands: matches.is_present("normalize-operands"),
+        detailed_operands: matches.is_present("detailed-operands"),
+        functions_from: matches.value_of("functions-from").map(String::from),
+        functions_format: match matches.value_of("functions-format").unwrap() {
+            "ida" => config::FUNCTIONS_FORMAT::IDA,
+            _ => config::FUNCTIONS_FORMAT::GHIDRA,
+        },
+        anomaly_threshold,
+        difficulty_weights,
+        function_timeout,
+        validate_offsets: matches.is_present("validate-offsets"),
+        strict: matches.is_present("strict"),
+        shard_by,
+        use_pdata: matches.is_present("use-pdata"),
+        count_only: matches.is_present("count-only"),
+        list_symbols_not_in_section: matches.is_present("list-symbols-not-in-section"),
+        deterministic: matches.is_present("deterministic"),
+        max_memory,
+        fill_min_run,
+        output_kind: match matches.value_of("output-kind").unwrap() {
+            "code" => config::OUTPUT_KIND::CODE,
+            "data" => config::OUTPUT_KIND::DATA,
+            "instructions" => config::OUTPUT_KIND::INSTRUCTIONS,
+            _ => config::OUTPUT_KIND::ALL,
+        },
+        sort_output: match matches.value_of("sort-output").unwrap() {
+            "name" => config::SORT_ORDER::NAME,
+            "size" => config::SORT_ORDER::SIZE,
+            _ => config::SORT_ORDER::ADDRESS,
+        },
+        compression: match matches.value_of("compress").unwrap() {
+            "gzip" => config::COMPRESSION::GZIP,
+            "zstd" => config::COMPRESSION::ZSTD,
+            _ => config::COMPRESSION::NONE,
+        },
+        flag_mnemonics: matches
+            .value_of("flag-mnemonics")
+            .map(|list| list.split(',').map(|m| m.trim().to_lowercase()).collect())
+            .unwrap_or_default(),
+        format: match matches.value_of("format").unwrap() {
+            "objdump" => config::FORMAT::OBJDUMP,
+            "dot" => config::FORMAT::DOT,
+            "labels-bin" => config::FORMAT::LABELS_BIN,
+            _ => config::FORMAT::YAML,
+        },
+        address_mode: match matches.value_of("address-mode").unwrap() {
+            "rva" => config::ADDRESS_MODE::RVA,
+            "file" => config::ADDRESS_MODE::FILE,
+            _ => config::ADDRESS_MODE::VA,
+        },
+        syntax: match matches.value_of("syntax").unwrap() {
+            "att" => config::SYNTAX::ATT,
+            _ => config::SYNTAX::INTEL,
+        },
+        // Capstone is the only backend the argument accepts
+        disassembler: disassembler::DISASSEMBLER::CAPSTONE,
+    };
+
+    if let Some(path) = matches.value_of("bundle") {
+        process::exit(run_bundle(path, config));
+    }
+
+    // Attach the binary to every log line emitted while processing it
+    let _span = info_span!("binary", path = matches.value_of("BINARY").unwrap()).entered();
+
+    let mut fd = File::open(matches.value_of("BINARY").unwrap()).expect("Could not find binary.");
     let mut buffer = Vec::new();
-    fd.read_to_end(&mut buffer)
-        .expect("[-] Could not read binary.");
-    match Object::parse(&buffer).expect("") {
-        Object::Elf(_) => {
+    fd.read_to_end(&mut buffer).expect("Could not read binary.");
+
+    let format = match binary_format(&buffer) {
+        Ok(format) => format,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    match format {
+        BINARY_FORMAT::ELF => {
             let mut p2g = b2g::elf::ELF::new(
                 matches.value_of("DUMP").unwrap(),
                 matches.value_of("BINARY").unwrap(),
+                config,
             );
             p2g.process();
         }
-        Object::PE(_) => {
+        BINARY_FORMAT::PE => {
             let mut p2g = b2g::pe::PE::new(
                 matches.value_of("DUMP").unwrap(),
                 matches.value_of("BINARY").unwrap(),
+                config,
             );
             p2g.process();
         }
-        _ => {
-            error!("[-] Binary not supported. Only PE and ELF binaries are supported.");
+    }
+
+    // The outputs are written, a resumed run may skip this pair (a census writes none)
+    if let Some((mut checkpoint, entry)) = checkpoint.filter(|_| !matches.is_present("count-only"))
+    {
+        if let Err(e) = checkpoint.record(entry) {
+            error!("{}", e);
+            process::exit(1);
         }
     }
 }
+
+/// Loads the checkpoint and creates the entry describing the given inputs.
+fn load_checkpoint(
+    path: &str,
+    dump: &str,
+    binary: &str,
+) -> Result<(checkpoint::Checkpoint, checkpoint::Entry), &'static str> {
+    let checkpoint = checkpoint::Checkpoint::load(path)?;
+
+    let mut inputs: Vec<&str> = dump.split(',').collect();
+    inputs.push(binary);
+
+    let entry = checkpoint::Entry {
+        hash: checkpoint::input_hash(&inputs)?,
+        dump: dump.to_string(),
+        binary: binary.to_string(),
+    };
+
+    Ok((checkpoint, entry))
+}
+
+/// Checks that an input file exists and can be read.
+fn check_input(kind: &str, path: &str) -> Result<(), String> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => return Err(format!("{} is not a file: {}", kind, path)),
+        Err(_e) => return Err(format!("{} file not found: {}", kind, path)),
+    }
+
+    match File::open(path) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(format!("{} file is not readable: {} ({})", kind, path, e)),
+    }
+}
+
+/// Verifies a dump against its binary and returns the process exit code.
+fn run_verify_dump(path_to_dump: &str, path_to_binary: &str) -> i32 {
+    let _span = info_span!("verify", dump = path_to_dump, binary = path_to_binary).entered();
+
+    let dump = match dumper::yaml::load(path_to_dump) {
+        Ok(dump) => dump,
+        Err(e) => {
+            error!("{}", e);
+            return 1;
+        }
+    };
+
+    let report = match verify::verify_dump(&dump, path_to_binary) {
+        Ok(report) => report,
+        Err(e) => {
+            error!("{}", e);
+            return 1;
+        }
+    };
+
+    for inconsistency in &report.inconsistencies {
+        error!(kind = ?inconsistency.kind, "{}", inconsistency.message);
+    }
+
+    if !report.is_consistent() {
+        error!(
+            "Dump is inconsistent: {} problems in {} bytes and {} functions.",
+            report.inconsistencies.len(),
+            report.bytes_checked,
+            report.functions_checked
+        );
+        return 1;
+    }
+
+    info!(
+        "Dump is consistent: {} bytes and {} functions checked.",
+        report.bytes_checked, report.functions_checked
+    );
+    0
+}
+
+/// Parses the binary, WebAssembly modules and binaries goblin can not parse are rejected.
+fn parse_binary(buffer: &[u8]) -> Result<Object<'_>, String> {
+    // Guard: goblin does not parse WebAssembly modules
+    if buffer.starts_with(WASM_MAGIC) {
+        return Err(
+            "WebAssembly is not supported. Only PE and ELF binaries are supported.".to_string(),
+        );
+    }
+
+    Object::parse(buffer).map_err(|e| format!("Could not parse binary: {}", e))
+}
+
+/// Determines the pipeline of the binary, all other formats are rejected with the reason.
+fn binary_format(buffer: &[u8]) -> Result<BINARY_FORMAT, String> {
+    match parse_binary(buffer)? {
+        Object::Elf(_) => Ok(BINARY_FORMAT::ELF),
+        Object::PE(_) => Ok(BINARY_FORMAT::PE),
+        Object::Mach(mach) => Err(format!(
+            "Mach-O binaries are not supported. Only PE and ELF binaries are supported.{}",
+            describe_slices(&mach)
+        )),
+        _ => Err("Binary not supported. Only PE and ELF binaries are supported.".to_string()),
+    }
+}
+
+/// Lists the architecture slices of a universal (fat) Mach-O binary, empty for a single
+/// architecture. Selecting a slice is blocked on a reader of Mach-O symbols and sections.
+fn describe_slices(mach: &Mach) -> String {
+    let multi = match mach {
+        Mach::Fat(multi) => multi,
+        Mach::Binary(_) => return String::new(),
+    };
+
+    let names: Vec<String> = multi
+        .iter_arches()
+        .filter_map(Result::ok)
+        .map(|arch| {
+            cputype::get_arch_name_from_types(arch.cputype(), arch.cpusubtype())
+                .map(String::from)
+                .unwrap_or_else(|| format!("cputype {:#x}", arch.cputype()))
+        })
+        .collect();
+
+    format!(
+        " The universal binary contains the slices: {}. Selecting a slice (--arch-slice) is \
+         not available until Mach-O binaries are supported.",
+        names.join(", ")
+    )
+}
+
+/// Processes the binary and the dump of a bundle in memory and returns the process exit code.
+fn run_bundle(path: &str, config: config::Config) -> i32 {
+    let _span = info_span!("bundle", path).entered();
+
+    let bundle = match bundle::read(path) {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            error!("{}", e);
+            return 1;
+        }
+    };
+
+    info!(
+        "Bundle holds the binary {} and the dump {}.",
+        bundle.binary_name, bundle.dump_name
+    );
+
+    let result = binary_format(&bundle.binary).and_then(|format| match format {
+        BINARY_FORMAT::ELF => b2g::elf::ELF::from_buffer(&bundle.dump, &bundle.binary, config)
+            .map(|mut elf| {
+                elf.file_name = bundle.file_stem();
+                elf.process();
+            })
+            .map_err(String::from),
+        BINARY_FORMAT::PE => b2g::pe::PE::from_buffer(&bundle.dump, &bundle.binary, config)
+            .map(|mut pe| {
+                pe.file_name = bundle.file_stem();
+                pe.process();
+            })
+            .map_err(String::from),
+    });
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("{}", e);
+            1
+        }
+    }
+}
+
+/// Runs the built-in fixtures, prints PASS/FAIL per fixture and returns the exit code.
+fn run_selftest() -> i32 {
+    let outcomes = selftest::run();
+
+    for outcome in &outcomes {
+        println!(
+            "{} {}: {} functions, {} instructions, {:.2}% identified",
+            if outcome.passed() { "PASS" } else { "FAIL" },
+            outcome.name,
+            outcome.functions,
+            outcome.instructions,
+            outcome.identified
+        );
+
+        for failure in &outcome.failures {
+            println!("    {}", failure);
+        }
+    }
+
+    if outcomes.iter().all(selftest::Outcome::passed) {
+        0
+    } else {
+        1
+    }
+}
+
+/// Prints the instructions and byte ranges of a dump matching a flag expression, returns the exit
+/// code.
+fn run_query(text: &str, path: &str) -> i32 {
+    let _span = info_span!("query", dump = path).entered();
+
+    let expression = match query::parse(text) {
+        Ok(expression) => expression,
+        Err(e) => {
+            error!("{}", e);
+            return 1;
+        }
+    };
+
+    let dump = match dumper::yaml::load(path) {
+        Ok(dump) => dump,
+        Err(e) => {
+            error!("{}", e);
+            return 1;
+        }
+    };
+
+    let matches = query::evaluate(&dump, &expression);
+
+    info!(
+        "{} instructions and {} byte ranges match.",
+        matches.instructions.len(),
+        matches.ranges.len()
+    );
+
+    for instruction in &matches.instructions {
+        println!(
+            "0x{:x}  {} {}",
+            instruction.address, instruction.mnemonic, instruction.operand
+        );
+    }
+
+    for range in &matches.ranges {
+        println!(
+            "0x{:x}-0x{:x} {:>8} bytes",
+            range.start, range.end, range.size
+        );
+    }
+
+    0
+}
+
+/// Prints the hole patterns of several dumps ranked by frequency and returns the exit code.
+fn run_hole_report(paths: &[&str]) -> i32 {
+    let mut all_holes = Vec::new();
+
+    for path in paths {
+        let _span = info_span!("holes", dump = path).entered();
+
+        let holes = match dumper::yaml::load(path).and_then(|dump| holes::collect(&dump)) {
+            Ok(holes) => holes,
+            Err(e) => {
+                error!("{}", e);
+                return 1;
+            }
+        };
+
+        info!("Found {} holes.", holes.len());
+        all_holes.push((path.to_string(), holes));
+    }
+
+    println!(
+        "{:<14} {:>8} {:>10} {:>8}  Examples",
+        "Pattern", "Holes", "Bytes", "Entropy"
+    );
+
+    for summary in holes::aggregate(&all_holes) {
+        let examples: Vec<String> = summary
+            .examples
+            .iter()
+            .map(|(dump, addresses)| {
+                let addresses: Vec<String> =
+                    addresses.iter().map(|a| format!("0x{:x}", a)).collect();
+                format!("{}: {}", dump, addresses.join(", "))
+            })
+            .collect();
+
+        println!(
+            "{:<14} {:>8} {:>10} {:>8.2}  {}",
+            format!("{:?}", summary.pattern),
+            summary.holes,
+            summary.bytes,
+            summary.entropy,
+            examples.join("; ")
+        );
+    }
+
+    0
+}
+
+/// Prints the net coverage gain of a run against a baseline and the regions responsible, returns
+/// the exit code.
+fn run_coverage_diff(path_to_baseline: &str, path_to_current: &str) -> i32 {
+    let _span = info_span!(
+        "coverage",
+        baseline = path_to_baseline,
+        current = path_to_current
+    )
+    .entered();
+
+    let result = dumper::yaml::load(path_to_baseline).and_then(|baseline| {
+        dumper::yaml::load(path_to_current).and_then(|current| coverage::diff(&baseline, &current))
+    });
+
+    let diff = match result {
+        Ok(diff) => diff,
+        Err(e) => {
+            error!("{}", e);
+            return 1;
+        }
+    };
+
+    println!(
+        "Identified: {} bytes (baseline) -> {} bytes (current), net gain {:+} bytes ({} gained, {} lost)",
+        diff.baseline_identified,
+        diff.current_identified,
+        diff.net_gain(),
+        diff.bytes(coverage::CHANGE::GAINED),
+        diff.bytes(coverage::CHANGE::LOST)
+    );
+
+    for region in &diff.regions {
+        println!(
+            "{:<6} 0x{:x}-0x{:x} {:>8} bytes  {:?}",
+            format!("{:?}", region.change),
+            region.start,
+            region.end,
+            region.size,
+            region.flags
+        );
+    }
+
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_binary_rejects_webassembly() {
+        let module = b"\0asm\x01\0\0\0";
+
+        assert!(parse_binary(module).unwrap_err().starts_with("WebAssembly"));
+    }
+
+    #[test]
+    fn parse_binary_rejects_truncated_binaries() {
+        let header = b"\x7fELF\x02\x01";
+
+        assert!(parse_binary(header)
+            .unwrap_err()
+            .starts_with("Could not parse binary"));
+    }
+
+    /// Universal binary with an x86_64 and an arm64 slice, the slices themselves are empty.
+    fn fat_binary() -> Vec<u8> {
+        let mut buffer = Vec::new();
+        let fields: [u32; 12] = [
+            0xCAFE_BABE, // FAT_MAGIC
+            2,
+            0x0100_0007, // CPU_TYPE_X86_64
+            3,           // CPU_SUBTYPE_X86_64_ALL
+            0x1000,
+            0x20,
+            12,
+            0x0100_000C, // CPU_TYPE_ARM64
+            0,           // CPU_SUBTYPE_ARM64_ALL
+            0x2000,
+            0x20,
+            14,
+        ];
+
+        for field in fields.iter() {
+            buffer.extend(field.to_be_bytes());
+        }
+        buffer.resize(0x2020, 0);
+        buffer
+    }
+
+    #[test]
+    fn describe_slices_of_a_universal_binary() {
+        let buffer = fat_binary();
+
+        let mach = match parse_binary(&buffer) {
+            Ok(Object::Mach(mach)) => mach,
+            _ => panic!("Expected a Mach-O binary."),
+        };
+
+        assert_eq!(
+            describe_slices(&mach),
+            " The universal binary contains the slices: x86_64, arm64. Selecting a slice \
+             (--arch-slice) is not available until Mach-O binaries are supported."
+        );
+    }
+
+    #[test]
+    fn describe_slices_of_a_single_architecture_is_empty() {
+        // mach_header_64 of an x86_64 executable without load commands
+        let mut buffer = Vec::new();
+        for field in [0xFEED_FACFu32, 0x0100_0007, 3, 2, 0, 0, 0, 0].iter() {
+            buffer.extend(field.to_le_bytes());
+        }
+
+        let mach = match parse_binary(&buffer) {
+            Ok(Object::Mach(mach)) => mach,
+            _ => panic!("Expected a Mach-O binary."),
+        };
+
+        assert!(describe_slices(&mach).is_empty());
+    }
+
+    #[test]
+    fn binary_format_rejects_universal_binaries_with_their_slices() {
+        let error = binary_format(&fat_binary()).unwrap_err();
+
+        assert!(error.starts_with("Mach-O binaries are not supported."));
+        assert!(error.contains("the slices: x86_64, arm64."));
+    }
+
+    #[test]
+    fn binary_format_of_pe_and_elf() {
+        let pe = include_bytes!("../fixtures/selftest/pe.exe");
+        let elf = include_bytes!("../fixtures/selftest/elf");
+
+        assert_eq!(binary_format(pe), Ok(BINARY_FORMAT::PE));
+        assert_eq!(binary_format(elf), Ok(BINARY_FORMAT::ELF));
+        assert!(binary_format(b"\0asm\x01\0\0\0").is_err());
+    }
+
+    #[test]
+    fn parse_binary_accepts_pe_and_elf() {
+        let pe = include_bytes!("../fixtures/selftest/pe.exe");
+        let elf = include_bytes!("../fixtures/selftest/elf");
+
+        assert!(matches!(parse_binary(pe), Ok(Object::PE(_))));
+        assert!(matches!(parse_binary(elf), Ok(Object::Elf(_))));
+    }
+}
@@ -1,32 +1,183 @@
 pub mod yaml {
+    use std::collections::HashSet;
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+    use tracing::debug;
+    use yaml_rust::Yaml;
+
+    /// Reads a YAML dump line by line and copies the blocks which `keep` accepts. `keep` is
+    /// called with the indentation and the trimmed line of every line which is not part of a
+    /// dropped block. Dropping a line drops every line below it, i.e. all lines indented deeper
+    /// and, for a key, the items of a sequence at its own indentation. The memory needed is
+    /// bounded by the copied blocks instead of the whole dump.
+    pub fn filter_blocks<R: BufRead>(
+        reader: R,
+        mut keep: impl FnMut(usize, &str) -> bool,
+    ) -> Result<String, &'static str> {
+        let mut filtered = String::new();
+        let mut dropped: Option<(usize, bool)> = None;
+
+        // Symbol names are not necessarily valid UTF-8, invalid sequences are replaced
+        for line in reader.split(b'\n') {
+            let line = match line {
+                Ok(line) => String::from_utf8_lossy(&line)
+                    .trim_end_matches('\r')
+                    .to_string(),
+                Err(_e) => return Err("Could not read file!"),
+            };
+
+            let trimmed = line.trim_start();
+            let indentation = line.len() - trimmed.len();
+
+            // Blank lines and comments do not end a block
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            if let Some((level, is_key)) = dropped {
+                let below = indentation > level
+                    || (indentation == level && is_key && trimmed.starts_with("- "));
+
+                if below {
+                    continue;
+                }
+
+                dropped = None;
+            }
+
+            if keep(indentation, trimmed) {
+                filtered.push_str(&line);
+                filtered.push('\n');
+            } else {
+                dropped = Some((indentation, !trimmed.starts_with("- ")));
+            }
+        }
+
+        Ok(filtered)
+    }
+
+    /// Opens a YAML dump and runs `filter` on its lines.
+    fn read_filtered(
+        path: &str,
+        filter: impl Fn(BufReader<File>) -> Result<String, &'static str>,
+    ) -> Result<String, &'static str> {
+        match File::open(path) {
+            Ok(f) => filter(BufReader::new(f)),
+            Err(_e) => Err("Could not find file!"),
+        }
+    }
+
+    /// Text of a scalar (e.g. a symbol name). YAML reads unquoted names which look like numbers or
+    /// booleans (e.g. `1234`) as such, they are converted back.
+    pub fn scalar(value: &Yaml) -> Option<String> {
+        match value {
+            Yaml::String(s) | Yaml::Real(s) => Some(s.clone()),
+            Yaml::Integer(i) => Some(i.to_string()),
+            Yaml::Boolean(b) => Some(b.to_string()),
+            _ => None,
+        }
+    }
+
+    /// Name of a top-level key (or the document marker itself).
+    fn top_level_key(line: &str) -> &str {
+        line.split(':').next().unwrap_or(line).trim_end()
+    }
+
+    /// Appends the symbols of a lower priority source. Symbols located at a (segment, offset)
+    /// which an earlier source already provides are dropped.
+    pub fn merge_by_location<T>(
+        merged: &mut Vec<T>,
+        symbols: Vec<T>,
+        location: impl Fn(&T) -> (u8, u64),
+    ) {
+        let known: HashSet<(u8, u64)> = merged.iter().map(&location).collect();
+        let count = symbols.len();
+
+        merged.extend(
+            symbols
+                .into_iter()
+                .filter(|s| !known.contains(&location(s))),
+        );
+
+        debug!(
+            "Merged {} symbols, {} were already known.",
+            count,
+            count + known.len() - merged.len()
+        );
+    }
+
     pub mod pdb {
 
-        use log::{debug, error, info, warn};
-        use std::fs::File;
-        use std::io::prelude::*;
+        use std::collections::HashMap;
+        use std::io::BufRead;
+        use tracing::debug;
 
         use crate::groundtruth;
         use yaml_rust::{Yaml, YamlLoader};
 
+        /// Name of the function created for a thunk (S_THUNK32 records have no name).
+        const THUNK_NAME: &str = "<Thunk>";
+
+        /// Symbol records of a module which are parsed. All other records are filtered out.
+        const RECORD_KINDS: [&str; 9] = [
+            "S_GPROC32",
+            "S_LPROC32",
+            "S_FRAMEPROC",
+            "S_PUB32",
+            "S_THUNK32",
+            "S_LABEL32",
+            "S_LDATA32",
+            "S_GDATA32",
+            "S_COMPILE3",
+        ];
+
         pub fn load_pdb(path: &str) -> Result<groundtruth::PDB, &'static str> {
-            let mut f = match File::open(path) {
-                Ok(f) => f,
-                Err(_e) => {
-                    return Err("[-] Could not find file!");
+            load_pdb_from_str(&read_pdb(path)?)
+        }
+
+        /// Reads a YAML dump and keeps only the parts needed by [`load_pdb_from_str`], see
+        /// [`filter_pdb`]. The whole dump is never held in memory.
+        pub fn read_pdb(path: &str) -> Result<String, &'static str> {
+            super::read_filtered(path, filter_pdb)
+        }
+
+        /// Keeps the `DbiStream` without the symbol records of other kinds than
+        /// [`RECORD_KINDS`], the line tables (`Subsections`) and the `SourceFiles`. Of the
+        /// `TpiStream` only its key is kept, all other streams are dropped.
+        pub fn filter_pdb<R: BufRead>(reader: R) -> Result<String, &'static str> {
+            let mut stream = String::new();
+
+            super::filter_blocks(reader, |indentation, line| {
+                if indentation == 0 {
+                    stream = super::top_level_key(line).to_string();
+
+                    return matches!(stream.as_str(), "---" | "..." | "TpiStream" | "DbiStream");
                 }
-            };
 
-            let mut contents = String::new();
+                if stream != "DbiStream" {
+                    return false;
+                }
+
+                if let Some(kind) = line.strip_prefix("- Kind:") {
+                    return RECORD_KINDS.contains(&kind.trim());
+                }
+
+                !line.starts_with("Subsections:") && !line.starts_with("SourceFiles:")
+            })
+        }
+
+        /// String-based variant of [`load_pdb`] taking the YAML dump itself.
+        pub fn load_pdb_from_str(contents: &str) -> Result<groundtruth::PDB, &'static str> {
+            // Only build the document of the symbols actually needed
+            let contents = filter_pdb(contents.as_bytes())?;
 
-            match f.read_to_string(&mut contents) {
-                Ok(_f) => {}
+            let docs = match YamlLoader::load_from_str(&contents) {
+                Ok(docs) => docs,
                 Err(_e) => {
-                    return Err("[-] Could not read file!");
+                    return Err("Could not parse YAML!");
                 }
             };
 
-            let docs = YamlLoader::load_from_str(contents.as_str()).unwrap();
-
             let doc = &docs[0];
 
             // Guard: Check if TpiStream exists
@@ -47,11 +198,15 @@ pub mod yaml {
             let mut labels: Vec<groundtruth::Label> = Vec::new();
             let mut data: Vec<groundtruth::Data> = Vec::new();
             let mut thunks: Vec<groundtruth::Thunk> = Vec::new();
+            let mut compilers: Vec<groundtruth::Compiler> = Vec::new();
+            let mut skipped_symbols = 0;
             let mut _types: Vec<groundtruth::Type> = Vec::new();
 
             // Collect all types
 
-            for record in tpi_stream["Records"].as_vec().unwrap() {
+            // Note: The records are dropped by the filter
+            for record in tpi_stream["Records"].as_vec().into_iter().flatten() {
+                #[allow(clippy::single_match)]
                 match record["Kind"].as_str().unwrap() {
                     "LF_STRUCTURE" => {}
                     _ => {}
@@ -65,39 +220,65 @@ pub mod yaml {
                     continue;
                 }
 
-                for record in module["Modi"]["Records"].as_vec().unwrap() {
-                    match record["Kind"].as_str().unwrap() {
-                        "S_GPROC32" => {
-                            functions.push(parse_function(&record));
-                        }
-                        "S_LPROC32" => {
-                            functions.push(parse_function(&record));
-                        }
-                        "S_PUB32" => {
-                            functions.push(parse_function(&record));
-                        }
+                // Procedure an S_FRAMEPROC belongs to, the record directly follows it
+                let mut procedure: Option<usize> = None;
+
+                // Note: A module may have no records left after filtering
+                for record in module["Modi"]["Records"].as_vec().into_iter().flatten() {
+                    let kind = record["Kind"].as_str().unwrap();
+
+                    if kind != "S_FRAMEPROC" {
+                        procedure = None;
+                    }
+
+                    match kind {
+                        "S_GPROC32" | "S_LPROC32" | "S_PUB32" => match parse_function(record) {
+                            Some(function) => {
+                                if kind != "S_PUB32" {
+                                    procedure = Some(functions.len());
+                                }
+
+                                functions.push(function);
+                            }
+                            None => skipped_symbols += 1,
+                        },
+                        "S_FRAMEPROC" => match procedure.take() {
+                            Some(index) => functions[index].frame = Some(parse_frame(record)),
+                            None => debug!("S_FRAMEPROC without a preceding procedure"),
+                        },
                         "S_THUNK32" => {
-                            let thunk = parse_thunk(&record);
+                            let thunk = parse_thunk(record);
 
                             functions.push(groundtruth::Function {
-                                name: "<Thunk>".to_string(),
+                                name: THUNK_NAME.to_string(),
+                                demangled_name: None,
                                 offset: thunk.offset,
                                 segment: thunk.segment,
                                 size: thunk.size,
                                 labels: Vec::new(),
                                 data: Vec::new(),
+                                frame: None,
+                                thumb: false,
                             });
 
                             thunks.push(thunk);
                         }
                         "S_LABEL32" => {
-                            labels.push(parse_label(&record));
+                            labels.push(parse_label(record));
                         }
                         "S_LDATA32" => {
-                            data.push(parse_data(&record));
+                            data.push(parse_data(record));
                         }
                         "S_GDATA32" => {
-                            data.push(parse_data(&record));
+                            data.push(parse_data(record));
+                        }
+                        "S_COMPILE3" => {
+                            let compiler = parse_compiler(record);
+
+                            // Every module carries a record, most of them are identical
+                            if !compilers.contains(&compiler) {
+                                compilers.push(compiler);
+                            }
                         }
                         _ => {}
                     }
@@ -109,18 +290,20 @@ pub mod yaml {
             debug!("Labels: {}", labels.len());
             debug!("Data: {}", data.len());
             debug!("Thunks: {}", thunks.len());
+            debug!("Compilers: {}", compilers.len());
 
             // Sort symbols by address
-            functions.sort_by(|a, b| a.offset.cmp(&b.offset));
-            data.sort_by(|a, b| a.offset.cmp(&b.offset));
-            labels.sort_by(|a, b| a.offset.cmp(&b.offset));
-            thunks.sort_by(|a, b| a.offset.cmp(&b.offset));
+            functions.sort_by_key(|a| a.offset);
+            data.sort_by_key(|a| (a.offset, a.segment));
+            labels.sort_by_key(|a| a.offset);
+            thunks.sort_by_key(|a| a.offset);
 
             // Remove duplicates
             functions.dedup();
-            data.dedup();
+            dedup_data(&mut data);
             labels.dedup();
             thunks.dedup();
+            reconcile_thunks(&mut functions, &mut thunks);
 
             // Collect meta information
             let architecture = match dbi_stream["MachineType"].as_str().unwrap() {
@@ -136,46 +319,219 @@ pub mod yaml {
             };
 
             Ok(groundtruth::PDB {
-                architecture,
+                architecture: groundtruth::ArchInfo::from(architecture),
                 image_base,
                 functions,
                 thunks,
                 data,
                 labels,
+                compilers,
+                skipped_symbols,
             })
         }
 
+        /// Loads several YAML dumps and merges their symbols. Earlier dumps take priority if two
+        /// dumps provide a symbol at the same location.
+        pub fn load_pdbs_from_strs(contents: &[String]) -> Result<groundtruth::PDB, &'static str> {
+            let mut pdbs = contents.iter().map(|c| load_pdb_from_str(c));
+
+            let mut merged = match pdbs.next() {
+                Some(pdb) => pdb?,
+                None => return Err("No dump given!"),
+            };
+
+            for pdb in pdbs {
+                let pdb = pdb?;
+
+                super::merge_by_location(&mut merged.functions, pdb.functions, |f| {
+                    (f.segment, f.offset)
+                });
+                super::merge_by_location(&mut merged.data, pdb.data, |d| (d.segment, d.offset));
+                super::merge_by_location(&mut merged.labels, pdb.labels, |l| (l.segment, l.offset));
+                super::merge_by_location(&mut merged.thunks, pdb.thunks, |t| (t.segment, t.offset));
+                merged.skipped_symbols += pdb.skipped_symbols;
+
+                for compiler in pdb.compilers {
+                    if !merged.compilers.contains(&compiler) {
+                        merged.compilers.push(compiler);
+                    }
+                }
+            }
+
+            // Restore the order of a single dump (stable, so priority is kept among equal offsets)
+            merged.functions.sort_by_key(|a| a.offset);
+            merged.data.sort_by_key(|a| (a.offset, a.segment));
+            merged.labels.sort_by_key(|a| a.offset);
+            merged.thunks.sort_by_key(|a| a.offset);
+            reconcile_thunks(&mut merged.functions, &mut merged.thunks);
+
+            Ok(merged)
+        }
+
         /// Add.
-        fn parse_function(record: &Yaml) -> groundtruth::Function {
-            groundtruth::Function {
-                name: record["ProcSym"]["DisplayName"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
-                offset: record["ProcSym"]["Offset"].as_i64().unwrap() as u64,
-                segment: record["ProcSym"]["Segment"].as_i64().unwrap() as u8,
-                size: record["ProcSym"]["CodeSize"].as_i64().unwrap() as u64,
+        fn parse_function(record: &Yaml) -> Option<groundtruth::Function> {
+            let name = super::scalar(&record["ProcSym"]["DisplayName"]).unwrap_or_default();
+
+            let size = match record["ProcSym"]["CodeSize"].as_i64() {
+                Some(size) => size,
+                None => {
+                    debug!("Function {} has no size", name);
+                    return None;
+                }
+            };
+
+            // Unrelocated symbols carry a size but no location
+            let offset = match record["ProcSym"]["Offset"].as_i64() {
+                Some(offset) => offset,
+                None => {
+                    debug!("Function {} has no offset", name);
+                    return None;
+                }
+            };
+
+            let segment = match record["ProcSym"]["Segment"].as_i64() {
+                Some(segment) => segment,
+                None => {
+                    debug!("Function {} has no segment", name);
+                    return None;
+                }
+            };
+
+            Some(groundtruth::Function {
+                name: name.to_string(),
+                demangled_name: None,
+                offset: offset as u64,
+                segment: segment as u8,
+                size: size as u64,
                 labels: Vec::new(),
                 data: Vec::new(),
+                frame: None,
+                thumb: false,
+            })
+        }
+
+        /// Sizes missing in the record are taken as zero.
+        fn parse_frame(record: &Yaml) -> groundtruth::Frame {
+            let symbol = &record["FrameProcSym"];
+            let bytes = |key: &str| symbol[key].as_i64().unwrap_or(0) as u64;
+
+            groundtruth::Frame {
+                size: bytes("TotalFrameBytes"),
+                padding: bytes("PaddingFrameBytes"),
+                callee_saved: bytes("BytesOfCalleeSavedRegisters"),
+                flags: symbol["Flags"]
+                    .as_vec()
+                    .map(|flags| {
+                        flags
+                            .iter()
+                            .filter_map(|f| f.as_str())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }
+        }
+
+        /// Versions are formatted as `major.minor.build.qfe`.
+        fn parse_compiler(record: &Yaml) -> groundtruth::Compiler {
+            let symbol = &record["Compile3Sym"];
+            let version = |prefix: &str| {
+                format!(
+                    "{}.{}.{}.{}",
+                    symbol[format!("{}Major", prefix).as_str()]
+                        .as_i64()
+                        .unwrap_or(0),
+                    symbol[format!("{}Minor", prefix).as_str()]
+                        .as_i64()
+                        .unwrap_or(0),
+                    symbol[format!("{}Build", prefix).as_str()]
+                        .as_i64()
+                        .unwrap_or(0),
+                    symbol[format!("{}QFE", prefix).as_str()]
+                        .as_i64()
+                        .unwrap_or(0)
+                )
+            };
+
+            groundtruth::Compiler {
+                name: symbol["Version"].as_str().unwrap_or("").to_string(),
+                frontend_version: version("Frontend"),
+                backend_version: version("Backend"),
+                machine: symbol["Machine"].as_str().unwrap_or("").to_string(),
+                flags: symbol["Flags"]
+                    .as_vec()
+                    .map(|flags| {
+                        flags
+                            .iter()
+                            .filter_map(|f| f.as_str())
+                            .map(String::from)
+                            .collect()
+                    })
+                    .unwrap_or_default(),
             }
         }
 
+        /// Merges data symbols sharing the same segment and offset. The merged symbol keeps the
+        /// first real name (not empty or "PLACEHOLDER") and the largest size.
+        fn dedup_data(data: &mut Vec<groundtruth::Data>) {
+            let is_placeholder = |name: &str| name.is_empty() || name == "PLACEHOLDER";
+
+            data.dedup_by(|duplicate, kept| {
+                if duplicate.segment != kept.segment || duplicate.offset != kept.offset {
+                    return false;
+                }
+
+                if is_placeholder(&kept.name) && !is_placeholder(&duplicate.name) {
+                    kept.name = duplicate.name.clone();
+                }
+
+                kept.size = kept.size.max(duplicate.size);
+
+                true
+            });
+        }
+
         /// Add.
         fn parse_thunk(record: &Yaml) -> groundtruth::Thunk {
             groundtruth::Thunk {
                 offset: record["Thunk32Sym"]["Off"].as_i64().unwrap() as u64,
                 segment: record["Thunk32Sym"]["Seg"].as_i64().unwrap() as u8,
                 size: record["Thunk32Sym"]["Len"].as_i64().unwrap() as u64,
+                function: None,
+            }
+        }
+
+        /// Drops the `<Thunk>` function of every thunk located at the same offset as a named
+        /// function (e.g. an incremental linking thunk with its own S_GPROC32), so a single
+        /// function starts there. The thunk records the name of the function instead.
+        fn reconcile_thunks(
+            functions: &mut Vec<groundtruth::Function>,
+            thunks: &mut [groundtruth::Thunk],
+        ) {
+            let named: HashMap<(u8, u64), String> = functions
+                .iter()
+                .filter(|f| f.name != THUNK_NAME)
+                .map(|f| ((f.segment, f.offset), f.name.clone()))
+                .collect();
+
+            let count = functions.len();
+            functions
+                .retain(|f| f.name != THUNK_NAME || !named.contains_key(&(f.segment, f.offset)));
+
+            for thunk in thunks {
+                thunk.function = named.get(&(thunk.segment, thunk.offset)).cloned();
             }
+
+            debug!(
+                "{} thunks share their offset with a named function.",
+                count - functions.len()
+            );
         }
 
         /// Add.
         fn parse_label(record: &Yaml) -> groundtruth::Label {
             groundtruth::Label {
-                name: record["LabelSym"]["DisplayName"]
-                    .as_str()
-                    .unwrap()
-                    .to_string(),
+                name: super::scalar(&record["LabelSym"]["DisplayName"]).unwrap_or_default(),
                 offset: record["LabelSym"]["Offset"].as_i64().unwrap() as u64,
                 segment: record["LabelSym"]["Segment"].as_i64().unwrap() as u8,
             }
@@ -183,25 +539,265 @@ pub mod yaml {
 
         /// Add.
         fn parse_data(record: &Yaml) -> groundtruth::Data {
-            let name = match record["DataSym"]["DisplayName"].as_str() {
-                Some(name) => name,
-                None => "PLACEHOLDER",
-            };
+            let name = super::scalar(&record["DataSym"]["DisplayName"])
+                .unwrap_or_else(|| "PLACEHOLDER".to_string());
 
             groundtruth::Data {
                 name: name.to_string(),
                 offset: record["DataSym"]["Offset"].as_i64().unwrap() as u64,
                 segment: record["DataSym"]["Segment"].as_i64().unwrap() as u8,
                 size: 0,
+                bytes: None,
+            }
+        }
+
+        #[cfg(test)]
+        mod tests {
+            use super::*;
+
+            /// Procedures with a complete location, without offset, without segment and without
+            /// size.
+            const INCOMPLETE_PROCEDURES: &str = r#"---
+TpiStream:
+  Records: []
+DbiStream:
+  MachineType: x64
+  Modules:
+    - Module: 'p.obj'
+      Modi:
+        Signature: 4
+        Records:
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 10
+              Offset: 0
+              Segment: 1
+              DisplayName: located
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 10
+              Segment: 1
+              DisplayName: no_offset
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 10
+              Offset: 16
+              DisplayName: no_segment
+          - Kind: S_GPROC32
+            ProcSym:
+              Offset: 32
+              Segment: 1
+              DisplayName: no_size
+"#;
+
+            /// Dump of the main binary: `main`, `shared`, a label and a global.
+            const MAIN_DUMP: &str = r#"---
+TpiStream:
+  Records: []
+DbiStream:
+  MachineType: x64
+  Modules:
+    - Module: 'main.obj'
+      Modi:
+        Signature: 4
+        Records:
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 16
+              Offset: 0
+              Segment: 1
+              DisplayName: main
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 16
+              Offset: 16
+              Segment: 1
+              DisplayName: shared
+          - Kind: S_LABEL32
+            LabelSym:
+              Offset: 4
+              Segment: 1
+              DisplayName: mainLoop
+          - Kind: S_GDATA32
+            DataSym:
+              Offset: 0
+              Segment: 2
+              DisplayName: gTable
+"#;
+
+            /// Dump of a static library overlapping [`MAIN_DUMP`]: `main` again, another name at
+            /// the location of `shared`, the label and the global and one new symbol of each kind.
+            const LIBRARY_DUMP: &str = r#"---
+TpiStream:
+  Records: []
+DbiStream:
+  MachineType: x64
+  Modules:
+    - Module: 'lib.obj'
+      Modi:
+        Signature: 4
+        Records:
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 16
+              Offset: 0
+              Segment: 1
+              DisplayName: main
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 8
+              Offset: 16
+              Segment: 1
+              DisplayName: lib_shared
+          - Kind: S_GPROC32
+            ProcSym:
+              CodeSize: 8
+              Offset: 32
+              Segment: 1
+              DisplayName: helper
+          - Kind: S_LABEL32
+            LabelSym:
+              Offset: 4
+              Segment: 1
+              DisplayName: libLoop
+          - Kind: S_LABEL32
+            LabelSym:
+              Offset: 36
+              Segment: 1
+              DisplayName: helperLoop
+          - Kind: S_GDATA32
+            DataSym:
+              Offset: 0
+              Segment: 2
+              DisplayName: gLibTable
+          - Kind: S_GDATA32
+            DataSym:
+              Offset: 8
+              Segment: 2
+              DisplayName: gCounter
+"#;
+
+            #[test]
+            fn merged_dumps_keep_every_location_once_and_the_earlier_symbol() {
+                let dumps = [MAIN_DUMP.to_string(), LIBRARY_DUMP.to_string()];
+                let pdb = load_pdbs_from_strs(&dumps).unwrap();
+
+                let functions: Vec<(&str, u64, u64)> = pdb
+                    .functions
+                    .iter()
+                    .map(|f| (f.name.as_str(), f.offset, f.size))
+                    .collect();
+                assert_eq!(
+                    functions,
+                    vec![("main", 0, 16), ("shared", 16, 16), ("helper", 32, 8)]
+                );
+
+                let labels: Vec<&str> = pdb.labels.iter().map(|l| l.name.as_str()).collect();
+                assert_eq!(labels, vec!["mainLoop", "helperLoop"]);
+
+                let data: Vec<&str> = pdb.data.iter().map(|d| d.name.as_str()).collect();
+                assert_eq!(data, vec!["gTable", "gCounter"]);
+            }
+
+            #[test]
+            fn names_with_invalid_utf8_bytes_are_parsed() {
+                // `shared` with two bytes which are no UTF-8 sequence
+                let (before, after) = MAIN_DUMP.split_once("shared").unwrap();
+                let mut dump = before.as_bytes().to_vec();
+                dump.extend(b"sh\xFF\xFEared");
+                dump.extend(after.as_bytes());
+
+                let pdb = load_pdb_from_str(&filter_pdb(&dump[..]).unwrap()).unwrap();
+
+                let names: Vec<&str> = pdb.functions.iter().map(|f| f.name.as_str()).collect();
+                assert_eq!(names, vec!["main", "sh\u{FFFD}\u{FFFD}ared"]);
+            }
+
+            #[test]
+            fn the_first_dump_wins_conflicts() {
+                let dumps = [LIBRARY_DUMP.to_string(), MAIN_DUMP.to_string()];
+                let pdb = load_pdbs_from_strs(&dumps).unwrap();
+
+                let functions: Vec<&str> = pdb.functions.iter().map(|f| f.name.as_str()).collect();
+                assert_eq!(functions, vec!["main", "lib_shared", "helper"]);
+
+                let labels: Vec<&str> = pdb.labels.iter().map(|l| l.name.as_str()).collect();
+                assert_eq!(labels, vec!["libLoop", "helperLoop"]);
+
+                let data: Vec<&str> = pdb.data.iter().map(|d| d.name.as_str()).collect();
+                assert_eq!(data, vec!["gLibTable", "gCounter"]);
+            }
+
+            #[test]
+            fn procedures_without_location_are_skipped() {
+                let pdb = load_pdb_from_str(INCOMPLETE_PROCEDURES).unwrap();
+
+                let names: Vec<&str> = pdb.functions.iter().map(|f| f.name.as_str()).collect();
+                assert_eq!(names, vec!["located"]);
+                assert_eq!(pdb.skipped_symbols, 3);
+            }
+
+            fn data(name: &str, segment: u8, offset: u64, size: u64) -> groundtruth::Data {
+                groundtruth::Data {
+                    name: name.to_string(),
+                    offset,
+                    segment,
+                    size,
+                    bytes: None,
+                }
+            }
+
+            #[test]
+            fn data_at_the_same_location_is_merged() {
+                let mut symbols = vec![
+                    data("PLACEHOLDER", 2, 0x10, 4),
+                    data("table", 2, 0x10, 0),
+                    data("", 2, 0x10, 8),
+                    data("other", 3, 0x10, 4),
+                ];
+
+                dedup_data(&mut symbols);
+
+                assert_eq!(symbols.len(), 2);
+                assert_eq!((symbols[0].name.as_str(), symbols[0].size), ("table", 8));
+                assert_eq!((symbols[1].name.as_str(), symbols[1].segment), ("other", 3));
+            }
+
+            #[test]
+            fn frames_of_s_frameproc_records() {
+                let dump = include_str!("../fixtures/selftest/pe-frameproc.pdb.yaml");
+                let pdb = load_pdb_from_str(dump).unwrap();
+
+                let frame_of = |name: &str| {
+                    pdb.functions
+                        .iter()
+                        .find(|f| f.name == name)
+                        .and_then(|f| f.frame.clone())
+                        .unwrap()
+                };
+
+                let func_a = frame_of("funcA");
+                assert_eq!(func_a.size, 40);
+                assert_eq!(
+                    func_a.flags,
+                    vec![
+                        "HasStructuredExceptionHandling",
+                        "AsynchronousExceptionHandling"
+                    ]
+                );
+                assert!(func_a.has_seh());
+
+                let func_b = frame_of("funcB");
+                assert_eq!(func_b.size, 0);
+                assert!(func_b.flags.is_empty());
             }
         }
     }
 
     pub mod elf {
-        use log::{debug, error, info, warn};
         use std::collections::HashMap;
-        use std::fs::File;
-        use std::io::prelude::*;
+        use std::io::BufRead;
+        use tracing::debug;
 
         use crate::groundtruth;
         use yaml_rust::{Yaml, YamlLoader};
@@ -209,24 +805,74 @@ pub mod yaml {
         /// Some documentation.
         #[allow(dead_code)]
         pub fn load_elf(path: &str) -> Result<groundtruth::DWARF, &'static str> {
-            let mut f = match File::open(path) {
-                Ok(f) => f,
-                Err(_e) => {
-                    return Err("[-] Could not find file!");
+            load_elf_from_str(&read_elf(path)?)
+        }
+
+        /// Reads a YAML dump and keeps only the parts needed by [`load_elf_from_str`], see
+        /// [`filter_elf`]. The whole dump is never held in memory.
+        pub fn read_elf(path: &str) -> Result<String, &'static str> {
+            super::read_filtered(path, filter_elf)
+        }
+
+        /// Keeps the `FileHeader`, the `Symbols` and the names of the `Sections` (without their
+        /// contents). All other parts (e.g. `DWARF`, `DynamicSymbols`) are dropped.
+        pub fn filter_elf<R: BufRead>(reader: R) -> Result<String, &'static str> {
+            let mut part = String::new();
+
+            super::filter_blocks(reader, |indentation, line| {
+                if indentation == 0 {
+                    part = super::top_level_key(line).to_string();
+
+                    return matches!(
+                        part.as_str(),
+                        "---" | "..." | "FileHeader" | "Sections" | "Symbols"
+                    );
                 }
+
+                part != "Sections" || line.starts_with("- Name:")
+            })
+        }
+
+        /// Loads several YAML dumps and merges their functions. Earlier dumps take priority if two
+        /// dumps provide a function at the same location.
+        pub fn load_elfs_from_strs(
+            contents: &[String],
+        ) -> Result<groundtruth::DWARF, &'static str> {
+            let mut elfs = contents.iter().map(|c| load_elf_from_str(c));
+
+            let mut merged = match elfs.next() {
+                Some(elf) => elf?,
+                None => return Err("No dump given!"),
             };
 
-            let mut contents = String::new();
+            for elf in elfs {
+                let elf = elf?;
+
+                super::merge_by_location(&mut merged.functions, elf.functions, |f| {
+                    (f.segment, f.offset)
+                });
+                super::merge_by_location(&mut merged.data, elf.data, |d| (d.segment, d.offset));
+                merged.skipped_symbols += elf.skipped_symbols;
+            }
+
+            merged.functions.sort_by_key(|a| a.offset);
+            merged.data.sort_by_key(|a| a.offset);
 
-            match f.read_to_string(&mut contents) {
-                Ok(_f) => {}
+            Ok(merged)
+        }
+
+        /// String-based variant of [`load_elf`] taking the YAML dump itself.
+        pub fn load_elf_from_str(contents: &str) -> Result<groundtruth::DWARF, &'static str> {
+            // Only build the document of the symbols and sections actually needed
+            let contents = filter_elf(contents.as_bytes())?;
+
+            let docs = match YamlLoader::load_from_str(&contents) {
+                Ok(docs) => docs,
                 Err(_e) => {
-                    return Err("[-] Could not read file!");
+                    return Err("Could not parse YAML!");
                 }
             };
 
-            let docs = YamlLoader::load_from_str(contents.as_str()).unwrap();
-
             let doc = &docs[0];
 
             // Guard: Check if TpiStream exists
@@ -241,12 +887,15 @@ pub mod yaml {
             let mut ssections = HashMap::new();
 
             for (index, section) in sections.as_vec().unwrap().iter().enumerate() {
-                ssections.insert(section["Name"].as_str().unwrap(), index);
-                debug!("{}: {}", index, section["Name"].as_str().unwrap());
+                let name = super::scalar(&section["Name"]).unwrap_or_default();
+                debug!("{}: {}", index, name);
+                ssections.insert(name, index);
             }
 
             // Collections
             let mut functions: Vec<groundtruth::Function> = Vec::new();
+            let mut data: Vec<groundtruth::Data> = Vec::new();
+            let mut skipped_symbols = 0;
 
             // Iterate all symbols (local, global, weak)
             let mut all_symbols = Vec::new();
@@ -263,31 +912,59 @@ pub mod yaml {
                     continue;
                 }
                 match symbol["Type"].as_str().unwrap() {
-                    "STT_FUNC" => {
-                        if let Some(function) = parse_function(&symbol, &ssections) {
-                            functions.push(function);
+                    "STT_FUNC" => match parse_function(&symbol, &ssections) {
+                        Some(function) => functions.push(function),
+                        None => skipped_symbols += 1,
+                    },
+                    "STT_OBJECT" => {
+                        if let Some(symbol) = parse_data(&symbol, &ssections) {
+                            data.push(symbol);
                         }
                     }
                     _ => {}
                 }
             }
 
+            // The lowest bit of an ARM function symbol marks Thumb code, it is no part of the
+            // address
+            if file_header["Machine"].as_str() == Some("EM_ARM") {
+                for function in &mut functions {
+                    function.thumb = function.offset & 1 == 1;
+                    function.offset &= !1;
+                }
+            }
+
             debug!("##### PARSER ######");
             debug!("Functions: {}", functions.len());
+            debug!("Data: {}", data.len());
 
             // Sort symbols by address
-            functions.sort_by(|a, b| a.offset.cmp(&b.offset));
+            functions.sort_by_key(|a| a.offset);
+            data.sort_by_key(|a| a.offset);
 
             // Remove duplicates
             functions.dedup();
+            data.dedup();
 
             // Collect meta information
-            let architecture = match file_header["Class"].as_str().unwrap() {
-                "ELFCLASS32" => groundtruth::ARCHITECTURE::X86,
-                "ELFCLASS64" => groundtruth::ARCHITECTURE::X64,
+            let architecture = match (
+                file_header["Machine"].as_str(),
+                file_header["Class"].as_str().unwrap(),
+            ) {
+                (Some("EM_MIPS"), "ELFCLASS64") => groundtruth::ARCHITECTURE::MIPS64,
+                (Some("EM_MIPS"), _) => groundtruth::ARCHITECTURE::MIPS,
+                (Some("EM_ARM"), _) => groundtruth::ARCHITECTURE::ARM,
+                (_, "ELFCLASS32") => groundtruth::ARCHITECTURE::X86,
+                (_, "ELFCLASS64") => groundtruth::ARCHITECTURE::X64,
                 _ => groundtruth::ARCHITECTURE::UNKNOWN,
             };
 
+            let mut architecture = groundtruth::ArchInfo::from(architecture);
+            architecture.endianness = match file_header["Data"].as_str() {
+                Some("ELFDATA2MSB") => groundtruth::ENDIANNESS::BIG,
+                _ => groundtruth::ENDIANNESS::LITTLE,
+            };
+
             let image_base = match file_header["Class"].as_str().unwrap() {
                 "ELFCLASS32" => 0x400000,
                 "ELFCLASS64" => 0x140000000,
@@ -298,17 +975,36 @@ pub mod yaml {
                 architecture,
                 image_base,
                 functions,
+                data,
+                skipped_symbols,
+            })
+        }
+
+        /// Parses a data symbol. Symbols without a section (e.g. absolute ones) are ignored, a
+        /// missing size is 0.
+        fn parse_data(
+            record: &Yaml,
+            sections: &HashMap<String, usize>,
+        ) -> Option<groundtruth::Data> {
+            let section = super::scalar(&record["Section"])?;
+
+            Some(groundtruth::Data {
+                name: super::scalar(&record["Name"]).unwrap_or_default(),
+                offset: record["Value"].as_i64()? as u64,
+                segment: *sections.get(&section)? as u8,
+                size: record["Size"].as_i64().unwrap_or(0) as u64,
+                bytes: None,
             })
         }
 
         /// Add.
         fn parse_function(
             record: &Yaml,
-            sections: &HashMap<&str, usize>,
+            sections: &HashMap<String, usize>,
         ) -> Option<groundtruth::Function> {
-            let name = record["Name"].as_str().unwrap();
+            let name = super::scalar(&record["Name"]).unwrap_or_default();
 
-            let section = match record["Section"].as_str() {
+            let section = match super::scalar(&record["Section"]) {
                 Some(section) => section,
                 None => {
                     debug!("Function {} has no section", name);
@@ -332,14 +1028,210 @@ pub mod yaml {
                 }
             };
 
+            // Guard: The section has to be listed
+            let segment = match sections.get(&section) {
+                Some(index) => *index as u8,
+                None => {
+                    debug!("Function {} lies in an unknown section {}", name, section);
+                    return None;
+                }
+            };
+
             Some(groundtruth::Function {
-                name: name.to_string(),
+                name,
+                demangled_name: None,
                 offset: offset as u64,
-                segment: *sections.get(section).unwrap() as u8,
+                segment,
                 size: size as u64,
                 labels: Vec::new(),
                 data: Vec::new(),
+                frame: None,
+                thumb: false,
             })
         }
     }
 }
+
+/// Reads function lists exported from the Functions window of Ghidra (CSV). Function offsets are
+/// the (absolute) addresses of the functions.
+pub mod ghidra {
+    use std::fs;
+    use tracing::{debug, warn};
+
+    use crate::groundtruth;
+
+    pub fn load_functions(path: &str) -> Result<Vec<groundtruth::Function>, &'static str> {
+        match fs::read_to_string(path) {
+            Ok(contents) => load_functions_from_str(&contents),
+            Err(_e) => Err("Could not read file!"),
+        }
+    }
+
+    /// String-based variant of [`load_functions`] taking the CSV export itself.
+    pub fn load_functions_from_str(
+        contents: &str,
+    ) -> Result<Vec<groundtruth::Function>, &'static str> {
+        let mut lines = contents.lines().filter(|l| !l.trim().is_empty());
+
+        let header = match lines.next() {
+            Some(header) => split_record(header),
+            None => return Err("Function list is empty!"),
+        };
+
+        let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+
+        let (name, location, size) = match (
+            column(&["Name", "Function Name"]),
+            column(&["Location", "Entry Point"]),
+            column(&["Function Size", "Size"]),
+        ) {
+            (Some(name), Some(location), Some(size)) => (name, location, size),
+            _ => return Err("Function list needs the columns Name, Location and Function Size!"),
+        };
+
+        let mut functions = Vec::new();
+        let mut skipped = 0;
+
+        for line in lines {
+            let record = split_record(line);
+
+            let function = match (record.get(name), record.get(location), record.get(size)) {
+                (Some(name), Some(location), Some(size)) => parse_function(name, location, size),
+                _ => None,
+            };
+
+            match function {
+                Some(function) => functions.push(function),
+                None => {
+                    debug!("Skipped function list entry: {}", line);
+                    skipped += 1;
+                }
+            }
+        }
+
+        if skipped > 0 {
+            warn!(
+                "Skipped {} entries of the function list (e.g. external functions).",
+                skipped
+            );
+        }
+
+        functions.sort_by_key(|a| a.offset);
+
+        Ok(functions)
+    }
+
+    fn parse_function(name: &str, location: &str, size: &str) -> Option<groundtruth::Function> {
+        // Guard: External functions only have a placeholder address
+        if location.starts_with("EXTERNAL") {
+            return None;
+        }
+
+        // Addresses may carry their address space (e.g. ram:00401000)
+        let digits = location.rsplit(':').next()?.trim_start_matches("0x");
+
+        Some(groundtruth::Function {
+            name: name.to_string(),
+            demangled_name: None,
+            offset: u64::from_str_radix(digits, 16).ok()?,
+            segment: 0,
+            size: size.parse().ok()?,
+            labels: Vec::new(),
+            data: Vec::new(),
+            frame: None,
+            thumb: false,
+        })
+    }
+
+    /// Splits a CSV line into its (unquoted) fields.
+    fn split_record(line: &str) -> Vec<String> {
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut chars = line.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => fields.push(field.split_off(0)),
+                _ => field.push(c),
+            }
+        }
+
+        fields.push(field);
+        fields
+    }
+}
+
+/// Reads function lists exported from the Functions window of IDA (tab-separated `Function name`,
+/// `Segment`, `Start` and `Length` columns). Function offsets are the (absolute) addresses of the
+/// functions.
+pub mod ida {
+    use std::fs;
+    use tracing::{debug, warn};
+
+    use crate::groundtruth;
+
+    pub fn load_functions(path: &str) -> Result<Vec<groundtruth::Function>, &'static str> {
+        match fs::read_to_string(path) {
+            Ok(contents) => load_functions_from_str(&contents),
+            Err(_e) => Err("Could not read file!"),
+        }
+    }
+
+    /// String-based variant of [`load_functions`] taking the export itself.
+    pub fn load_functions_from_str(
+        contents: &str,
+    ) -> Result<Vec<groundtruth::Function>, &'static str> {
+        let mut functions = Vec::new();
+        let mut skipped = 0;
+
+        for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+            let fields: Vec<&str> = line.split('\t').map(str::trim).collect();
+
+            // Guard: Skip the header
+            if fields[0] == "Function name" {
+                continue;
+            }
+
+            let function = match fields.as_slice() {
+                [name, _segment, start, length, ..] => parse_function(name, start, length),
+                _ => None,
+            };
+
+            match function {
+                Some(function) => functions.push(function),
+                None => {
+                    debug!("Skipped function list entry: {}", line);
+                    skipped += 1;
+                }
+            }
+        }
+
+        if skipped > 0 {
+            warn!("Skipped {} entries of the function list.", skipped);
+        }
+
+        functions.sort_by_key(|a| a.offset);
+
+        Ok(functions)
+    }
+
+    fn parse_function(name: &str, start: &str, length: &str) -> Option<groundtruth::Function> {
+        Some(groundtruth::Function {
+            name: name.to_string(),
+            demangled_name: None,
+            offset: u64::from_str_radix(start.trim_start_matches("0x"), 16).ok()?,
+            segment: 0,
+            size: u64::from_str_radix(length.trim_start_matches("0x"), 16).ok()?,
+            labels: Vec::new(),
+            data: Vec::new(),
+            frame: None,
+            thumb: false,
+        })
+    }
+}
@@ -3,110 +3,474 @@ use std::io::Read;
 
 use goblin::pe;
 use goblin::pe::header::{COFF_MACHINE_X86, COFF_MACHINE_X86_64};
+use goblin::pe::section_table;
 
 use crate::groundtruth;
 
-pub fn get_architecture(path: &str) -> Result<groundtruth::ARCHITECTURE, &'static str> {
-    let mut buffer = Vec::new();
+pub fn get_architecture(path: &str) -> Result<groundtruth::ArchInfo, &'static str> {
+    get_architecture_from_buffer(&read_file(path)?)
+}
 
-    let mut f = match File::open(path) {
-        Ok(f) => f,
+/// Buffer-based variant of [`get_architecture`].
+pub fn get_architecture_from_buffer(buffer: &[u8]) -> Result<groundtruth::ArchInfo, &'static str> {
+    let pe = match pe::PE::parse(buffer) {
+        Ok(pe) => pe,
         Err(_e) => {
-            return Err("[-] Could not find file!");
+            return Err("Could not parse pe");
         }
     };
 
-    match f.read_to_end(&mut buffer) {
-        Ok(_f) => {}
+    let machine = pe.header.coff_header.machine;
+
+    let (family, machine_name) = match machine {
+        COFF_MACHINE_X86 => (groundtruth::ARCHITECTURE::X86, "i386".to_string()),
+        COFF_MACHINE_X86_64 => (groundtruth::ARCHITECTURE::X64, "amd64".to_string()),
+        _ => (
+            groundtruth::ARCHITECTURE::UNKNOWN,
+            format!("0x{:04x}", machine),
+        ),
+    };
+
+    Ok(groundtruth::ArchInfo {
+        family,
+        bits: if pe.is_64 { 64 } else { 32 },
+        // PE images are always little endian
+        endianness: groundtruth::ENDIANNESS::LITTLE,
+        machine_name,
+    })
+}
+
+pub fn read_pe(path: &str) -> Result<Vec<groundtruth::Byte>, &'static str> {
+    read_pe_from_buffer(&read_file(path)?)
+}
+
+/// Buffer-based variant of [`read_pe`].
+pub fn read_pe_from_buffer(buffer: &[u8]) -> Result<Vec<groundtruth::Byte>, &'static str> {
+    let mut bytes = Vec::new();
+
+    for (offset, byte) in buffer.iter().enumerate() {
+        bytes.push(groundtruth::Byte {
+            offset: offset as u64,
+            value: *byte,
+            flags: Vec::new(),
+            provenance: Vec::new(),
+        })
+    }
+
+    Ok(bytes)
+}
+
+pub fn parse_sections(path: &str) -> Result<Vec<groundtruth::Section>, &'static str> {
+    parse_sections_from_buffer(&read_file(path)?)
+}
+
+/// Buffer-based variant of [`parse_sections`].
+pub fn parse_sections_from_buffer(
+    buffer: &[u8],
+) -> Result<Vec<groundtruth::Section>, &'static str> {
+    let pe = match pe::PE::parse(buffer) {
+        Ok(pe) => pe,
         Err(_e) => {
-            return Err("[-] Could not read file!");
+            return Err("Could not parse pe");
         }
     };
 
-    let pe = match pe::PE::parse(&buffer) {
+    let mut sections: Vec<groundtruth::Section> = Vec::new();
+
+    for section in pe.sections {
+        // Section names are raw bytes, invalid sequences are replaced
+        let name = String::from_utf8_lossy(&section.name)
+            .trim_matches(char::from(0))
+            .to_string();
+
+        let characteristics = [
+            (section_table::IMAGE_SCN_CNT_CODE, groundtruth::FLAG::CODE),
+            (
+                section_table::IMAGE_SCN_CNT_INITIALIZED_DATA,
+                groundtruth::FLAG::DATA,
+            ),
+            (
+                section_table::IMAGE_SCN_MEM_READ,
+                groundtruth::FLAG::READABLE,
+            ),
+            (
+                section_table::IMAGE_SCN_MEM_WRITE,
+                groundtruth::FLAG::WRITEABLE,
+            ),
+            (
+                section_table::IMAGE_SCN_MEM_EXECUTE,
+                groundtruth::FLAG::EXECUTABLE,
+            ),
+        ];
+
+        sections.push(groundtruth::Section {
+            name,
+            va: section.virtual_address as u64,
+            raw_data_offset: section.pointer_to_raw_data as u64,
+            raw_data_size: section.size_of_raw_data as u64,
+            flags: characteristics
+                .iter()
+                .filter(|(mask, _)| section.characteristics & mask != 0)
+                .map(|(_, flag)| flag.clone())
+                .collect(),
+            overlaps: Vec::new(),
+        });
+    }
+
+    // Every byte of the file belongs to the first section containing it
+    groundtruth::resolve_section_overlaps(&mut sections, |_| true);
+
+    Ok(sections)
+}
+
+pub fn parse_imports(path: &str) -> Result<Vec<groundtruth::Import>, &'static str> {
+    parse_imports_from_buffer(&read_file(path)?)
+}
+
+/// Buffer-based variant of [`parse_imports`]. The IAT addresses are RVAs.
+pub fn parse_imports_from_buffer(buffer: &[u8]) -> Result<Vec<groundtruth::Import>, &'static str> {
+    let pe = match pe::PE::parse(buffer) {
         Ok(pe) => pe,
         Err(_e) => {
-            return Err("[-] Could not parse pe");
+            return Err("Could not parse pe");
         }
     };
 
-    let architecture = match pe.header.coff_header.machine {
-        COFF_MACHINE_X86 => groundtruth::ARCHITECTURE::X86,
-        COFF_MACHINE_X86_64 => groundtruth::ARCHITECTURE::X64,
-        _ => groundtruth::ARCHITECTURE::UNKNOWN,
-    };
+    let mut imports = Vec::new();
+
+    for import in pe.imports {
+        imports.push(groundtruth::Import {
+            name: import.name.to_string(),
+            dll: import.dll.to_string(),
+            iat: import.offset as u64,
+            size: import.size as u64,
+            thunk: None,
+            delayed: false,
+            load_thunk: None,
+        });
+    }
 
-    Ok(architecture)
+    Ok(imports)
 }
 
-pub fn read_pe(path: &str) -> Result<Vec<groundtruth::Byte>, &'static str> {
-    let mut buffer = Vec::new();
-    let mut bytes = Vec::new();
+pub fn parse_delay_imports(path: &str) -> Result<Vec<groundtruth::Import>, &'static str> {
+    parse_delay_imports_from_buffer(&read_file(path)?)
+}
 
-    let mut f = match File::open(path) {
-        Ok(f) => f,
+/// Buffer-based variant of [`parse_delay_imports`]. Reads the descriptors of the delay import
+/// directory (`ImgDelayDescr`), every entry of a delay IAT initially holds the address of its load
+/// thunk. All addresses are RVAs. Imports whose names lie outside of the file are skipped.
+pub fn parse_delay_imports_from_buffer(
+    buffer: &[u8],
+) -> Result<Vec<groundtruth::Import>, &'static str> {
+    let pe = match pe::PE::parse(buffer) {
+        Ok(pe) => pe,
         Err(_e) => {
-            return Err("[-] Could not find file!");
+            return Err("Could not parse pe");
         }
     };
 
-    match f.read_to_end(&mut buffer) {
-        Ok(_f) => {}
+    let directory = match pe
+        .header
+        .optional_header
+        .and_then(|h| *h.data_directories.get_delay_import_descriptor())
+    {
+        Some(directory) if directory.virtual_address != 0 => directory,
+        _ => return Ok(Vec::new()),
+    };
+
+    let sections = parse_sections_from_buffer(buffer)?;
+    let image_base = pe.image_base as u64;
+    let pointer_size = if pe.is_64 { 8 } else { 4 };
+
+    let table = match file_offset(&sections, directory.virtual_address as u64) {
+        Some(table) => table as usize,
+        None => return Err("Delay import directory lies outside of all sections!"),
+    };
+
+    let mut imports = Vec::new();
+
+    for descriptor in (table..).step_by(DELAY_DESCRIPTOR_SIZE) {
+        let (attributes, dll_name, iat, int) = match (
+            read_u32(buffer, descriptor),
+            read_u32(buffer, descriptor + 4),
+            read_u32(buffer, descriptor + 12),
+            read_u32(buffer, descriptor + 16),
+        ) {
+            (Some(attributes), Some(dll_name), Some(iat), Some(int)) => {
+                (attributes, dll_name, iat, int)
+            }
+            _ => break,
+        };
+
+        // Guard: The table ends with an empty descriptor
+        if dll_name == 0 || iat == 0 || int == 0 {
+            break;
+        }
+
+        // Descriptors of old linkers hold virtual addresses instead of RVAs
+        let rva = |address: u64| match attributes & DLATTR_RVA {
+            0 => address.wrapping_sub(image_base),
+            _ => address,
+        };
+
+        let dll = match file_offset(&sections, rva(dll_name)).and_then(|o| read_c_string(buffer, o))
+        {
+            Some(dll) => dll,
+            None => continue,
+        };
+
+        for entry in 0.. {
+            let name_entry = file_offset(&sections, rva(int) + entry * pointer_size)
+                .and_then(|o| read_pointer(buffer, o as usize, pointer_size));
+            let iat_entry = rva(iat) + entry * pointer_size;
+
+            let name_entry = match name_entry {
+                Some(name_entry) if name_entry != 0 => name_entry,
+                _ => break,
+            };
+
+            // The highest bit marks an import by ordinal, names are preceded by a hint
+            let ordinal_flag = 1 << (pointer_size * 8 - 1);
+            let name = match name_entry & ordinal_flag {
+                0 => match file_offset(&sections, rva(name_entry) + 2)
+                    .and_then(|o| read_c_string(buffer, o))
+                {
+                    Some(name) => name,
+                    None => continue,
+                },
+                _ => format!("#{}", name_entry & 0xFFFF),
+            };
+
+            // The IAT holds the virtual address of the load thunk until the import is resolved
+            let load_thunk = file_offset(&sections, iat_entry)
+                .and_then(|o| read_pointer(buffer, o as usize, pointer_size))
+                .filter(|address| *address != 0)
+                .map(|address| address.wrapping_sub(image_base));
+
+            imports.push(groundtruth::Import {
+                name,
+                dll: dll.clone(),
+                iat: iat_entry,
+                size: pointer_size,
+                thunk: None,
+                delayed: true,
+                load_thunk,
+            });
+        }
+    }
+
+    Ok(imports)
+}
+
+/// Size of an `ImgDelayDescr` entry of the delay import directory.
+const DELAY_DESCRIPTOR_SIZE: usize = 32;
+
+/// Attribute of delay import descriptors which hold RVAs (every linker since Visual C++ 7).
+const DLATTR_RVA: u64 = 0x1;
+
+pub fn parse_runtime_functions(
+    path: &str,
+) -> Result<Vec<groundtruth::RuntimeFunction>, &'static str> {
+    parse_runtime_functions_from_buffer(&read_file(path)?)
+}
+
+/// Buffer-based variant of [`parse_runtime_functions`]. Reads the RUNTIME_FUNCTION entries of the
+/// exception directory, which only x64 images have. Entries pointing outside of the file are
+/// skipped.
+pub fn parse_runtime_functions_from_buffer(
+    buffer: &[u8],
+) -> Result<Vec<groundtruth::RuntimeFunction>, &'static str> {
+    let pe = match pe::PE::parse(buffer) {
+        Ok(pe) => pe,
         Err(_e) => {
-            return Err("[-] Could not read file!");
+            return Err("Could not parse pe");
         }
     };
 
-    for (offset, byte) in buffer.iter().enumerate() {
-        bytes.push(groundtruth::Byte {
-            offset: offset as u64,
-            value: *byte,
-            flags: Vec::new(),
-        })
+    // Guard: x86 images do not use table based exception handling
+    if pe.header.coff_header.machine != COFF_MACHINE_X86_64 {
+        return Ok(Vec::new());
     }
 
-    Ok(bytes)
+    let directory = match pe
+        .header
+        .optional_header
+        .and_then(|h| *h.data_directories.get_exception_table())
+    {
+        Some(directory) => directory,
+        None => return Ok(Vec::new()),
+    };
+
+    let sections = parse_sections_from_buffer(buffer)?;
+
+    let table = match file_offset(&sections, directory.virtual_address as u64) {
+        Some(table) => table as usize,
+        None => return Err("Exception directory lies outside of all sections!"),
+    };
+
+    let mut runtime_functions = Vec::new();
+
+    for entry in 0..directory.size as usize / RUNTIME_FUNCTION_SIZE {
+        let offset = table + entry * RUNTIME_FUNCTION_SIZE;
+
+        let (begin, end, unwind_info) = match (
+            read_u32(buffer, offset),
+            read_u32(buffer, offset + 4),
+            read_u32(buffer, offset + 8),
+        ) {
+            (Some(begin), Some(end), Some(unwind_info)) => (begin, end, unwind_info),
+            _ => break,
+        };
+
+        // An odd RVA points to the RUNTIME_FUNCTION of the primary entry instead of an UNWIND_INFO
+        let unwind_info_size = match unwind_info & 1 {
+            0 => file_offset(&sections, unwind_info)
+                .and_then(|offset| unwind_info_size(buffer, offset as usize))
+                .unwrap_or(0),
+            _ => 0,
+        };
+        let unwind_info = unwind_info & !1;
+
+        runtime_functions.push(groundtruth::RuntimeFunction {
+            begin,
+            end,
+            unwind_info,
+            unwind_info_size,
+        });
+    }
+
+    Ok(runtime_functions)
 }
 
-pub fn parse_sections(path: &str) -> Result<Vec<groundtruth::Section>, &'static str> {
+/// Size of a RUNTIME_FUNCTION entry (begin, end and unwind info RVA).
+const RUNTIME_FUNCTION_SIZE: usize = 12;
+
+/// UNWIND_INFO flags announcing an exception or termination handler.
+const UNW_FLAG_EHANDLER: u8 = 0x1;
+const UNW_FLAG_UHANDLER: u8 = 0x2;
+/// UNWIND_INFO flag announcing a chained RUNTIME_FUNCTION.
+const UNW_FLAG_CHAININFO: u8 = 0x4;
+
+/// Size of the UNWIND_INFO at the given file offset: the header, the (even) number of unwind
+/// codes and the handler RVA or the chained RUNTIME_FUNCTION.
+fn unwind_info_size(buffer: &[u8], offset: usize) -> Option<u64> {
+    let flags = buffer.get(offset)? >> 3;
+    let codes = u64::from(*buffer.get(offset + 2)?);
+
+    let mut size = 4 + 2 * (codes + codes % 2);
+
+    if flags & UNW_FLAG_CHAININFO != 0 {
+        size += RUNTIME_FUNCTION_SIZE as u64;
+    } else if flags & (UNW_FLAG_EHANDLER | UNW_FLAG_UHANDLER) != 0 {
+        size += 4;
+    }
+
+    Some(size)
+}
+
+/// Maps an RVA to its file offset.
+fn file_offset(sections: &[groundtruth::Section], rva: u64) -> Option<u64> {
+    sections
+        .iter()
+        .find(|s| rva >= s.va && rva < s.va + s.raw_data_size)
+        .map(|s| rva - s.va + s.raw_data_offset)
+}
+
+fn read_u32(buffer: &[u8], offset: usize) -> Option<u64> {
+    let bytes = buffer.get(offset..offset + 4)?;
+    Some(u64::from(u32::from_le_bytes([
+        bytes[0], bytes[1], bytes[2], bytes[3],
+    ])))
+}
+
+fn read_pointer(buffer: &[u8], offset: usize, size: u64) -> Option<u64> {
+    match size {
+        8 => {
+            let bytes = buffer.get(offset..offset + 8)?;
+            let mut value = [0; 8];
+            value.copy_from_slice(bytes);
+            Some(u64::from_le_bytes(value))
+        }
+        _ => read_u32(buffer, offset),
+    }
+}
+
+/// Reads the NUL terminated string at the given file offset.
+fn read_c_string(buffer: &[u8], offset: u64) -> Option<String> {
+    let bytes = buffer.get(offset as usize..)?;
+    let length = bytes.iter().position(|b| *b == 0)?;
+
+    Some(String::from_utf8_lossy(&bytes[..length]).into_owned())
+}
+
+/// Retrieves the preferred image base from the PE header.
+pub fn get_image_base_from_buffer(buffer: &[u8]) -> Result<u64, &'static str> {
+    match pe::PE::parse(buffer) {
+        Ok(pe) => Ok(pe.image_base as u64),
+        Err(_e) => Err("Could not parse pe"),
+    }
+}
+
+/// Reads the whole binary into memory.
+fn read_file(path: &str) -> Result<Vec<u8>, &'static str> {
     let mut buffer = Vec::new();
 
     let mut f = match File::open(path) {
         Ok(f) => f,
         Err(_e) => {
-            return Err("[-] Could not find file!");
+            return Err("Could not find file!");
         }
     };
 
     match f.read_to_end(&mut buffer) {
         Ok(_f) => {}
         Err(_e) => {
-            return Err("[-] Could not read file!");
+            return Err("Could not read file!");
         }
     };
 
-    let pe = match pe::PE::parse(&buffer) {
-        Ok(pe) => pe,
-        Err(_e) => {
-            return Err("[-] Could not parse pe");
-        }
-    };
+    Ok(buffer)
+}
 
-    let mut sections: Vec<groundtruth::Section> = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    for section in pe.sections {
-        let name = match String::from_utf8(section.name.to_vec()) {
-            Ok(name) => name.trim_matches(char::from(0)).to_string(),
-            Err(_e) => "PLACEHOLDER".to_string(),
-        };
+    #[test]
+    fn overlapping_section_is_cut_behind_the_earlier_one() {
+        // The raw data of .pdata starts within .text (at 0x300 instead of 0x400)
+        let binary = include_bytes!("../fixtures/selftest/pe-overlap.exe");
+        let sections = parse_sections_from_buffer(binary).unwrap();
 
-        sections.push(groundtruth::Section {
-            name,
-            va: section.virtual_address as u64,
-            raw_data_offset: section.pointer_to_raw_data as u64,
-            raw_data_size: section.size_of_raw_data as u64,
-        });
+        let cut: Vec<(&str, u64, u64, u64)> = sections
+            .iter()
+            .filter(|s| !s.overlaps.is_empty())
+            .map(|s| (s.name.as_str(), s.va, s.raw_data_offset, s.raw_data_size))
+            .collect();
+        assert_eq!(cut, vec![(".pdata", 0x2100, 0x400, 0x100)]);
     }
 
-    Ok(sections)
+    #[test]
+    fn runtime_functions_of_the_exception_directory() {
+        // funcA, funcB and a function without symbol whose unwind info lies in .text
+        let binary = include_bytes!("../fixtures/selftest/pe.exe");
+        let runtime_functions = parse_runtime_functions_from_buffer(binary).unwrap();
+
+        let ranges: Vec<(u64, u64)> = runtime_functions.iter().map(|f| (f.begin, f.end)).collect();
+        assert_eq!(
+            ranges,
+            vec![(0x1000, 0x100a), (0x1010, 0x101e), (0x1020, 0x1025)]
+        );
+        assert_eq!(runtime_functions[2].unwind_info, 0x1028);
+        assert_eq!(runtime_functions[2].unwind_info_size, 8);
+    }
+
+    #[test]
+    fn no_section_is_cut_without_overlap() {
+        let binary = include_bytes!("../fixtures/selftest/pe.exe");
+        let sections = parse_sections_from_buffer(binary).unwrap();
+
+        assert!(sections.iter().all(|s| s.overlaps.is_empty()));
+    }
 }
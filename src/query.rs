@@ -0,0 +1,156 @@
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::IntoDeserializer;
+use serde::Deserialize;
+
+use crate::dumper;
+use crate::groundtruth;
+
+/// Represents a parsed flag expression (e.g. `DATA AND NOT INSTRUCTION_START`).
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum EXPRESSION {
+    FLAG(groundtruth::FLAG),
+    NOT(Box<EXPRESSION>),
+    AND(Box<EXPRESSION>, Box<EXPRESSION>),
+    OR(Box<EXPRESSION>, Box<EXPRESSION>),
+}
+
+impl EXPRESSION {
+    /// Whether the given flags satisfy the expression.
+    pub fn matches(&self, flags: &[groundtruth::FLAG]) -> bool {
+        match self {
+            EXPRESSION::FLAG(flag) => flags.contains(flag),
+            EXPRESSION::NOT(inner) => !inner.matches(flags),
+            EXPRESSION::AND(left, right) => left.matches(flags) && right.matches(flags),
+            EXPRESSION::OR(left, right) => left.matches(flags) || right.matches(flags),
+        }
+    }
+}
+
+/// Represents contiguous bytes matching an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Range {
+    pub start: u64,
+    /// Address of the last byte (inclusive).
+    pub end: u64,
+    pub size: u64,
+}
+
+/// Results of an expression evaluated against a dump.
+#[derive(Debug, Default)]
+pub struct Matches<'a> {
+    pub instructions: Vec<&'a groundtruth::Instruction>,
+    pub ranges: Vec<Range>,
+}
+
+/// Parses an expression of flag names, `AND`, `OR`, `NOT` (or `!`) and parentheses. Names and
+/// operators are case insensitive, `NOT` binds tighter than `AND`, which binds tighter than `OR`.
+pub fn parse(text: &str) -> Result<EXPRESSION, String> {
+    let tokens = tokenize(text);
+    let mut position = 0;
+
+    let expression = parse_or(&tokens, &mut position)?;
+
+    match tokens.get(position) {
+        None => Ok(expression),
+        Some(token) => Err(format!("Unexpected {} in expression.", token)),
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace('!', " ! ")
+        .split_whitespace()
+        .map(String::from)
+        .collect()
+}
+
+fn parse_or(tokens: &[String], position: &mut usize) -> Result<EXPRESSION, String> {
+    let mut left = parse_and(tokens, position)?;
+
+    while is_keyword(tokens.get(*position), "or") {
+        *position += 1;
+        let right = parse_and(tokens, position)?;
+        left = EXPRESSION::OR(Box::new(left), Box::new(right));
+    }
+
+    Ok(left)
+}
+
+fn parse_and(tokens: &[String], position: &mut usize) -> Result<EXPRESSION, String> {
+    let mut left = parse_not(tokens, position)?;
+
+    while is_keyword(tokens.get(*position), "and") {
+        *position += 1;
+        let right = parse_not(tokens, position)?;
+        left = EXPRESSION::AND(Box::new(left), Box::new(right));
+    }
+
+    Ok(left)
+}
+
+fn parse_not(tokens: &[String], position: &mut usize) -> Result<EXPRESSION, String> {
+    let token = match tokens.get(*position) {
+        Some(token) => token,
+        None => return Err("Expected a flag at the end of the expression.".to_string()),
+    };
+    *position += 1;
+
+    if is_keyword(Some(token), "not") || token == "!" {
+        return Ok(EXPRESSION::NOT(Box::new(parse_not(tokens, position)?)));
+    }
+
+    if token == "(" {
+        let inner = parse_or(tokens, position)?;
+
+        // Guard: Every group has to be closed
+        if tokens.get(*position).map(String::as_str) != Some(")") {
+            return Err("Expected ) in expression.".to_string());
+        }
+        *position += 1;
+
+        return Ok(inner);
+    }
+
+    parse_flag(token).map(EXPRESSION::FLAG)
+}
+
+fn is_keyword(token: Option<&String>, keyword: &str) -> bool {
+    token.is_some_and(|t| t.eq_ignore_ascii_case(keyword))
+}
+
+/// Reads a flag by the name it has in the dumps (e.g. `INSTRUCTION_CALL`, case insensitive).
+fn parse_flag(name: &str) -> Result<groundtruth::FLAG, String> {
+    let upper = name.to_ascii_uppercase();
+    let deserializer: StrDeserializer<ValueError> = upper.as_str().into_deserializer();
+
+    groundtruth::FLAG::deserialize(deserializer).map_err(|_e| format!("Unknown flag: {}", name))
+}
+
+/// Finds the instructions and the runs of bytes whose flags satisfy the expression.
+pub fn evaluate<'a>(dump: &'a dumper::Dump, expression: &EXPRESSION) -> Matches<'a> {
+    let mut matches = Matches::default();
+
+    matches.instructions = dump
+        .instructions
+        .iter()
+        .filter(|i| expression.matches(&i.flags))
+        .collect();
+
+    for byte in dump.bytes.iter().filter(|b| expression.matches(&b.flags)) {
+        match matches.ranges.last_mut() {
+            Some(range) if range.end + 1 == byte.offset => {
+                range.end = byte.offset;
+                range.size += 1;
+            }
+            _ => matches.ranges.push(Range {
+                start: byte.offset,
+                end: byte.offset,
+                size: 1,
+            }),
+        }
+    }
+
+    matches
+}
@@ -0,0 +1,199 @@
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
+use tracing::error;
+
+use crate::disassembler;
+use crate::dumper;
+use crate::groundtruth;
+
+/// Runs an interactive prompt for querying a dump.
+///
+/// `function_base` is added to a function offset to obtain the address of its first byte.
+pub fn run(dump: &dumper::Dump, function_base: u64) {
+    let mut editor = Editor::<()>::new();
+
+    println!("[+] Entering REPL. Type 'help' for a list of commands.");
+
+    loop {
+        let line = match editor.readline("b2g> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                error!("[-] {}", e);
+                break;
+            }
+        };
+
+        editor.add_history_entry(line.as_str());
+
+        let mut arguments = line.split_whitespace();
+
+        match arguments.next() {
+            Some("help") => print_help(),
+            Some("flags") => print_flags(dump, arguments.next()),
+            Some("funcs") => print_functions(dump, function_base),
+            Some("func") => {
+                let name = arguments.collect::<Vec<&str>>().join(" ");
+                print_function(dump, function_base, &name);
+            }
+            Some("holes") => print_holes(dump),
+            Some("quit") | Some("exit") => break,
+            Some(command) => println!("[-] Unknown command: {}", command),
+            None => {}
+        }
+    }
+}
+
+fn print_help() {
+    println!("flags <addr>  Show value and flags of the byte at the given (hex) address.");
+    println!("funcs         List all functions.");
+    println!("func <name>   Show a function, its labels, data and instructions.");
+    println!("holes         List all ranges of bytes without any flags.");
+    println!("quit          Leave the REPL.");
+}
+
+fn parse_address(address: &str) -> Option<u64> {
+    let digits = address.trim_start_matches("0x").trim_start_matches("0X");
+
+    u64::from_str_radix(digits, 16).ok()
+}
+
+fn print_flags(dump: &dumper::Dump, address: Option<&str>) {
+    let address = match address.and_then(parse_address) {
+        Some(address) => address,
+        None => {
+            println!("[-] Usage: flags <addr>");
+            return;
+        }
+    };
+
+    match dump.byte_at(address) {
+        Some(byte) => println!(
+            "0x{:x}: 0x{:02x} {:?}",
+            byte.offset,
+            byte.value,
+            dump.flags_at(address)
+        ),
+        None => println!("[-] Address 0x{:x} is outside of the dump.", address),
+    }
+}
+
+fn print_functions(dump: &dumper::Dump, function_base: u64) {
+    for function in &dump.functions {
+        println!(
+            "0x{:x} (size 0x{:x}): {}",
+            function_base.wrapping_add(function.offset),
+            function.size,
+            function.name
+        );
+    }
+}
+
+fn print_function(dump: &dumper::Dump, function_base: u64, name: &str) {
+    let function = match dump.functions.iter().find(|f| f.name == name) {
+        Some(function) => function,
+        None => {
+            println!("[-] Function {} not found.", name);
+            return;
+        }
+    };
+
+    let start = function_base.wrapping_add(function.offset);
+    let end = start + function.size;
+
+    println!(
+        "{} @ 0x{:x} (size 0x{:x})",
+        function.name, start, function.size
+    );
+
+    for label in &function.labels {
+        println!("  label {} @ 0x{:x}", label.name, label.offset);
+    }
+
+    for data in &function.data {
+        println!(
+            "  data {} @ 0x{:x} (size 0x{:x})",
+            data.name, data.offset, data.size
+        );
+    }
+
+    // Disassemble every contiguous run of code bytes separately so in-line data is skipped
+    let mut run: Vec<&groundtruth::Byte> = Vec::new();
+
+    for byte in dump
+        .bytes
+        .iter()
+        .filter(|b| b.offset >= start && b.offset < end)
+    {
+        if byte.is_code() {
+            run.push(byte);
+            continue;
+        }
+
+        print_instructions(dump, &run);
+        run.clear();
+    }
+
+    print_instructions(dump, &run);
+}
+
+fn print_instructions(dump: &dumper::Dump, run: &[&groundtruth::Byte]) {
+    // Guard: Nothing to disassemble
+    if run.is_empty() {
+        return;
+    }
+
+    let buffer = run.iter().map(|b| b.value).collect();
+
+    let instructions = match disassembler::disassemble(
+        buffer,
+        &dump.arch_info,
+        disassembler::DISASSEMBLER::CAPSTONE,
+    ) {
+        Ok(instructions) => instructions,
+        Err(e) => {
+            println!("[-] {}", e);
+            return;
+        }
+    };
+
+    for instruction in instructions {
+        println!(
+            "  0x{:x}: {} {}",
+            run[0].offset + instruction.offset,
+            instruction.mnemonic,
+            instruction.operand
+        );
+    }
+}
+
+fn print_holes(dump: &dumper::Dump) {
+    let mut hole_start: Option<u64> = None;
+    let mut previous = 0;
+
+    for byte in &dump.bytes {
+        if !byte.is_identified() {
+            if hole_start.is_none() {
+                hole_start = Some(byte.offset);
+            }
+        } else if let Some(start) = hole_start.take() {
+            println!(
+                "0x{:x}-0x{:x} (size 0x{:x})",
+                start,
+                previous,
+                previous - start + 1
+            );
+        }
+
+        previous = byte.offset;
+    }
+
+    if let Some(start) = hole_start {
+        println!(
+            "0x{:x}-0x{:x} (size 0x{:x})",
+            start,
+            previous,
+            previous - start + 1
+        );
+    }
+}
@@ -0,0 +1,622 @@
+use std::env;
+
+use goblin::Object;
+
+use crate::b2g;
+use crate::b2g::common;
+use crate::config;
+use crate::groundtruth;
+
+/// Represents an embedded (dump, binary) pair and the expected result of processing it.
+struct Fixture {
+    name: &'static str,
+    dump: &'static str,
+    binary: &'static [u8],
+    functions: usize,
+    instructions: usize,
+    /// Minimum share of the code section which has to be identified (in percent).
+    min_identified: f64,
+    /// Time budget per function in milliseconds (see `--function-timeout`).
+    function_timeout: Option<u64>,
+}
+
+impl Fixture {
+    /// Fixture without dump, binary or expectations, the fixtures below only set what differs.
+    const DEFAULT: Fixture = Fixture {
+        name: "",
+        dump: "",
+        binary: &[],
+        functions: 0,
+        instructions: 0,
+        min_identified: 0.0,
+        function_timeout: None,
+    };
+}
+
+/// Hand-crafted PE x64 with two functions (`funcA`, `funcB`) and a PDB dump.
+const PE_FIXTURE: Fixture = Fixture {
+    name: "pe",
+    dump: include_str!("../fixtures/selftest/pe.pdb.yaml"),
+    binary: include_bytes!("../fixtures/selftest/pe.exe"),
+    functions: 2,
+    instructions: 10,
+    min_identified: 75.0,
+    ..Fixture::DEFAULT
+};
+
+/// ELF x64 built from `fixtures/selftest/elf.c`, see the comment at its top.
+const ELF_FIXTURE: Fixture = Fixture {
+    name: "elf",
+    dump: include_str!("../fixtures/selftest/elf.obj.yaml"),
+    binary: include_bytes!("../fixtures/selftest/elf"),
+    functions: 5,
+    instructions: 28,
+    min_identified: 45.0,
+    ..Fixture::DEFAULT
+};
+
+/// Represents the outcome of processing a single fixture.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub name: &'static str,
+    pub functions: usize,
+    pub instructions: usize,
+    /// Share of the code section which has been identified (in percent).
+    pub identified: f64,
+    /// Describes every expectation which was not met.
+    pub failures: Vec<String>,
+}
+
+impl Outcome {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    /// Outcome of a fixture which could not be processed at all.
+    fn failed(name: &'static str, failure: String) -> Self {
+        Outcome {
+            name,
+            functions: 0,
+            instructions: 0,
+            identified: 0.0,
+            failures: vec![failure],
+        }
+    }
+}
+
+/// Runs the complete pipeline on the embedded fixtures. Outputs are written to the temporary
+/// directory. The features are checked on the fixtures of the unit tests.
+pub fn run() -> Vec<Outcome> {
+    [PE_FIXTURE, ELF_FIXTURE].iter().map(run_fixture).collect()
+}
+
+fn run_fixture(fixture: &Fixture) -> Outcome {
+    let config = config::Config {
+        verify: true,
+        deterministic: true,
+        include_data_sections: true,
+        demangle: true,
+        function_timeout: fixture.function_timeout,
+        include_data_bytes: true,
+        validate_offsets: true,
+        include_imports: true,
+        ..config::Config::default()
+    };
+
+    // Keep the outputs of the pipeline out of the working directory
+    let file_name = env::temp_dir()
+        .join(format!("binary2groundtruth-selftest-{}", fixture.name))
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let (dump, binary) = (fixture.dump, fixture.binary);
+
+    let result = match Object::parse(binary) {
+        Ok(Object::PE(_)) => b2g::pe::PE::from_buffer(dump, binary, config).map(|mut pe| {
+            pe.file_name = file_name;
+            pe.process();
+            Outcome {
+                name: fixture.name,
+                functions: pe.pdb.functions.len(),
+                instructions: pe.instructions.len(),
+                identified: identified(&pe.bytes),
+                failures: Vec::new(),
+            }
+        }),
+        Ok(Object::Elf(_)) => b2g::elf::ELF::from_buffer(dump, binary, config).map(|mut elf| {
+            elf.file_name = file_name;
+            elf.process();
+            Outcome {
+                name: fixture.name,
+                functions: elf.dwarf.functions.len(),
+                instructions: elf.instructions.len(),
+                identified: identified(&elf.bytes),
+                failures: Vec::new(),
+            }
+        }),
+        _ => Err("Could not parse binary!"),
+    };
+
+    let mut outcome = match result {
+        Ok(outcome) => outcome,
+        Err(e) => return Outcome::failed(fixture.name, e.to_string()),
+    };
+
+    if outcome.functions != fixture.functions {
+        outcome.failures.push(format!(
+            "Expected {} functions but found {}.",
+            fixture.functions, outcome.functions
+        ));
+    }
+
+    if outcome.instructions != fixture.instructions {
+        outcome.failures.push(format!(
+            "Expected {} instructions but found {}.",
+            fixture.instructions, outcome.instructions
+        ));
+    }
+
+    if outcome.identified < fixture.min_identified {
+        outcome.failures.push(format!(
+            "Expected at least {:.2}% identified bytes but found {:.2}%.",
+            fixture.min_identified, outcome.identified
+        ));
+    }
+
+    outcome
+}
+
+/// Share of the bytes which are not part of a hole (in percent).
+fn identified(bytes: &[groundtruth::Byte]) -> f64 {
+    // Guard: Nothing to identify
+    if bytes.is_empty() {
+        return 0.0;
+    }
+
+    let unknown: u64 = common::detect_holes(bytes)
+        .iter()
+        .map(|hole| hole.size)
+        .sum();
+
+    100.0 * (bytes.len() as u64 - unknown) as f64 / bytes.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler;
+    use std::fs;
+
+    /// x64 instructions covering the flag derivation: prologue, direct and indirect calls and jumps,
+    /// a conditional branch, one and multi-byte nops, memory reads and writes, interrupts, returns,
+    /// multiplications and exchanges (operand access).
+    const GOLDEN_BUFFER: &[u8] = &[
+        0x55, // push rbp
+        0x48, 0x89, 0xE5, // mov rbp, rsp
+        0xE8, 0x00, 0x00, 0x00, 0x00, // call rel32
+        0x74, 0x02, // je short
+        0x90, // nop
+        0x0F, 0x1F, 0x44, 0x00, 0x00, // nop dword ptr [rax + rax]
+        0x66, 0x0F, 0x1F, 0x84, 0x00, 0x00, 0x00, 0x00, 0x00, // nop word ptr [rax + rax]
+        0xEB, 0x00, // jmp short
+        0x48, 0x89, 0x18, // mov qword ptr [rax], rbx
+        0x48, 0x8B, 0x03, // mov rax, qword ptr [rbx]
+        0x48, 0x01, 0x18, // add qword ptr [rax], rbx
+        0xFF, 0x15, 0x00, 0x00, 0x00, 0x00, // call qword ptr [rip]
+        0xFF, 0xE0, // jmp rax
+        0xCC, // int3
+        0xCD, 0x2D, // int 0x2d
+        0x48, 0xCF, // iretq
+        0x0F, 0xAF, 0xC1, // imul eax, ecx
+        0x6B, 0xC1, 0x05, // imul eax, ecx, 5
+        0x48, 0xF7, 0xE1, // mul rcx
+        0x48, 0x87, 0xD8, // xchg rax, rbx
+        0x48, 0x87, 0x18, // xchg qword ptr [rax], rbx
+        0x48, 0x0F, 0xC1, 0x18, // xadd qword ptr [rax], rbx
+        0x48, 0x0F, 0xB1, 0x18, // cmpxchg qword ptr [rax], rbx
+        0x5D, // pop rbp
+        0xC3, // ret
+    ];
+
+    /// Expected disassembly of `GOLDEN_BUFFER`, regenerated by the `capstone_golden` test.
+    const GOLDEN: &str = include_str!("../fixtures/selftest/capstone.golden");
+
+    /// The PE fixture with a thunk located at `funcB`, which has to be represented by `funcB` alone.
+    const PE_THUNK_FIXTURE: Fixture = Fixture {
+        name: "pe-thunk",
+        dump: include_str!("../fixtures/selftest/pe-thunk.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe.exe"),
+        functions: 2,
+        instructions: 10,
+        min_identified: 75.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// ELF x64 with functions ending in `int3` (`breakpoint`), in a call to `exit` (`fail`) and in
+    /// `hlt` (`_start`). `check` ends in `int3` as well but returns on its other path.
+    const ELF_NORETURN_FIXTURE: Fixture = Fixture {
+        name: "elf-noreturn",
+        dump: include_str!("../fixtures/selftest/noreturn.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/noreturn"),
+        functions: 6,
+        instructions: 29,
+        min_identified: 40.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// The PE fixture with a function reaching past the raw data of `.text` and one in a segment
+    /// without section, both have to be dropped by `--validate-offsets`.
+    const PE_BOUNDS_FIXTURE: Fixture = Fixture {
+        name: "pe-bounds",
+        dump: include_str!("../fixtures/selftest/pe-bounds.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe.exe"),
+        functions: 2,
+        instructions: 10,
+        min_identified: 75.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// The PE fixture with a label in `.pdata` and a data symbol in `.rdata`. Only the data symbol
+    /// lies in a processed section (`--include-data-sections`).
+    const PE_SECTIONS_FIXTURE: Fixture = Fixture {
+        name: "pe-sections",
+        dump: include_str!("../fixtures/selftest/pe-sections.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe.exe"),
+        functions: 2,
+        instructions: 10,
+        min_identified: 75.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// The PE fixture whose `.pdata` raw data starts within `.text` (at 0x300 instead of 0x400), so
+    /// `.text` keeps the shared bytes and `.pdata` starts behind it.
+    const PE_OVERLAP_FIXTURE: Fixture = Fixture {
+        name: "pe-overlap",
+        dump: include_str!("../fixtures/selftest/pe.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe-overlap.exe"),
+        functions: 2,
+        instructions: 10,
+        min_identified: 75.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// The PE fixture with an S_FRAMEPROC record after each procedure, `funcA` uses SEH.
+    const PE_FRAMEPROC_FIXTURE: Fixture = Fixture {
+        name: "pe-frameproc",
+        dump: include_str!("../fixtures/selftest/pe-frameproc.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe.exe"),
+        functions: 2,
+        instructions: 10,
+        min_identified: 75.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// The PE fixture with a delay import (`delay.dll!DelayedFunc`), its load thunk and the tail merge
+    /// helper calling `funcA` as `__delayLoadHelper2`.
+    const PE_DELAY_FIXTURE: Fixture = Fixture {
+        name: "pe-delay",
+        dump: include_str!("../fixtures/selftest/pe.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe-delay.exe"),
+        functions: 2,
+        instructions: 10,
+        min_identified: 65.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// Hand-crafted PE x64 with a switch (`sw`) whose jump table holds offsets relative to the table.
+    const PE_JUMP_RELATIVE_FIXTURE: Fixture = Fixture {
+        name: "pe-jump-relative",
+        dump: include_str!("../fixtures/selftest/pe-jump-relative.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe-jump-relative.exe"),
+        functions: 1,
+        instructions: 18,
+        min_identified: 100.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// The PE fixture with a jump table `swTab` in the middle of `funcA` whose four labels imply
+    /// 16 bytes, which would reach into `funcB`.
+    const PE_JUMP_OVERRUN_FIXTURE: Fixture = Fixture {
+        name: "pe-jump-overrun",
+        dump: include_str!("../fixtures/selftest/pe-jump-overrun.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe.exe"),
+        functions: 2,
+        instructions: 7,
+        min_identified: 75.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// Same switch as [`PE_JUMP_RELATIVE_FIXTURE`] with a jump table of absolute addresses.
+    const PE_JUMP_ABSOLUTE_FIXTURE: Fixture = Fixture {
+        name: "pe-jump-absolute",
+        dump: include_str!("../fixtures/selftest/pe-jump-absolute.pdb.yaml"),
+        binary: include_bytes!("../fixtures/selftest/pe-jump-absolute.exe"),
+        functions: 1,
+        instructions: 18,
+        min_identified: 100.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// ELF x64 with labeled globals in `.data`, built from `fixtures/selftest/data.c`.
+    const ELF_DATA_FIXTURE: Fixture = Fixture {
+        name: "elf-data",
+        dump: include_str!("../fixtures/selftest/data.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/data"),
+        functions: 4,
+        instructions: 26,
+        min_identified: 45.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// ELF x64 whose code section is renamed to `.code`, built from `fixtures/selftest/code.c`.
+    const ELF_CODE_FIXTURE: Fixture = Fixture {
+        name: "elf-code",
+        dump: include_str!("../fixtures/selftest/code.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/code"),
+        functions: 5,
+        instructions: 32,
+        min_identified: 45.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// Big-endian MIPS32 with a call and delay slots, assembled from `fixtures/selftest/mips.s`.
+    const ELF_MIPS_FIXTURE: Fixture = Fixture {
+        name: "elf-mips",
+        dump: include_str!("../fixtures/selftest/mips.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/mips"),
+        functions: 2,
+        instructions: 17,
+        min_identified: 100.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// Thumb-2 with literal pools behind the code, assembled from `fixtures/selftest/thumb.s`.
+    const ELF_THUMB_FIXTURE: Fixture = Fixture {
+        name: "elf-thumb",
+        dump: include_str!("../fixtures/selftest/thumb.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/thumb"),
+        functions: 2,
+        instructions: 11,
+        min_identified: 100.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// Symbol names which are not plain ASCII: UTF-8, an unquoted number and escaped invalid bytes,
+    /// built from `fixtures/selftest/names.c`.
+    const ELF_NAMES_FIXTURE: Fixture = Fixture {
+        name: "elf-names",
+        dump: include_str!("../fixtures/selftest/names.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/names"),
+        functions: 6,
+        instructions: 21,
+        min_identified: 40.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// Itanium, Rust (legacy and v0) and MSVC mangled names, built from `fixtures/selftest/mangled.c`.
+    const ELF_MANGLED_FIXTURE: Fixture = Fixture {
+        name: "elf-mangled",
+        dump: include_str!("../fixtures/selftest/mangled.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/mangled"),
+        functions: 8,
+        instructions: 32,
+        min_identified: 40.0,
+        ..Fixture::DEFAULT
+    };
+
+    /// The ELF fixture without any time for the disassembly, every function is skipped.
+    const ELF_TIMEOUT_FIXTURE: Fixture = Fixture {
+        name: "elf-timeout",
+        dump: include_str!("../fixtures/selftest/elf.obj.yaml"),
+        binary: include_bytes!("../fixtures/selftest/elf"),
+        functions: 5,
+        instructions: 0,
+        min_identified: 0.0,
+        function_timeout: Some(0),
+    };
+
+    /// Disassembles `GOLDEN_BUFFER` and renders the instructions in the format of the golden file.
+    fn disassemble_golden() -> Result<(Vec<groundtruth::Instruction>, String), &'static str> {
+        let architecture = groundtruth::ArchInfo::from(groundtruth::ARCHITECTURE::X64);
+
+        let mut instructions =
+            disassembler::disassemble_capstone(GOLDEN_BUFFER.to_vec(), &architecture, None)?;
+        disassembler::describe_operands(&mut instructions, &architecture);
+
+        let mut lines = vec![
+            "# Disassembly of the selftest GOLDEN_BUFFER (x64, Capstone), one instruction per line:"
+                .to_string(),
+            "# offset, length, mnemonic, operand, flags, operands (tab separated). Regenerate with B2G_UPDATE_GOLDEN=1 cargo test capstone_golden."
+                .to_string(),
+        ];
+        lines.extend(instructions.iter().map(golden_line));
+        let produced = lines.join("\n") + "\n";
+
+        Ok((instructions, produced))
+    }
+
+    /// Tab separated line of the golden file, `-` stands for an empty operand, no flags or no operands.
+    fn golden_line(instruction: &groundtruth::Instruction) -> String {
+        let or_dash = |text: String| {
+            if text.is_empty() {
+                "-".to_string()
+            } else {
+                text
+            }
+        };
+
+        format!(
+            "{:04x}\t{}\t{}\t{}\t{}\t{}",
+            instruction.offset,
+            instruction.length,
+            instruction.mnemonic,
+            or_dash(instruction.operand.clone()),
+            or_dash(
+                instruction
+                    .flags
+                    .iter()
+                    .map(|f| format!("{:?}", f))
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+            or_dash(
+                instruction
+                    .operands
+                    .iter()
+                    .map(golden_operand)
+                    .collect::<Vec<String>>()
+                    .join(",")
+            )
+        )
+    }
+
+    /// Compact form of a detailed operand in the golden file, e.g. `mem:[rax+rbx*1+0x8]:rw`.
+    fn golden_operand(operand: &groundtruth::Operand) -> String {
+        let value = match (&operand.register, operand.immediate, &operand.memory) {
+            (Some(register), _, _) => register.clone(),
+            (_, Some(immediate), _) => format!("{:#x}", immediate),
+            (_, _, Some(memory)) => format!(
+                "{}[{}{}{:+#x}]",
+                memory
+                    .segment
+                    .as_ref()
+                    .map(|s| format!("{}:", s))
+                    .unwrap_or_default(),
+                memory.base.clone().unwrap_or_default(),
+                memory
+                    .index
+                    .as_ref()
+                    .map(|i| format!("+{}*{}", i, memory.scale))
+                    .unwrap_or_default(),
+                memory.displacement
+            ),
+            _ => String::new(),
+        };
+
+        let access = match operand.access {
+            groundtruth::ACCESS::NONE => "-",
+            groundtruth::ACCESS::READ => "r",
+            groundtruth::ACCESS::WRITE => "w",
+            groundtruth::ACCESS::READ_WRITE => "rw",
+        };
+
+        let kind = match operand.kind {
+            groundtruth::OPERAND_KIND::REGISTER => "reg",
+            groundtruth::OPERAND_KIND::IMMEDIATE => "imm",
+            groundtruth::OPERAND_KIND::MEMORY => "mem",
+        };
+
+        format!("{}:{}:{}:{}", kind, value, operand.size, access)
+    }
+
+    /// Processes a fixture like the subcommand and fails with its unmet expectations.
+    fn assert_passes(fixture: &Fixture) {
+        let outcome = run_fixture(fixture);
+
+        assert!(outcome.passed(), "{}: {:?}", outcome.name, outcome.failures);
+    }
+
+    #[test]
+    fn pe_thunk_fixture_passes() {
+        assert_passes(&PE_THUNK_FIXTURE);
+    }
+
+    #[test]
+    fn elf_noreturn_fixture_passes() {
+        assert_passes(&ELF_NORETURN_FIXTURE);
+    }
+
+    #[test]
+    fn pe_bounds_fixture_passes() {
+        assert_passes(&PE_BOUNDS_FIXTURE);
+    }
+
+    #[test]
+    fn pe_sections_fixture_passes() {
+        assert_passes(&PE_SECTIONS_FIXTURE);
+    }
+
+    #[test]
+    fn pe_overlap_fixture_passes() {
+        assert_passes(&PE_OVERLAP_FIXTURE);
+    }
+
+    #[test]
+    fn pe_frameproc_fixture_passes() {
+        assert_passes(&PE_FRAMEPROC_FIXTURE);
+    }
+
+    #[test]
+    fn pe_delay_fixture_passes() {
+        assert_passes(&PE_DELAY_FIXTURE);
+    }
+
+    #[test]
+    fn pe_jump_relative_fixture_passes() {
+        assert_passes(&PE_JUMP_RELATIVE_FIXTURE);
+    }
+
+    #[test]
+    fn pe_jump_overrun_fixture_passes() {
+        assert_passes(&PE_JUMP_OVERRUN_FIXTURE);
+    }
+
+    #[test]
+    fn pe_jump_absolute_fixture_passes() {
+        assert_passes(&PE_JUMP_ABSOLUTE_FIXTURE);
+    }
+
+    #[test]
+    fn elf_data_fixture_passes() {
+        assert_passes(&ELF_DATA_FIXTURE);
+    }
+
+    #[test]
+    fn elf_code_fixture_passes() {
+        assert_passes(&ELF_CODE_FIXTURE);
+    }
+
+    #[test]
+    fn elf_mips_fixture_passes() {
+        assert_passes(&ELF_MIPS_FIXTURE);
+    }
+
+    #[test]
+    fn elf_thumb_fixture_passes() {
+        assert_passes(&ELF_THUMB_FIXTURE);
+    }
+
+    #[test]
+    fn elf_names_fixture_passes() {
+        assert_passes(&ELF_NAMES_FIXTURE);
+    }
+
+    #[test]
+    fn elf_mangled_fixture_passes() {
+        assert_passes(&ELF_MANGLED_FIXTURE);
+    }
+
+    #[test]
+    fn elf_timeout_fixture_passes() {
+        assert_passes(&ELF_TIMEOUT_FIXTURE);
+    }
+
+    /// Compares the disassembly of `GOLDEN_BUFFER` with the golden file like `capstone-golden`.
+    /// After an intended change, `B2G_UPDATE_GOLDEN=1 cargo test capstone_golden` rewrites the
+    /// golden file in the source tree instead.
+    #[test]
+    fn capstone_golden() {
+        let (_, produced) = disassemble_golden().unwrap();
+
+        if env::var("B2G_UPDATE_GOLDEN").is_ok_and(|v| v == "1") {
+            let path = concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/fixtures/selftest/capstone.golden"
+            );
+            fs::write(path, &produced).unwrap();
+        } else {
+            assert_eq!(produced, GOLDEN);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Builders and fixture runners shared by the unit tests.
+
+use std::env;
+
+use crate::b2g;
+use crate::config;
+use crate::groundtruth;
+
+/// Bytes without flags holding `values`, the first one at `base`.
+pub fn bytes(base: u64, values: &[u8]) -> Vec<groundtruth::Byte> {
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| groundtruth::Byte {
+            offset: base + i as u64,
+            value: *value,
+            flags: Vec::new(),
+            provenance: Vec::new(),
+        })
+        .collect()
+}
+
+/// Function without labels, in-line data and frame in segment 1.
+pub fn function(name: &str, offset: u64, size: u64) -> groundtruth::Function {
+    groundtruth::Function {
+        name: name.to_string(),
+        demangled_name: None,
+        offset,
+        segment: 1,
+        size,
+        labels: Vec::new(),
+        data: Vec::new(),
+        frame: None,
+        thumb: false,
+    }
+}
+
+/// Instruction at `address` without flags, its bytes are nops.
+pub fn instruction(mnemonic: &str, address: u64, length: u64) -> groundtruth::Instruction {
+    groundtruth::Instruction {
+        mnemonic: mnemonic.to_string(),
+        operand: String::new(),
+        raw_operand: None,
+        bytes: vec![0x90; length as usize],
+        prefixes: Vec::new(),
+        rex: None,
+        offset: 0,
+        address,
+        length,
+        flags: Vec::new(),
+        source: None,
+        target: None,
+        operands: Vec::new(),
+    }
+}
+
+/// Configuration of the fixture runs: deterministic, with the data sections, the contents of
+/// in-line data and the imports.
+pub fn config() -> config::Config {
+    config::Config {
+        deterministic: true,
+        include_data_sections: true,
+        include_data_bytes: true,
+        include_imports: true,
+        ..config::Config::default()
+    }
+}
+
+/// Path of the outputs of a test in the temporary directory.
+fn file_name(test: &str) -> String {
+    env::temp_dir()
+        .join(format!("binary2groundtruth-test-{}", test))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Processes an embedded PE fixture with its PDB dump, the outputs are named after the test.
+pub fn process_pe(test: &str, dump: &str, binary: &[u8], config: config::Config) -> b2g::pe::PE {
+    let mut pe = b2g::pe::PE::from_buffer(dump, binary, config).unwrap();
+    pe.file_name = file_name(test);
+    pe.process();
+    pe
+}
+
+/// Processes an embedded ELF fixture with its obj2yaml dump, the outputs are named after the
+/// test.
+pub fn process_elf(test: &str, dump: &str, binary: &[u8], config: config::Config) -> b2g::elf::ELF {
+    let mut elf = b2g::elf::ELF::from_buffer(dump, binary, config).unwrap();
+    elf.file_name = file_name(test);
+    elf.process();
+    elf
+}
@@ -0,0 +1,248 @@
+use std::fs;
+
+use goblin::Object;
+
+use crate::config;
+use crate::dumper;
+use crate::elf;
+use crate::groundtruth;
+use crate::pe;
+
+/// Describes different kinds of inconsistencies between a dump and its binary.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum INCONSISTENCY {
+    BYTE_MISMATCH,
+    BYTE_OUTSIDE_BINARY,
+    FUNCTION_OUTSIDE_SECTION,
+    CODE_AND_DATA,
+    BOUNDARY_WITHOUT_CODE,
+}
+
+/// Represents a single inconsistency found while verifying a dump.
+#[derive(Debug, Clone)]
+pub struct Inconsistency {
+    pub offset: u64,
+    pub kind: INCONSISTENCY,
+    pub message: String,
+}
+
+/// Represents the result of verifying a dump against its binary.
+#[derive(Debug, Default)]
+pub struct VerificationReport {
+    pub bytes_checked: u64,
+    pub functions_checked: u64,
+    pub inconsistencies: Vec<Inconsistency>,
+}
+
+impl VerificationReport {
+    pub fn is_consistent(&self) -> bool {
+        self.inconsistencies.is_empty()
+    }
+
+    fn add(&mut self, offset: u64, kind: INCONSISTENCY, message: String) {
+        self.inconsistencies.push(Inconsistency {
+            offset,
+            kind,
+            message,
+        });
+    }
+}
+
+/// Checks that a previously generated dump is still consistent with the given binary.
+///
+/// Byte offsets in the dump are addresses (see `address_space`) while function and in-line data
+/// offsets are relative to the first byte of the dump.
+pub fn verify_dump(
+    dump: &dumper::Dump,
+    binary_path: &str,
+) -> Result<VerificationReport, &'static str> {
+    // Function offsets are relative to the first byte of the complete byte list
+    if dump.output_kind != config::OUTPUT_KIND::ALL {
+        return Err("Only dumps written with --output-kind all can be verified!");
+    }
+
+    let buffer = match fs::read(binary_path) {
+        Ok(buffer) => buffer,
+        Err(_e) => {
+            return Err("Could not read file!");
+        }
+    };
+
+    let sections = match Object::parse(&buffer) {
+        Ok(Object::Elf(_)) => elf::parse_sections_from_buffer(&buffer)?,
+        Ok(Object::PE(_)) => pe::parse_sections_from_buffer(&buffer)?,
+        Ok(_) => {
+            return Err("Binary not supported. Only PE and ELF binaries are supported.");
+        }
+        Err(_e) => {
+            return Err("Could not parse binary!");
+        }
+    };
+
+    // Sections which are not mapped (e.g. ELF debug sections) share va 0 and are skipped
+    let sections: Vec<&groundtruth::Section> = sections.iter().filter(|s| s.va != 0).collect();
+
+    let mut report = VerificationReport::default();
+
+    // Sections use RVAs (PE) or virtual addresses (ELF) like the byte vector of the pipeline
+    let resolve = |address: u64| dump.address_space.resolve(address);
+
+    let base = match dump.bytes.first() {
+        Some(byte) => resolve(byte.offset),
+        None => return Ok(report),
+    };
+
+    // Byte values have to match the binary
+    for byte in &dump.bytes {
+        report.bytes_checked += 1;
+
+        let address = resolve(byte.offset);
+
+        let file_offset = match find_section(&sections, address, 1) {
+            Some(section) => address - section.va + section.raw_data_offset,
+            None => {
+                report.add(
+                    byte.offset,
+                    INCONSISTENCY::BYTE_OUTSIDE_BINARY,
+                    format!("Byte 0x{:x} lies outside of all sections.", byte.offset),
+                );
+                continue;
+            }
+        };
+
+        match buffer.get(file_offset as usize) {
+            Some(value) if *value == byte.value => {}
+            Some(value) => report.add(
+                byte.offset,
+                INCONSISTENCY::BYTE_MISMATCH,
+                format!(
+                    "Byte 0x{:x} (file offset 0x{:x}) is 0x{:02x} but binary has 0x{:02x}.",
+                    byte.offset, file_offset, byte.value, value
+                ),
+            ),
+            None => report.add(
+                byte.offset,
+                INCONSISTENCY::BYTE_OUTSIDE_BINARY,
+                format!(
+                    "Byte 0x{:x} (file offset 0x{:x}) lies outside of the binary.",
+                    byte.offset, file_offset
+                ),
+            ),
+        }
+    }
+
+    // Functions have to lie completely within a single section
+    for function in &dump.functions {
+        report.functions_checked += 1;
+
+        let address = base + function.offset;
+
+        if find_section(&sections, address, function.size).is_none() {
+            report.add(
+                address,
+                INCONSISTENCY::FUNCTION_OUTSIDE_SECTION,
+                format!(
+                    "Function {} (0x{:x}-0x{:x}) does not lie within a section.",
+                    function.name,
+                    address,
+                    address + function.size
+                ),
+            );
+        }
+    }
+
+    // A byte may only be code and data at the same time if it is in-line data of a function
+    for byte in dump.bytes.iter().filter(|b| b.is_code() && b.is_data()) {
+        let justified = dump.functions.iter().flat_map(|f| &f.data).any(|data| {
+            let start = base + data.offset;
+            resolve(byte.offset) >= start && resolve(byte.offset) < start + data.size
+        });
+
+        if !justified {
+            report.add(
+                byte.offset,
+                INCONSISTENCY::CODE_AND_DATA,
+                format!(
+                    "Byte 0x{:x} is flagged as code and data but is not in-line data.",
+                    byte.offset
+                ),
+            );
+        }
+    }
+
+    // Function boundaries have to be code
+    for byte in dump
+        .bytes
+        .iter()
+        .filter(|b| (b.is_function_start() || b.is_function_end()) && !b.is_code())
+    {
+        report.add(
+            byte.offset,
+            INCONSISTENCY::BOUNDARY_WITHOUT_CODE,
+            format!(
+                "Byte 0x{:x} is a function boundary but not code.",
+                byte.offset
+            ),
+        );
+    }
+
+    Ok(report)
+}
+
+fn find_section<'a>(
+    sections: &[&'a groundtruth::Section],
+    address: u64,
+    size: u64,
+) -> Option<&'a groundtruth::Section> {
+    sections
+        .iter()
+        .find(|s| address >= s.va && address + size <= s.va + s.raw_data_size)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    const DUMP: &str = include_str!("../fixtures/selftest/pe.pdb.yaml");
+    const BINARY: &[u8] = include_bytes!("../fixtures/selftest/pe.exe");
+    const BINARY_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/selftest/pe.exe");
+
+    fn kinds(report: &VerificationReport) -> Vec<INCONSISTENCY> {
+        report
+            .inconsistencies
+            .iter()
+            .map(|i| i.kind.clone())
+            .collect()
+    }
+
+    #[test]
+    fn dump_of_the_binary_is_consistent() {
+        let pe = testing::process_pe("verify", DUMP, BINARY, testing::config());
+        let dump = dumper::create_dump_pe(&pe);
+
+        let report = verify_dump(&dump, BINARY_PATH).unwrap();
+
+        assert!(report.is_consistent(), "{:?}", kinds(&report));
+        assert_eq!(report.functions_checked, 2);
+    }
+
+    #[test]
+    fn function_boundary_without_code_is_reported() {
+        let pe = testing::process_pe("verify-boundary", DUMP, BINARY, testing::config());
+        let mut dump = dumper::create_dump_pe(&pe);
+
+        let start = dump
+            .bytes
+            .iter_mut()
+            .find(|b| b.is_function_start())
+            .unwrap();
+        start.flags = vec![groundtruth::FLAG::FUNCTION_START];
+
+        let report = verify_dump(&dump, BINARY_PATH).unwrap();
+
+        assert_eq!(kinds(&report), vec![INCONSISTENCY::BOUNDARY_WITHOUT_CODE]);
+    }
+}